@@ -1,22 +1,49 @@
-QUERY CreateAsset(kind: String, path: String, content_hash: String) =>
+QUERY CreateAsset(kind: String, path: String, extension: String, content_hash: String, modified_at: I64, size_bytes: I64, indexed_at: I64) =>
     existing <- N<Asset>::WHERE(_::{content_hash}::EQ(content_hash))
     asset <- existing::UpsertN({
         kind: kind,
         content_hash: content_hash,
-        path: path
+        path: path,
+        extension: extension,
+        modified_at: modified_at,
+        size_bytes: size_bytes,
+        indexed_at: indexed_at
     })
     RETURN asset
 
+QUERY ListAssets() =>
+    assets <- N<Asset>
+    RETURN assets
+
+QUERY UpdateAssetLocation(content_hash: String, path: String, extension: String, modified_at: I64, size_bytes: I64) =>
+    asset <- N<Asset>({content_hash: content_hash})::UPDATE({
+        path: path,
+        extension: extension,
+        modified_at: modified_at,
+        size_bytes: size_bytes
+    })
+    RETURN asset
+
+QUERY DeleteAssetByHash(content_hash: String) =>
+    DROP N<Asset>({content_hash: content_hash})::Out<HasAssetEmbedding>
+    DROP N<Asset>({content_hash: content_hash})
+    RETURN "deleted"
+
 QUERY GetAssetByHash(content_hash: String) =>
     asset <- N<Asset>({content_hash: content_hash})
     RETURN asset
 
+// Several hashes can briefly share a path while a file is being re-indexed.
+QUERY GetAssetsByPath(path: String) =>
+    assets <- N<Asset>::WHERE(_::{path}::EQ(path))
+    RETURN assets
+
 QUERY GetAssetEmbeddingsByHash(content_hash: String) =>
     asset <- N<Asset>({content_hash: content_hash})
     embeddings <- asset::Out<HasAssetEmbedding>
     RETURN embeddings
 
-QUERY CreateAssetEmbeddingByHash(content_hash: String, unit_kind: String, unit_key: String, content: String,vector: [F64], created_at: Date) =>
+QUERY CreateAssetEmbeddingByHash(content_hash: String, asset_kind: String, unit_kind: String, unit_key: String, content: String,vector: [F64], created_at: Date) =>
     asset <- N<Asset>({content_hash: content_hash})
     existing_embedding <- asset::Out<HasAssetEmbedding>
         ::WHERE(_::{unit_kind}::EQ(unit_kind))
@@ -24,16 +51,41 @@ QUERY CreateAssetEmbeddingByHash(content_hash: String, unit_kind: String, unit_k
     embedding <- existing_embedding::UpsertV(vector, { // this embed needs to leave, pass vectors directly as content
         unit_kind: unit_kind,
         unit_key: unit_key,
-        content: content
+        content: content,
+        asset_kind: asset_kind
     })
     existing_edge <- E<HasAssetEmbedding>
     has_embedding <- existing_edge::UpsertE({created_at: created_at})::From(asset)::To(embedding)
     RETURN embedding
 
+// embeddings come back nearest-first, each carrying its cosine distance to
+// the query vector; the sidecar turns that into the result score.
 QUERY SearchAssetEmbeddings(vector: [F64]) =>
     embeddings <- SearchV<AssetEmbedding>(vector, 50) // this embed needs to leave, pass vectors directly as query
     assets <- embeddings::In<HasAssetEmbedding>
-    RETURN assets
+    RETURN assets, embeddings
+
+// Same as SearchAssetEmbeddings with a caller-chosen k. Several embeddings
+// usually share an asset, so the sidecar picks k and re-queries with a
+// larger one until it has enough unique (and filter-passing) assets.
+QUERY SearchAssetEmbeddingsTopK(vector: [F64], k: I64) =>
+    embeddings <- SearchV<AssetEmbedding>(vector, k) // this embed needs to leave, pass vectors directly as query
+    assets <- embeddings::In<HasAssetEmbedding>
+    RETURN assets, embeddings
+
+// Like SearchAssetEmbeddingsTopK, but the k nearest are taken among the
+// embeddings of one asset kind only, so a kind filter is not crowded out by
+// nearer embeddings of other kinds.
+QUERY SearchAssetEmbeddingsOfKindTopK(vector: [F64], k: I64, asset_kind: String) =>
+    embeddings <- SearchV<AssetEmbedding>(vector, k)::PREFILTER(_::{asset_kind}::EQ(asset_kind))
+    assets <- embeddings::In<HasAssetEmbedding>
+    RETURN assets, embeddings
+
+// The same, among the embeddings of one unit kind.
+QUERY SearchAssetEmbeddingsOfUnitKindTopK(vector: [F64], k: I64, unit_kind: String) =>
+    embeddings <- SearchV<AssetEmbedding>(vector, k)::PREFILTER(_::{unit_kind}::EQ(unit_kind))
+    assets <- embeddings::In<HasAssetEmbedding>
+    RETURN assets, embeddings
 
 QUERY ClearSearchIndex() =>
     DROP N<Asset>::Out<HasAssetEmbedding>
@@ -2,12 +2,21 @@ N::Asset {
     INDEX content_hash: String,
     kind: String,
     path: String,
+    // lowercased with the leading dot, e.g. ".rs"; empty when there is none
+    extension: String DEFAULT "",
+    modified_at: I64,
+    size_bytes: I64,
+    // unix seconds; 0 for assets indexed before this was recorded
+    indexed_at: I64 DEFAULT 0,
 }
 
 V::AssetEmbedding{
     unit_key: String,
     unit_kind: String,
     content: String,
+    // kind of the owning asset, copied so searches can prefilter on it;
+    // empty for embeddings stored before it was recorded
+    asset_kind: String DEFAULT "",
 }
 
 E::HasAssetEmbedding {
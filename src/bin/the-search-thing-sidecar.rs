@@ -2,15 +2,35 @@ use serde_json::json;
 use serde_json::Value;
 use std::io;
 use std::io::BufRead;
-use std::io::Write;
+use tokio::task::JoinSet;
 
+use sidecar::output::write_response;
 use sidecar::protocol::err_response;
 use sidecar::protocol::JsonRpcError;
 use sidecar::protocol::JsonRpcRequest;
 use sidecar::protocol::JsonRpcResponse;
+use sidecar::runtime::{acquire_family_permit, shared_runtime};
 use the_search_thing::sidecar;
 
-fn handle_request(request: JsonRpcRequest) -> JsonRpcResponse {
+/// Runs a synchronous handler that may touch the filesystem or block on locks
+/// off the async workers.
+async fn run_blocking(
+    request: JsonRpcRequest,
+    handler: fn(&JsonRpcRequest) -> JsonRpcResponse,
+) -> JsonRpcResponse {
+    let id = request.id.clone();
+    match tokio::task::spawn_blocking(move || handler(&request)).await {
+        Ok(response) => response,
+        Err(error) => err_response(
+            id,
+            -32603,
+            "Internal error",
+            Some(json!({ "reason": error.to_string() })),
+        ),
+    }
+}
+
+async fn handle_request(request: JsonRpcRequest) -> JsonRpcResponse {
     if request.jsonrpc != "2.0" {
         return err_response(request.id, -32600, "Invalid Request", None);
     }
@@ -18,12 +38,29 @@ fn handle_request(request: JsonRpcRequest) -> JsonRpcResponse {
     match request.method.as_str() {
         "health.ping" => sidecar::rpc::health::handle(request.id),
         "fs.walkTextBatch" | "fs.walk_text_batch" => {
-            sidecar::rpc::fs::handle_walk_text_batch(&request)
+            run_blocking(request, sidecar::rpc::fs::handle_walk_text_batch).await
+        }
+        "index.start" => run_blocking(request, sidecar::rpc::index::handle_start).await,
+        "index.status" => run_blocking(request, sidecar::rpc::index::handle_status).await,
+        "index.list" => run_blocking(request, sidecar::rpc::index::handle_list).await,
+        "index.errors" => run_blocking(request, sidecar::rpc::index::handle_errors).await,
+        "index.cancel" => run_blocking(request, sidecar::rpc::index::handle_cancel).await,
+        "index.pause" => run_blocking(request, sidecar::rpc::index::handle_pause).await,
+        "index.resume" => run_blocking(request, sidecar::rpc::index::handle_resume).await,
+        "index.clear" => sidecar::rpc::index::handle_clear(&request).await,
+        "search.query" => sidecar::rpc::search::handle_query(&request).await,
+        "search.similar" => sidecar::rpc::search::handle_similar(&request).await,
+        "search.byImage" => sidecar::rpc::search::handle_by_image(&request).await,
+        "search.cacheStats" => sidecar::rpc::search::handle_cache_stats(&request),
+        "modelCache.stats" => {
+            run_blocking(request, sidecar::rpc::indexing::model_cache::handle_stats).await
+        }
+        "modelCache.purge" => {
+            run_blocking(request, sidecar::rpc::indexing::model_cache::handle_purge).await
         }
-        "index.start" => sidecar::rpc::index::handle_start(&request),
-        "index.status" => sidecar::rpc::index::handle_status(&request),
-        "index.clear" => sidecar::rpc::index::handle_clear(&request),
-        "search.query" => sidecar::rpc::search::handle_query(&request),
+        "watch.add" => run_blocking(request, sidecar::rpc::watch::handle_add).await,
+        "watch.remove" => run_blocking(request, sidecar::rpc::watch::handle_remove).await,
+        "watch.list" => run_blocking(request, sidecar::rpc::watch::handle_list).await,
         _ => err_response(
             request.id,
             -32601,
@@ -33,18 +70,22 @@ fn handle_request(request: JsonRpcRequest) -> JsonRpcResponse {
     }
 }
 
-fn write_response(stdout: &mut io::StdoutLock<'_>, response: JsonRpcResponse) -> io::Result<()> {
-    let serialized = serde_json::to_string(&response).map_err(io::Error::other)?;
-    stdout.write_all(serialized.as_bytes())?;
-    stdout.write_all(b"\n")?;
-    stdout.flush()
+/// Handles one request under its method family's concurrency limit. Responses
+/// go out as soon as they are ready, so clients correlate them by `id`.
+async fn dispatch(request: JsonRpcRequest) {
+    let _permit = acquire_family_permit(&request.method).await;
+    let response = handle_request(request).await;
+    if let Err(error) = write_response(&response) {
+        eprintln!("[sidecar] failed to write response: {}", error);
+    }
 }
 
 fn main() {
     dotenv::dotenv().ok();
 
+    let runtime = shared_runtime();
+    let mut in_flight = JoinSet::new();
     let stdin = io::stdin();
-    let mut stdout = io::stdout().lock();
 
     for line_result in stdin.lock().lines() {
         let line = match line_result {
@@ -52,27 +93,37 @@ fn main() {
             Err(_) => break,
         };
 
+        // Reap finished requests so the set doesn't grow for the whole session.
+        while in_flight.try_join_next().is_some() {}
+
         let trimmed = line.trim();
         if trimmed.is_empty() {
             continue;
         }
 
-        let response = match serde_json::from_str::<JsonRpcRequest>(trimmed) {
-            Ok(request) => handle_request(request),
-            Err(error) => JsonRpcResponse {
-                jsonrpc: "2.0",
-                id: Value::Null,
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32700,
-                    message: "Parse error".to_string(),
-                    data: Some(json!({ "reason": error.to_string() })),
-                }),
-            },
-        };
-
-        if write_response(&mut stdout, response).is_err() {
-            break;
+        match serde_json::from_str::<JsonRpcRequest>(trimmed) {
+            Ok(request) => {
+                in_flight.spawn_on(dispatch(request), runtime.handle());
+            }
+            Err(error) => {
+                let response = JsonRpcResponse {
+                    jsonrpc: "2.0",
+                    id: Value::Null,
+                    result: None,
+                    error: Some(JsonRpcError {
+                        code: -32700,
+                        message: "Parse error".to_string(),
+                        data: Some(json!({ "reason": error.to_string() })),
+                    }),
+                };
+                if write_response(&response).is_err() {
+                    break;
+                }
+            }
         }
     }
+
+    // stdin closed: let in-flight requests finish writing their responses.
+    runtime.block_on(async { while in_flight.join_next().await.is_some() {} });
+    sidecar::rpc::search::flush_caches();
 }
@@ -0,0 +1,36 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory for state that has to survive sidecar restarts. Configurable via
+/// `SIDECAR_DATA_DIR`; defaults to `sidecar_data` under the working directory.
+pub fn data_dir() -> PathBuf {
+    env::var("SIDECAR_DATA_DIR")
+        .ok()
+        .map(|raw| raw.trim().to_string())
+        .filter(|raw| !raw.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join("sidecar_data")
+        })
+}
+
+pub fn data_file(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+/// Writes through a temporary sibling and renames it into place, so a crash
+/// mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    fs::write(&tmp, bytes).map_err(|e| format!("failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("failed to replace {}: {}", path.display(), e))
+}
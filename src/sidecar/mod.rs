@@ -1,2 +1,5 @@
+pub mod data;
+pub mod output;
 pub mod protocol;
 pub mod rpc;
+pub mod runtime;
@@ -0,0 +1,35 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::io;
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::sidecar::protocol::{notification, JsonRpcResponse};
+
+// Responses and notifications come from different threads; every message is
+// written under this lock so lines never interleave on stdout.
+static STDOUT_LOCK: Mutex<()> = Mutex::new(());
+
+fn write_line<T: Serialize>(message: &T) -> io::Result<()> {
+    let serialized = serde_json::to_string(message).map_err(io::Error::other)?;
+    let _guard = STDOUT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(serialized.as_bytes())?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()
+}
+
+pub fn write_response(response: &JsonRpcResponse) -> io::Result<()> {
+    write_line(response)
+}
+
+/// Pushes a JSON-RPC notification (no `id`) to the client. Failures are only
+/// logged: a client that stopped reading will also stop the main loop.
+pub fn emit_notification(method: &str, params: Value) {
+    if let Err(error) = write_line(&notification(method, params)) {
+        eprintln!(
+            "[sidecar:output] failed to write {} notification: {}",
+            method, error
+        );
+    }
+}
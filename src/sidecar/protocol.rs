@@ -72,3 +72,18 @@ pub fn parse_params<T: for<'de> Deserialize<'de>>(
         )
     })
 }
+
+#[derive(Debug, Serialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: &'static str,
+    pub method: String,
+    pub params: Value,
+}
+
+pub fn notification(method: &str, params: Value) -> JsonRpcNotification {
+    JsonRpcNotification {
+        jsonrpc: "2.0",
+        method: method.to_string(),
+        params,
+    }
+}
@@ -1,14 +1,15 @@
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::json;
-use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
-use walkdir::WalkDir;
+use std::path::Path;
 
 use crate::sidecar::protocol::{
     err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
 };
+use crate::sidecar::rpc::indexing::collect::{extension_of, normalize_extension};
+use crate::sidecar::rpc::indexing::ignore::IgnoreRules;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +20,8 @@ struct WalkTextBatchParams {
     ignore_exts: Vec<String>,
     #[serde(default)]
     ignore_files: Vec<String>,
+    #[serde(default)]
+    ignore_patterns: Vec<String>,
     cursor: usize,
     batch_size: usize,
 }
@@ -33,73 +36,33 @@ struct WalkTextBatchResult {
     skipped_count: usize,
 }
 
-fn normalize_extensions(values: Vec<String>) -> HashSet<String> {
-    values
-        .into_iter()
-        .map(|ext| {
-            let mut normalized = ext.trim().to_lowercase();
-            if !normalized.is_empty() && !normalized.starts_with('.') {
-                normalized = format!(".{}", normalized);
-            }
-            normalized
-        })
-        .filter(|ext| !ext.is_empty())
-        .collect()
-}
-
-fn normalize_file_names(values: Vec<String>) -> HashSet<String> {
-    values
-        .into_iter()
-        .map(|name| name.trim().to_lowercase())
-        .filter(|name| !name.is_empty())
-        .collect()
-}
-
 fn walk_text_batch(params: WalkTextBatchParams) -> Result<WalkTextBatchResult, String> {
-    let text_exts = normalize_extensions(params.text_exts);
-    let ignore_exts = normalize_extensions(params.ignore_exts);
-    let ignore_files = normalize_file_names(params.ignore_files);
+    let text_exts: HashSet<String> = params
+        .text_exts
+        .iter()
+        .map(|ext| normalize_extension(ext))
+        .filter(|ext| !ext.is_empty())
+        .collect();
+    let ignore = IgnoreRules::load().with_extra(
+        params.ignore_exts,
+        params.ignore_files,
+        params.ignore_patterns,
+    );
 
     let mut all_entries: Vec<(String, String)> = Vec::new();
     let mut scanned_count = 0usize;
-    let mut skipped_count = 0usize;
-
-    for entry in WalkDir::new(&params.dir).into_iter().flatten() {
-        let path = entry.path();
+    let walk = ignore.walk(Path::new(&params.dir));
+    let mut skipped_count = walk.ignored;
 
-        if !path.is_file() {
+    for path in walk.files {
+        let ext = extension_of(&path);
+        if !text_exts.contains(&ext) {
+            skipped_count += 1;
             continue;
         }
-
-        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-            if ignore_files.contains(&name.to_lowercase()) {
-                skipped_count += 1;
-                continue;
-            }
-        }
-
-        let ext = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| format!(".{}", s.to_lowercase()));
-
-        if let Some(ref extension) = ext {
-            if ignore_exts.contains(extension) {
-                skipped_count += 1;
-                continue;
-            }
-        }
-
-        match ext {
-            Some(ref extension) if text_exts.contains(extension) => {
-                if let Ok(content) = fs::read_to_string(path) {
-                    all_entries.push((path.to_string_lossy().to_string(), content));
-                    scanned_count += 1;
-                }
-            }
-            _ => {
-                skipped_count += 1;
-            }
+        if let Ok(content) = fs::read_to_string(&path) {
+            all_entries.push((path.to_string_lossy().to_string(), content));
+            scanned_count += 1;
         }
     }
 
@@ -142,25 +105,37 @@ pub fn handle_walk_text_batch(request: &JsonRpcRequest) -> JsonRpcResponse {
     }
 }
 
+pub const FILE_TOO_LARGE: &str = "File exceeds text size limit";
+pub const BINARY_CONTENT: &str = "Binary content";
+
+const DEFAULT_TEXT_MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+pub fn text_max_file_bytes() -> u64 {
+    std::env::var("SIDECAR_TEXT_MAX_FILE_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_TEXT_MAX_FILE_BYTES)
+}
+
 pub fn get_file_contents(file_path: String) -> Result<String, String> {
     let contents = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
     Ok(contents)
 }
 
-pub fn walk_and_get_files_content(dir: String) -> Result<HashMap<String, String>, String> {
-    let mut files_content: HashMap<String, String> = HashMap::new();
-    for entry in WalkDir::new(&dir) {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = entry.path().to_string_lossy().to_string();
-
-        if entry.path().is_file() {
-            match get_file_contents(path.clone()) {
-                Ok(content) => {
-                    files_content.insert(path, content);
-                }
-                Err(_) => continue,
-            }
-        }
+/// Reads a file as UTF-8 text, rejecting it with `FILE_TOO_LARGE` or
+/// `BINARY_CONTENT` so callers can report those as skips rather than failures.
+pub fn read_text_file(file_path: &str, max_bytes: u64) -> Result<String, String> {
+    let metadata = fs::metadata(file_path).map_err(|e| e.to_string())?;
+    if metadata.len() > max_bytes {
+        return Err(FILE_TOO_LARGE.to_string());
     }
-    Ok(files_content)
+
+    let bytes = fs::read(file_path).map_err(|e| e.to_string())?;
+    let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if sniff.contains(&0) {
+        return Err(BINARY_CONTENT.to_string());
+    }
+
+    String::from_utf8(bytes).map_err(|_| BINARY_CONTENT.to_string())
 }
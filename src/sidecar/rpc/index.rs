@@ -2,30 +2,47 @@ use serde::Deserialize;
 use serde::Serialize;
 use serde_json::json;
 use std::collections::HashMap;
-use std::env;
 use std::fs;
-use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::{SystemTime, UNIX_EPOCH};
-use walkdir::WalkDir;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use crate::sidecar::data::{data_file, write_atomic};
+use crate::sidecar::output::emit_notification;
 use crate::sidecar::protocol::{
     err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
 };
 use crate::sidecar::rpc::indexing::adapters::groq::GroqClient;
-use crate::sidecar::rpc::indexing::adapters::hash::{PathHasher, Sha256PathHasher};
+use crate::sidecar::rpc::indexing::adapters::hash::{
+    CachedPathHasher, PathHasher, Sha256PathHasher,
+};
 use crate::sidecar::rpc::indexing::adapters::helix::HelixTextStore;
+use crate::sidecar::rpc::indexing::adapters::http_policy::{self, ProviderOutage};
+use crate::sidecar::rpc::indexing::collect::{collect_files, FileTypeConfig};
+use crate::sidecar::rpc::indexing::control::{JobControl, JOB_CANCELLED};
+use crate::sidecar::rpc::indexing::ignore::IgnoreRules;
 use crate::sidecar::rpc::indexing::image::image_indexer_with_sidecar;
-use crate::sidecar::rpc::indexing::text::file_indexer;
-use crate::sidecar::rpc::indexing::video::index_video_with_sidecar;
+use crate::sidecar::rpc::indexing::issues::{self, FileIssue};
+use crate::sidecar::rpc::indexing::lexical;
+use crate::sidecar::rpc::indexing::reconcile::{
+    drop_partial_assets, normalize_root, reconcile, KindPlan, ReconcileReport,
+};
+use crate::sidecar::rpc::indexing::text::{file_indexer, DUPLICATE_CONTENT_HASH};
+use crate::sidecar::rpc::indexing::video::{
+    default_output_dir, index_video_with_sidecar, DEFAULT_CHUNK_DURATION_SECS,
+};
+use crate::sidecar::runtime::shared_runtime;
 
 #[derive(Debug, Deserialize)]
 struct IndexStartParams {
     dir: String,
     #[serde(default)]
     batch_size: usize,
+    /// Opt in to `index.progress`/`index.fileIndexed`/`index.fileFailed`/
+    /// `index.finished` notifications for this job.
+    #[serde(default)]
+    notify: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,7 +50,35 @@ struct IndexStatusParams {
     job_id: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Deserialize)]
+struct IndexControlParams {
+    job_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexListParams {
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexErrorsParams {
+    job_id: String,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+    /// `failed` or `skipped`; both when omitted.
+    #[serde(default)]
+    outcome: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 struct IndexJobStatus {
     job_id: String,
     dir: String,
@@ -52,6 +97,16 @@ struct IndexJobStatus {
     image_indexed: usize,
     image_errors: usize,
     image_skipped: usize,
+    files_unchanged: usize,
+    files_moved: usize,
+    assets_removed: usize,
+    /// Set on jobs cut short by a sidecar restart; `index.resume` restarts them.
+    resumable: bool,
+    /// Whether the client asked for progress notifications for this job.
+    notify: bool,
+    /// Model providers failing fast behind an open circuit breaker, as of
+    /// the job's last update.
+    provider_outages: Vec<ProviderOutage>,
     message: String,
     error: String,
     started_at: String,
@@ -59,8 +114,31 @@ struct IndexJobStatus {
     finished_at: Option<String>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PersistedJobs {
+    jobs: Vec<IndexJobStatus>,
+}
+
+const JOBS_FILE: &str = "jobs.json";
+const DEFAULT_JOB_HISTORY_LIMIT: usize = 200;
+const DEFAULT_LIST_LIMIT: usize = 50;
+const MAX_LIST_LIMIT: usize = 500;
+/// Files handed to one indexer call, so their units can share embedding
+/// requests. Images stay smaller because each waits on a vision call.
+const TEXT_FILES_PER_CALL: usize = 32;
+const IMAGE_FILES_PER_CALL: usize = 8;
+/// Progress-only updates are written at most this often; status changes are
+/// always written immediately.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
 static JOB_COUNTER: AtomicU64 = AtomicU64::new(1);
+static LAST_PERSIST: Mutex<Option<Instant>> = Mutex::new(None);
 static JOB_STORE: OnceLock<Mutex<HashMap<String, IndexJobStatus>>> = OnceLock::new();
+/// Cancel/pause handles for jobs whose thread is still alive.
+static JOB_CONTROLS: OnceLock<Mutex<HashMap<String, Arc<JobControl>>>> = OnceLock::new();
+
+/// How long `index.clear` with `cancel_running` waits for jobs to stop.
+const CLEAR_CANCEL_TIMEOUT: Duration = Duration::from_secs(30);
 
 fn now_string() -> String {
     SystemTime::now()
@@ -70,182 +148,136 @@ fn now_string() -> String {
         .to_string()
 }
 
-fn normalize_extension(ext: &str) -> String {
-    let ext = ext.trim().to_lowercase();
-    if ext.is_empty() {
-        return ext;
-    }
-    if ext.starts_with('.') {
-        ext
-    } else {
-        format!(".{}", ext)
-    }
+fn store() -> &'static Mutex<HashMap<String, IndexJobStatus>> {
+    JOB_STORE.get_or_init(|| Mutex::new(load_jobs()))
 }
 
-fn load_video_extensions() -> Vec<String> {
-    let path = Path::new("config/file_types.json");
-    let Ok(raw) = fs::read_to_string(path) else {
-        return vec![".mp4".to_string(), ".mov".to_string()];
-    };
-    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&raw) else {
-        return vec![".mp4".to_string(), ".mov".to_string()];
-    };
+fn job_history_limit() -> usize {
+    std::env::var("SIDECAR_JOB_HISTORY_LIMIT")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_JOB_HISTORY_LIMIT)
+}
 
-    parsed
-        .get("video")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str())
-                .map(normalize_extension)
-                .filter(|v| !v.is_empty())
-                .collect::<Vec<String>>()
-        })
-        .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| vec![".mp4".to_string(), ".mov".to_string()])
-}
-
-fn load_image_extensions() -> Vec<String> {
-    let path = Path::new("config/file_types.json");
-    let Ok(raw) = fs::read_to_string(path) else {
-        return vec![
-            ".jpeg".to_string(),
-            ".jpg".to_string(),
-            ".png".to_string(),
-            ".webp".to_string(),
-        ];
-    };
-    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&raw) else {
-        return vec![
-            ".jpeg".to_string(),
-            ".jpg".to_string(),
-            ".png".to_string(),
-            ".webp".to_string(),
-        ];
-    };
+fn is_live_status(status: &str) -> bool {
+    status == "running" || status == "paused"
+}
 
-    parsed
-        .get("image")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str())
-                .map(normalize_extension)
-                .filter(|v| !v.is_empty())
-                .collect::<Vec<String>>()
-        })
-        .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| {
-            vec![
-                ".jpeg".to_string(),
-                ".jpg".to_string(),
-                ".png".to_string(),
-                ".webp".to_string(),
-            ]
-        })
+fn newest_first(jobs: &mut [&IndexJobStatus]) {
+    jobs.sort_by(|a, b| {
+        let started = |job: &IndexJobStatus| job.started_at.parse::<u64>().unwrap_or(0);
+        started(b)
+            .cmp(&started(a))
+            .then_with(|| b.job_id.cmp(&a.job_id))
+    });
 }
 
-fn load_ignore_config() -> (Vec<String>, Vec<String>) {
-    let path = Path::new("config/ignore.json");
-    let Ok(raw) = fs::read_to_string(path) else {
-        return (Vec::new(), Vec::new());
+/// Loads the job history written by a previous sidecar process. Jobs that were
+/// still running or paused when it exited are marked `interrupted`.
+fn load_jobs() -> HashMap<String, IndexJobStatus> {
+    let path = data_file(JOBS_FILE);
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(_) => return HashMap::new(),
     };
-    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&raw) else {
-        return (Vec::new(), Vec::new());
+    let persisted: PersistedJobs = match serde_json::from_str(&raw) {
+        Ok(persisted) => persisted,
+        Err(error) => {
+            eprintln!(
+                "[sidecar:index] ignoring unreadable job history {}: {}",
+                path.display(),
+                error
+            );
+            return HashMap::new();
+        }
     };
 
-    let ignore_exts = parsed
-        .get("ignore_extensions")
-        .or_else(|| parsed.get("ignore"))
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str())
-                .map(normalize_extension)
-                .filter(|v| !v.is_empty())
-                .collect::<Vec<String>>()
-        })
-        .unwrap_or_default();
-
-    let ignore_files = parsed
-        .get("ignore_files")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str())
-                .map(|s| s.trim().to_lowercase())
-                .filter(|s| !s.is_empty())
-                .collect::<Vec<String>>()
+    let mut interrupted = 0usize;
+    let jobs: HashMap<String, IndexJobStatus> = persisted
+        .jobs
+        .into_iter()
+        .map(|mut job| {
+            if is_live_status(&job.status) {
+                job.status = "interrupted".to_string();
+                job.resumable = true;
+                job.message =
+                    "Interrupted by a sidecar restart; call index.resume to continue".to_string();
+                interrupted += 1;
+            }
+            (job.job_id.clone(), job)
         })
-        .unwrap_or_default();
-
-    (ignore_exts, ignore_files)
-}
-
-fn collect_video_files_with_ignore(
-    root: &str,
-    video_exts: &[String],
-    ignore_exts: &[String],
-    ignore_files: &[String],
-) -> Vec<String> {
-    let ext_set: std::collections::HashSet<String> = video_exts.iter().cloned().collect();
-    let ignore_ext_set: std::collections::HashSet<String> = ignore_exts.iter().cloned().collect();
-    let ignore_file_set: std::collections::HashSet<String> = ignore_files.iter().cloned().collect();
-
-    if Path::new(root).is_file() {
-        let base_name = Path::new(root)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or_default()
-            .to_lowercase();
-        if ignore_file_set.contains(&base_name) {
-            return Vec::new();
-        }
-        let ext = Path::new(root)
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| format!(".{}", s.to_lowercase()))
-            .unwrap_or_default();
-        if ignore_ext_set.contains(&ext) {
-            return Vec::new();
-        }
-        if ext_set.contains(&ext) {
-            return vec![root.replace('\\', "/")];
-        }
-        return Vec::new();
+        .collect();
+    // Job ids embed a per-process sequence number; skip past restored ones.
+    JOB_COUNTER.fetch_add(jobs.len() as u64, Ordering::Relaxed);
+
+    eprintln!(
+        "[sidecar:index] restored {} job(s) from {} ({} interrupted)",
+        jobs.len(),
+        path.display(),
+        interrupted
+    );
+    if interrupted > 0 {
+        persist_jobs(&jobs, true);
     }
+    jobs
+}
 
-    let mut matches = Vec::new();
-    for entry in WalkDir::new(root).into_iter().flatten() {
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        let base_name = path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or_default()
-            .to_lowercase();
-        if ignore_file_set.contains(&base_name) {
-            continue;
-        }
-        let ext = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| format!(".{}", s.to_lowercase()))
-            .unwrap_or_default();
-        if ignore_ext_set.contains(&ext) {
-            continue;
-        }
-        if ext_set.contains(&ext) {
-            matches.push(path.to_string_lossy().replace('\\', "/"));
+fn persist_jobs(jobs: &HashMap<String, IndexJobStatus>, force: bool) {
+    {
+        let mut last = LAST_PERSIST.lock().unwrap_or_else(|e| e.into_inner());
+        if !force && last.is_some_and(|at| at.elapsed() < PERSIST_INTERVAL) {
+            return;
         }
+        *last = Some(Instant::now());
+    }
+
+    let mut ordered: Vec<&IndexJobStatus> = jobs.values().collect();
+    newest_first(&mut ordered);
+    let result = serde_json::to_vec_pretty(&json!({ "jobs": ordered }))
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| write_atomic(&data_file(JOBS_FILE), &bytes));
+    if let Err(error) = result {
+        eprintln!("[sidecar:index] failed to persist job history: {}", error);
     }
-    matches
 }
 
-fn store() -> &'static Mutex<HashMap<String, IndexJobStatus>> {
-    JOB_STORE.get_or_init(|| Mutex::new(HashMap::new()))
+/// Drops the oldest finished jobs once the history grows past its limit.
+fn prune_history(jobs: &mut HashMap<String, IndexJobStatus>) {
+    let limit = job_history_limit();
+    if jobs.len() <= limit {
+        return;
+    }
+    let mut finished: Vec<&IndexJobStatus> = jobs
+        .values()
+        .filter(|job| !is_live_status(&job.status) && job.status != "interrupted")
+        .collect();
+    newest_first(&mut finished);
+    let excess = jobs.len() - limit;
+    let stale: Vec<String> = finished
+        .iter()
+        .rev()
+        .take(excess)
+        .map(|job| job.job_id.clone())
+        .collect();
+    for job_id in stale {
+        jobs.remove(&job_id);
+        issues::forget(&job_id);
+    }
+}
+
+fn controls() -> &'static Mutex<HashMap<String, Arc<JobControl>>> {
+    JOB_CONTROLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn job_control(job_id: &str) -> Option<Arc<JobControl>> {
+    let controls = controls().lock().unwrap_or_else(|e| e.into_inner());
+    controls.get(job_id).cloned()
+}
+
+fn forget_job_control(job_id: &str) {
+    let mut controls = controls().lock().unwrap_or_else(|e| e.into_inner());
+    controls.remove(job_id);
 }
 
 fn make_job_id() -> String {
@@ -256,6 +288,8 @@ fn make_job_id() -> String {
 fn put_job(status: IndexJobStatus) -> Result<(), String> {
     let mut jobs = store().lock().map_err(|e| e.to_string())?;
     jobs.insert(status.job_id.clone(), status);
+    prune_history(&mut jobs);
+    persist_jobs(&jobs, true);
     Ok(())
 }
 
@@ -263,12 +297,26 @@ fn update_job<F>(job_id: &str, updater: F) -> Result<(), String>
 where
     F: FnOnce(&mut IndexJobStatus),
 {
-    let mut jobs = store().lock().map_err(|e| e.to_string())?;
-    let job = jobs
-        .get_mut(job_id)
-        .ok_or_else(|| format!("job not found: {}", job_id))?;
-    updater(job);
-    job.updated_at = now_string();
+    let snapshot = {
+        let mut jobs = store().lock().map_err(|e| e.to_string())?;
+        let job = jobs
+            .get_mut(job_id)
+            .ok_or_else(|| format!("job not found: {}", job_id))?;
+        let previous_status = job.status.clone();
+        updater(job);
+        if is_live_status(&previous_status) {
+            job.provider_outages = http_policy::outages();
+        }
+        job.updated_at = now_string();
+        let status_changed = job.status != previous_status;
+        let snapshot = job.notify.then(|| job.clone());
+        persist_jobs(&jobs, status_changed);
+        snapshot
+    };
+    // Emitted outside the lock so a slow stdout reader can't stall other jobs.
+    if let Some(job) = snapshot {
+        emit_job_status(&job);
+    }
     Ok(())
 }
 
@@ -281,11 +329,40 @@ fn list_running_index_jobs() -> Result<Vec<(String, String)>, String> {
     let jobs = store().lock().map_err(|e| e.to_string())?;
     Ok(jobs
         .values()
-        .filter(|j| j.status == "running")
+        .filter(|j| is_live_status(&j.status))
         .map(|j| (j.job_id.clone(), j.dir.clone()))
         .collect())
 }
 
+/// Polls until no job is running or paused, or `timeout` elapses, and returns
+/// whatever is still running.
+async fn wait_for_jobs_to_stop(timeout: Duration) -> Result<Vec<(String, String)>, String> {
+    let started = Instant::now();
+    loop {
+        let running = list_running_index_jobs()?;
+        if running.is_empty() || started.elapsed() >= timeout {
+            return Ok(running);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+fn mark_cancelled(job_id: &str) {
+    issues::flush(job_id);
+    lexical::flush();
+    let _ = update_job(job_id, |job| {
+        job.status = "cancelled".to_string();
+        job.phase = "done".to_string();
+        job.message = "Indexing cancelled".to_string();
+        job.finished_at = Some(now_string());
+    });
+    eprintln!("[sidecar:index] job {} cancelled", job_id);
+}
+
+fn format_text_result_error(path: &str, error: &str) -> String {
+    format!("Text indexing failed for {}: {}", path, error)
+}
+
 fn format_image_result_error(path: &str, error: &str) -> String {
     format!("Image indexing failed for {}: {}", path, error)
 }
@@ -294,315 +371,535 @@ fn format_video_result_error(path: &str, error: &str) -> String {
     format!("Video indexing failed for {}: {}", path, error)
 }
 
+fn is_terminal_status(status: &str) -> bool {
+    matches!(
+        status,
+        "completed" | "completed_with_errors" | "failed" | "cancelled"
+    )
+}
+
+/// Pushes `index.progress`, or `index.finished` once the job reached a final
+/// status, to clients that opted in with `notify`.
+fn emit_job_status(job: &IndexJobStatus) {
+    let method = if is_terminal_status(&job.status) {
+        "index.finished"
+    } else {
+        "index.progress"
+    };
+    emit_notification(method, json!(job));
+}
+
+fn phase_for_kind(kind: &str) -> &'static str {
+    match kind {
+        "video" => "index_video",
+        "image" => "index_image",
+        _ => "index_text",
+    }
+}
+
+/// Per-file outcomes for one job: failures and skips go into the job's issue
+/// report, and notifications go out if the job opted in.
+struct JobEvents<'a> {
+    job_id: &'a str,
+    enabled: bool,
+}
+
+impl JobEvents<'_> {
+    fn file_indexed(&self, kind: &str, path: &str) {
+        if self.enabled {
+            emit_notification(
+                "index.fileIndexed",
+                json!({ "job_id": self.job_id, "kind": kind, "path": path }),
+            );
+        }
+    }
+
+    fn file_skipped(&self, kind: &str, path: &str, reason: &str) {
+        issues::record(
+            self.job_id,
+            FileIssue::skipped(kind, phase_for_kind(kind), path, reason),
+        );
+    }
+
+    fn file_failed(&self, kind: &str, path: &str, error: &str) {
+        issues::record(
+            self.job_id,
+            FileIssue::failed(kind, phase_for_kind(kind), path, error),
+        );
+        if self.enabled {
+            emit_notification(
+                "index.fileFailed",
+                json!({ "job_id": self.job_id, "kind": kind, "path": path, "error": error }),
+            );
+        }
+    }
+}
+
 fn spawn_rust_index_job(job_id: String, dir: String) {
+    let control = Arc::new(JobControl::default());
+    controls()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(job_id.clone(), Arc::clone(&control));
     thread::spawn(move || {
-        eprintln!("[sidecar:index] starting job {} for {}", job_id, dir);
-        let runtime = match tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-        {
-            Ok(rt) => rt,
-            Err(error) => {
-                let _ = update_job(&job_id, |job| {
-                    job.status = "failed".to_string();
-                    job.phase = "done".to_string();
-                    job.error = format!("failed to init runtime: {}", error);
-                    job.message = "Indexing failed".to_string();
-                    job.finished_at = Some(now_string());
-                });
-                eprintln!(
-                    "[sidecar:index] job {} failed to initialize runtime: {}",
-                    job_id, error
-                );
-                return;
-            }
-        };
+        run_index_job(&job_id, &dir, &control);
+        forget_job_control(&job_id);
+    });
+}
 
-        let hasher = Sha256PathHasher;
-        let store = match HelixTextStore::from_env() {
-            Ok(store) => store,
-            Err(error) => {
-                let _ = update_job(&job_id, |job| {
-                    job.status = "failed".to_string();
-                    job.phase = "done".to_string();
-                    job.error = error;
-                    job.message = "Indexing failed".to_string();
-                    job.finished_at = Some(now_string());
-                });
-                eprintln!(
-                    "[sidecar:index] job {} failed to initialize Helix store",
-                    job_id
-                );
-                return;
-            }
-        };
-        let groq = match GroqClient::from_env() {
-            Ok(client) => client,
-            Err(error) => {
-                let error_message = error.clone();
-                let _ = update_job(&job_id, |job| {
-                    job.status = "failed".to_string();
-                    job.phase = "done".to_string();
-                    job.error = error;
-                    job.message = "Indexing failed".to_string();
-                    job.finished_at = Some(now_string());
-                });
-                eprintln!(
-                    "[sidecar:index] job {} failed to initialize Groq client: {}",
-                    job_id, error_message
-                );
-                return;
-            }
-        };
+fn run_index_job(job_id: &str, dir: &str, control: &JobControl) {
+    eprintln!("[sidecar:index] starting job {} for {}", job_id, dir);
+    issues::reset(job_id);
+    let events = JobEvents {
+        job_id,
+        enabled: matches!(get_job(job_id), Ok(Some(job)) if job.notify),
+    };
+    let runtime = shared_runtime();
 
-        let _ = update_job(&job_id, |job| {
-            job.phase = "index_text".to_string();
-            job.message = "Indexing text files (Rust orchestrator)".to_string();
-        });
+    let store = match HelixTextStore::from_env() {
+        Ok(store) => store,
+        Err(error) => {
+            let _ = update_job(job_id, |job| {
+                job.status = "failed".to_string();
+                job.phase = "done".to_string();
+                job.error = error;
+                job.message = "Indexing failed".to_string();
+                job.finished_at = Some(now_string());
+            });
+            eprintln!(
+                "[sidecar:index] job {} failed to initialize Helix store",
+                job_id
+            );
+            return;
+        }
+    };
+    let groq = match GroqClient::from_env() {
+        Ok(client) => client,
+        Err(error) => {
+            let error_message = error.clone();
+            let _ = update_job(job_id, |job| {
+                job.status = "failed".to_string();
+                job.phase = "done".to_string();
+                job.error = error;
+                job.message = "Indexing failed".to_string();
+                job.finished_at = Some(now_string());
+            });
+            eprintln!(
+                "[sidecar:index] job {} failed to initialize Groq client: {}",
+                job_id, error_message
+            );
+            return;
+        }
+    };
+
+    let _ = update_job(job_id, |job| {
+        job.phase = "reconcile".to_string();
+        job.message = "Comparing files on disk with the index".to_string();
+    });
+
+    let file_types = FileTypeConfig::load();
+    let ignore = IgnoreRules::load();
+    let text_files = collect_files(dir, &file_types.text, &ignore);
+    let video_files = collect_files(dir, &file_types.video, &ignore);
+    let image_files = collect_files(dir, &file_types.image, &ignore);
+    let text_found = text_files.len();
+    let video_found = video_files.len();
+    let image_found = image_files.len();
 
-        let results = runtime.block_on(file_indexer(vec![dir.clone()], &hasher, &store));
+    let mut report = match runtime.block_on(reconcile(
+        dir,
+        vec![
+            ("file", text_files.clone()),
+            ("video", video_files.clone()),
+            ("image", image_files.clone()),
+        ],
+        &Sha256PathHasher,
+        &store,
+    )) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!(
+                "[sidecar:index] job {} reconcile failed, indexing every file: {}",
+                job_id, error
+            );
+            let mut report = ReconcileReport::default();
+            for (kind, files) in [
+                ("file", text_files),
+                ("video", video_files),
+                ("image", image_files),
+            ] {
+                report.plans.insert(
+                    kind.to_string(),
+                    KindPlan {
+                        to_index: files,
+                        ..KindPlan::default()
+                    },
+                );
+            }
+            report
+        }
+    };
+    for (path, error) in &report.errors {
         eprintln!(
-            "[sidecar:index] job {} text pass complete: found={}, indexed={}, errors={}, skipped={}",
-            job_id,
-            results.len(),
-            results.iter().filter(|r| r.indexed).count(),
-            results
-                .iter()
-                .filter(|r| !r.indexed && r.error.as_deref() != Some("Duplicate content hash"))
-                .count(),
-            results
-                .iter()
-                .filter(|r| r.error.as_deref() == Some("Duplicate content hash"))
-                .count()
+            "[sidecar:index] job {} reconcile warning for {}: {}",
+            job_id, path, error
         );
+    }
+    match runtime.block_on(store.backfill_lexical_index(&normalize_root(dir))) {
+        Ok(0) => {}
+        Ok(count) => eprintln!(
+            "[sidecar:index] job {} added {} existing asset(s) to the lexical index",
+            job_id, count
+        ),
+        Err(error) => eprintln!(
+            "[sidecar:index] job {} lexical backfill failed: {}",
+            job_id, error
+        ),
+    }
+    if runtime.block_on(control.checkpoint()).is_err() {
+        mark_cancelled(job_id);
+        return;
+    }
 
-        let text_found = results.len();
-        let text_indexed = results.iter().filter(|r| r.indexed).count();
-        let text_skipped = results
-            .iter()
-            .filter(|r| r.error.as_deref() == Some("Duplicate content hash"))
-            .count();
-        let text_errors = results
-            .iter()
-            .filter(|r| !r.indexed && r.error.as_deref() != Some("Duplicate content hash"))
-            .count();
+    let text_plan = report.take_plan("file");
+    let video_plan = report.take_plan("video");
+    let image_plan = report.take_plan("image");
+    let hasher = CachedPathHasher::new(std::mem::take(&mut report.hashes));
+    eprintln!(
+        "[sidecar:index] job {} reconcile complete: unchanged={}, moved={}, replaced={}, removed={}",
+        job_id,
+        text_plan.unchanged + video_plan.unchanged + image_plan.unchanged,
+        text_plan.moved + video_plan.moved + image_plan.moved,
+        report.replaced,
+        report.removed_paths.len()
+    );
 
-        let failed_example = results
-            .iter()
-            .find(|r| !r.indexed && r.error.as_deref() != Some("Duplicate content hash"))
-            .and_then(|r| r.error.clone())
-            .unwrap_or_default();
+    let _ = update_job(job_id, |job| {
+        job.files_unchanged = text_plan.unchanged + video_plan.unchanged + image_plan.unchanged;
+        job.files_moved = text_plan.moved + video_plan.moved + image_plan.moved;
+        job.assets_removed = report.replaced + report.removed_paths.len();
+        job.text_found = text_found;
+        job.video_found = video_found;
+        job.image_found = image_found;
+        job.phase = "index_text".to_string();
+        job.message = "Indexing text files (Rust orchestrator)".to_string();
+    });
+
+    let mut text_indexed = 0usize;
+    let mut text_errors = 0usize;
+    let mut text_skipped = text_plan.unchanged + text_plan.moved;
+    let mut failed_example = String::new();
+
+    // Files go in small groups so their units share embedding requests;
+    // counters and notifications move as each group lands.
+    for group in text_plan.to_index.chunks(TEXT_FILES_PER_CALL) {
+        let results = runtime.block_on(file_indexer(group.to_vec(), &hasher, &store, control));
+        let cancelled = results.len() < group.len();
+        for result in results {
+            if result.indexed {
+                text_indexed += 1;
+                events.file_indexed("file", &result.path);
+            } else if result.is_skipped() {
+                text_skipped += 1;
+                events.file_skipped(
+                    "file",
+                    &result.path,
+                    result.error.as_deref().unwrap_or_default(),
+                );
+            } else {
+                text_errors += 1;
+                runtime.block_on(drop_partial_assets(
+                    &store,
+                    result
+                        .content_hash
+                        .as_deref()
+                        .filter(|_| result.created)
+                        .into_iter(),
+                ));
+                let error = result.error.as_deref().unwrap_or("unknown error");
+                if failed_example.is_empty() {
+                    failed_example = format_text_result_error(&result.path, error);
+                }
+                events.file_failed("file", &result.path, error);
+            }
+        }
 
-        let _ = update_job(&job_id, |job| {
-            job.text_found = text_found;
+        let _ = update_job(job_id, |job| {
             job.text_indexed = text_indexed;
             job.text_skipped = text_skipped;
             job.text_errors = text_errors;
-            job.message = "Text indexing complete, starting video indexing".to_string();
         });
+        if cancelled {
+            // Cancelled before the rest of the group was started.
+            break;
+        }
+    }
+    eprintln!(
+        "[sidecar:index] job {} text pass complete: found={}, indexed={}, errors={}, skipped={}",
+        job_id, text_found, text_indexed, text_errors, text_skipped
+    );
 
-        let video_exts = load_video_extensions();
-        let image_exts = load_image_extensions();
-        let (ignore_exts, ignore_files) = load_ignore_config();
-        let video_files =
-            collect_video_files_with_ignore(&dir, &video_exts, &ignore_exts, &ignore_files);
-        let video_found = video_files.len();
-        let mut video_indexed = 0usize;
-        let mut video_errors = 0usize;
-        let video_skipped = 0usize;
-        let mut first_video_error: Option<String> = None;
-
-        let output_dir = env::current_dir()
-            .map(|d| d.join("videos").join("output_indexer"))
-            .unwrap_or_else(|_| Path::new("videos/output_indexer").to_path_buf());
-        let output_dir_str = output_dir.to_string_lossy().replace('\\', "/");
-
-        let _ = update_job(&job_id, |job| {
-            job.video_found = video_found;
-            job.phase = "index_video".to_string();
-            job.message = "Indexing video files (Rust sidecar)".to_string();
-        });
-        eprintln!(
-            "[sidecar:index] job {} video pass starting: {} candidate files",
-            job_id, video_found
-        );
+    let _ = update_job(job_id, |job| {
+        job.text_found = text_found;
+        job.text_indexed = text_indexed;
+        job.text_skipped = text_skipped;
+        job.text_errors = text_errors;
+        job.message = "Text indexing complete, starting video indexing".to_string();
+    });
+    if runtime.block_on(control.checkpoint()).is_err() {
+        mark_cancelled(job_id);
+        return;
+    }
 
-        for video_path in video_files {
-            let content_hash = match runtime.block_on(hasher.compute_file_hash(&video_path)) {
-                Ok(hash) => hash,
-                Err(error) => {
-                    video_errors += 1;
-                    if first_video_error.is_none() {
-                        first_video_error = Some(format_video_result_error(&video_path, &error));
-                    }
-                    eprintln!(
-                        "[sidecar:index] job {} failed to hash video {}: {}",
-                        job_id, video_path, error
-                    );
-                    continue;
-                }
-            };
+    let mut video_indexed = 0usize;
+    let mut video_errors = 0usize;
+    let mut video_skipped = video_plan.unchanged + video_plan.moved;
+    let mut first_video_error: Option<String> = None;
 
-            let result = runtime.block_on(index_video_with_sidecar(
-                &content_hash,
-                &video_path,
-                &output_dir_str,
-                30.0,
-                &groq,
-                &store,
-            ));
-
-            match result {
-                Ok(r) if r.indexed => {
-                    video_indexed += 1;
-                    eprintln!(
-                        "[sidecar:index] job {} indexed video {}",
-                        job_id, video_path
-                    );
+    let output_dir_str = default_output_dir();
+
+    let _ = update_job(job_id, |job| {
+        job.phase = "index_video".to_string();
+        job.message = "Indexing video files (Rust sidecar)".to_string();
+    });
+    eprintln!(
+        "[sidecar:index] job {} video pass starting: {} candidate files",
+        job_id,
+        video_plan.to_index.len()
+    );
+
+    for video_path in video_plan.to_index {
+        if runtime.block_on(control.checkpoint()).is_err() {
+            mark_cancelled(job_id);
+            return;
+        }
+
+        let content_hash = match runtime.block_on(hasher.compute_file_hash(&video_path)) {
+            Ok(hash) => hash,
+            Err(error) => {
+                video_errors += 1;
+                if first_video_error.is_none() {
+                    first_video_error = Some(format_video_result_error(&video_path, &error));
                 }
-                Ok(r) => {
-                    video_errors += 1;
-                    let error_message = r
-                        .error
-                        .clone()
-                        .unwrap_or_else(|| "video produced no searchable chunks".to_string());
-                    if first_video_error.is_none() {
-                        first_video_error =
-                            Some(format_video_result_error(&video_path, &error_message));
-                    }
-                    eprintln!(
-                        "[sidecar:index] job {} video indexing returned not-indexed for {}: {}",
-                        job_id, video_path, error_message
-                    );
+                events.file_failed("video", &video_path, &error);
+                eprintln!(
+                    "[sidecar:index] job {} failed to hash video {}: {}",
+                    job_id, video_path, error
+                );
+                let _ = update_job(job_id, |job| job.video_errors = video_errors);
+                continue;
+            }
+        };
+
+        let result = runtime.block_on(index_video_with_sidecar(
+            &content_hash,
+            &video_path,
+            &output_dir_str,
+            DEFAULT_CHUNK_DURATION_SECS,
+            &groq,
+            &store,
+            control,
+        ));
+
+        match result {
+            Ok(r) if r.indexed => {
+                video_indexed += 1;
+                events.file_indexed("video", &video_path);
+                eprintln!(
+                    "[sidecar:index] job {} indexed video {}",
+                    job_id, video_path
+                );
+            }
+            Ok(r) if r.error.as_deref() == Some(DUPLICATE_CONTENT_HASH) => {
+                video_skipped += 1;
+                events.file_skipped("video", &video_path, DUPLICATE_CONTENT_HASH);
+                eprintln!(
+                    "[sidecar:index] job {} skipping duplicate video {}",
+                    job_id, video_path
+                );
+            }
+            Ok(r) => {
+                video_errors += 1;
+                let error_message = r
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "video produced no searchable chunks".to_string());
+                if first_video_error.is_none() {
+                    first_video_error =
+                        Some(format_video_result_error(&video_path, &error_message));
                 }
-                Err(error) => {
-                    video_errors += 1;
-                    if first_video_error.is_none() {
-                        first_video_error = Some(format_video_result_error(&video_path, &error));
-                    }
-                    eprintln!(
-                        "[sidecar:index] job {} video indexing failed for {}: {}",
-                        job_id, video_path, error
-                    );
+                events.file_failed("video", &video_path, &error_message);
+                eprintln!(
+                    "[sidecar:index] job {} video indexing returned not-indexed for {}: {}",
+                    job_id, video_path, error_message
+                );
+            }
+            Err(error) if error == JOB_CANCELLED => {
+                mark_cancelled(job_id);
+                return;
+            }
+            Err(error) => {
+                video_errors += 1;
+                if first_video_error.is_none() {
+                    first_video_error = Some(format_video_result_error(&video_path, &error));
                 }
+                events.file_failed("video", &video_path, &error);
+                eprintln!(
+                    "[sidecar:index] job {} video indexing failed for {}: {}",
+                    job_id, video_path, error
+                );
             }
-
-            let _ = update_job(&job_id, |job| {
-                job.video_indexed = video_indexed;
-                job.video_errors = video_errors;
-                job.video_skipped = video_skipped;
-            });
         }
 
-        let image_files =
-            collect_video_files_with_ignore(&dir, &image_exts, &ignore_exts, &ignore_files);
-        let image_found = image_files.len();
-        let mut image_indexed = 0usize;
-        let mut image_errors = 0usize;
-        let mut image_skipped = 0usize;
-
-        let _ = update_job(&job_id, |job| {
-            job.image_found = image_found;
-            job.phase = "index_image".to_string();
-            job.message = "Indexing image files (Rust sidecar)".to_string();
+        let _ = update_job(job_id, |job| {
+            job.video_indexed = video_indexed;
+            job.video_errors = video_errors;
+            job.video_skipped = video_skipped;
         });
-        eprintln!(
-            "[sidecar:index] job {} image pass starting: {} candidate files",
-            job_id, image_found
-        );
+    }
 
-        let image_results =
-            runtime.block_on(image_indexer_with_sidecar(image_files, &groq, &store));
-        let first_image_error = image_results.iter().find_map(|result| {
-            result
-                .error
-                .as_ref()
-                .filter(|error| error.as_str() != "Duplicate content hash")
-                .map(|error| format_image_result_error(&result.path, error))
-        });
-        for result in image_results {
+    let mut image_indexed = 0usize;
+    let mut image_errors = 0usize;
+    let mut image_skipped = image_plan.unchanged + image_plan.moved;
+
+    let _ = update_job(job_id, |job| {
+        job.phase = "index_image".to_string();
+        job.message = "Indexing image files (Rust sidecar)".to_string();
+    });
+    eprintln!(
+        "[sidecar:index] job {} image pass starting: {} candidate files",
+        job_id,
+        image_plan.to_index.len()
+    );
+
+    let mut first_image_error: Option<String> = None;
+    for group in image_plan.to_index.chunks(IMAGE_FILES_PER_CALL) {
+        let results = runtime.block_on(image_indexer_with_sidecar(
+            group.to_vec(),
+            &groq,
+            &hasher,
+            &store,
+            control,
+        ));
+        let cancelled = results.len() < group.len();
+        for result in results {
             if result.indexed {
                 image_indexed += 1;
+                events.file_indexed("image", &result.path);
                 eprintln!(
                     "[sidecar:index] job {} indexed image {}",
                     job_id, result.path
                 );
-            } else if result.error.as_deref() == Some("Duplicate content hash") {
+            } else if result.error.as_deref() == Some(DUPLICATE_CONTENT_HASH) {
                 image_skipped += 1;
+                events.file_skipped("image", &result.path, DUPLICATE_CONTENT_HASH);
                 eprintln!(
                     "[sidecar:index] job {} skipping duplicate image {}",
                     job_id, result.path
                 );
             } else {
                 image_errors += 1;
+                runtime.block_on(drop_partial_assets(
+                    &store,
+                    result
+                        .content_hash
+                        .as_deref()
+                        .filter(|_| result.created)
+                        .into_iter(),
+                ));
+                let error = result.error.as_deref().unwrap_or("unknown error");
+                if first_image_error.is_none() {
+                    first_image_error = Some(format_image_result_error(&result.path, error));
+                }
+                events.file_failed("image", &result.path, error);
                 eprintln!(
                     "[sidecar:index] job {} image indexing failed for {}: {}",
-                    job_id,
-                    result.path,
-                    result
-                        .error
-                        .clone()
-                        .unwrap_or_else(|| "unknown error".to_string())
+                    job_id, result.path, error
                 );
             }
-
-            let _ = update_job(&job_id, |job| {
-                job.image_indexed = image_indexed;
-                job.image_errors = image_errors;
-                job.image_skipped = image_skipped;
-            });
         }
 
-        let _ = update_job(&job_id, |job| {
-            job.text_found = text_found;
-            job.text_indexed = text_indexed;
-            job.text_skipped = text_skipped;
-            job.text_errors = text_errors;
-            job.video_found = video_found;
-            job.video_indexed = video_indexed;
-            job.video_errors = video_errors;
-            job.video_skipped = video_skipped;
-            job.image_found = image_found;
+        let _ = update_job(job_id, |job| {
             job.image_indexed = image_indexed;
             job.image_errors = image_errors;
             job.image_skipped = image_skipped;
-            job.phase = "done".to_string();
-            job.finished_at = Some(now_string());
+        });
+        if cancelled {
+            break;
+        }
+    }
+
+    if runtime.block_on(control.checkpoint()).is_err() {
+        mark_cancelled(job_id);
+        return;
+    }
 
-            if text_errors > 0 || video_errors > 0 || image_errors > 0 {
+    issues::flush(job_id);
+    lexical::flush();
+    let _ = update_job(job_id, |job| {
+        job.text_found = text_found;
+        job.text_indexed = text_indexed;
+        job.text_skipped = text_skipped;
+        job.text_errors = text_errors;
+        job.video_found = video_found;
+        job.video_indexed = video_indexed;
+        job.video_errors = video_errors;
+        job.video_skipped = video_skipped;
+        job.image_found = image_found;
+        job.image_indexed = image_indexed;
+        job.image_errors = image_errors;
+        job.image_skipped = image_skipped;
+        job.phase = "done".to_string();
+        job.finished_at = Some(now_string());
+
+        let total_errors = text_errors + video_errors + image_errors;
+        let total_ok = text_indexed
+            + text_skipped
+            + video_indexed
+            + video_skipped
+            + image_indexed
+            + image_skipped;
+        if total_errors > 0 {
+            // Only a job where nothing went through counts as failed; the
+            // per-file report lists what went wrong either way.
+            if total_ok == 0 {
                 job.status = "failed".to_string();
                 job.message = "Indexing failed".to_string();
-                job.error = if !failed_example.is_empty() {
-                    failed_example
-                } else if image_errors > 0 {
-                    first_image_error.unwrap_or_else(|| {
-                        "Image indexing encountered one or more errors".to_string()
-                    })
-                } else {
-                    first_video_error.unwrap_or_else(|| {
-                        "Video indexing encountered one or more errors".to_string()
-                    })
-                };
             } else {
-                job.status = "completed".to_string();
-                job.message = "Text, video, and image indexing complete".to_string();
-                job.error.clear();
+                job.status = "completed_with_errors".to_string();
+                job.message = format!(
+                    "Indexing complete; {} file(s) failed, see index.errors",
+                    total_errors
+                );
             }
-        });
-        eprintln!(
-            "[sidecar:index] job {} finished: text(indexed={}, errors={}, skipped={}), video(indexed={}, errors={}, skipped={}), image(indexed={}, errors={}, skipped={})",
-            job_id,
-            text_indexed,
-            text_errors,
-            text_skipped,
-            video_indexed,
-            video_errors,
-            video_skipped,
-            image_indexed,
-            image_errors,
-            image_skipped
-        );
+            job.error = if !failed_example.is_empty() {
+                failed_example
+            } else if image_errors > 0 {
+                first_image_error
+                    .unwrap_or_else(|| "Image indexing encountered one or more errors".to_string())
+            } else {
+                first_video_error
+                    .unwrap_or_else(|| "Video indexing encountered one or more errors".to_string())
+            };
+        } else {
+            job.status = "completed".to_string();
+            job.message = "Text, video, and image indexing complete".to_string();
+            job.error.clear();
+        }
     });
+    eprintln!(
+        "[sidecar:index] job {} finished: text(indexed={}, errors={}, skipped={}), video(indexed={}, errors={}, skipped={}), image(indexed={}, errors={}, skipped={})",
+        job_id,
+        text_indexed,
+        text_errors,
+        text_skipped,
+        video_indexed,
+        video_errors,
+        video_skipped,
+        image_indexed,
+        image_errors,
+        image_skipped
+    );
 }
 
 pub fn handle_start(request: &JsonRpcRequest) -> JsonRpcResponse {
@@ -631,6 +928,12 @@ pub fn handle_start(request: &JsonRpcRequest) -> JsonRpcResponse {
         image_indexed: 0,
         image_errors: 0,
         image_skipped: 0,
+        files_unchanged: 0,
+        files_moved: 0,
+        assets_removed: 0,
+        resumable: false,
+        notify: parsed.notify,
+        provider_outages: http_policy::outages(),
         message: "Starting Rust indexer".to_string(),
         error: String::new(),
         started_at: now.clone(),
@@ -677,16 +980,256 @@ pub fn handle_status(request: &JsonRpcRequest) -> JsonRpcResponse {
     }
 }
 
+/// Applies a cancel/pause/resume transition to a job. `apply` gets the job's
+/// control handle if its thread is still alive, and rejects transitions that
+/// don't make sense for the job's current status.
+fn handle_control<F>(request: &JsonRpcRequest, failure: &str, apply: F) -> JsonRpcResponse
+where
+    F: FnOnce(Option<&JobControl>, &mut IndexJobStatus) -> Result<(), String>,
+{
+    let parsed: IndexControlParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+
+    let control = job_control(&parsed.job_id);
+    let mut jobs = match store().lock() {
+        Ok(jobs) => jobs,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                failure,
+                Some(json!({ "reason": error.to_string() })),
+            );
+        }
+    };
+    let Some(job) = jobs.get_mut(&parsed.job_id) else {
+        return err_response(
+            request.id.clone(),
+            -32004,
+            failure,
+            Some(json!({ "reason": format!("Job not found: {}", parsed.job_id) })),
+        );
+    };
+    if let Err(reason) = apply(control.as_deref(), job) {
+        return err_response(
+            request.id.clone(),
+            -32603,
+            failure,
+            Some(json!({ "reason": reason, "status": job.status })),
+        );
+    }
+    job.updated_at = now_string();
+    let snapshot = job.clone();
+    drop(jobs);
+
+    // Emitted outside the lock, as in `update_job`; the history is persisted
+    // from the live map afterwards so a newer job update is never overwritten.
+    if snapshot.notify {
+        emit_job_status(&snapshot);
+    }
+    if let Ok(jobs) = store().lock() {
+        persist_jobs(&jobs, true);
+    }
+    ok_response(
+        request.id.clone(),
+        json!({ "success": true, "job_id": snapshot.job_id, "status": snapshot.status }),
+    )
+}
+
+pub fn handle_cancel(request: &JsonRpcRequest) -> JsonRpcResponse {
+    handle_control(request, "Index cancel failed", |control, job| {
+        if job.status == "interrupted" {
+            job.status = "cancelled".to_string();
+            job.phase = "done".to_string();
+            job.resumable = false;
+            job.message = "Indexing cancelled".to_string();
+            job.finished_at = Some(now_string());
+            return Ok(());
+        }
+        match control {
+            Some(control) if is_live_status(&job.status) => {
+                control.cancel();
+                job.message = "Cancelling at the next checkpoint".to_string();
+                Ok(())
+            }
+            _ => Err(format!("Job {} is already {}", job.job_id, job.status)),
+        }
+    })
+}
+
+pub fn handle_pause(request: &JsonRpcRequest) -> JsonRpcResponse {
+    handle_control(request, "Index pause failed", |control, job| {
+        let control = control
+            .filter(|control| job.status == "running" && !control.is_cancelled())
+            .ok_or_else(|| format!("Job {} is not running", job.job_id))?;
+        control.pause();
+        job.status = "paused".to_string();
+        job.message = "Paused".to_string();
+        Ok(())
+    })
+}
+
+/// Resumes a paused job, or restarts an interrupted one. A restarted job
+/// re-runs from the top, but reconciliation skips everything already indexed.
+pub fn handle_resume(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let mut restart: Option<(String, String)> = None;
+    let response = handle_control(request, "Index resume failed", |control, job| {
+        if job.status == "interrupted" {
+            job.status = "running".to_string();
+            job.phase = "reconcile".to_string();
+            job.resumable = false;
+            job.error.clear();
+            job.finished_at = None;
+            job.message = "Resuming interrupted job".to_string();
+            restart = Some((job.job_id.clone(), job.dir.clone()));
+            return Ok(());
+        }
+        let control = control
+            .filter(|_| job.status == "paused")
+            .ok_or_else(|| format!("Job {} is not paused", job.job_id))?;
+        control.resume();
+        job.status = "running".to_string();
+        job.message = "Resuming".to_string();
+        Ok(())
+    });
+
+    if let Some((job_id, dir)) = restart {
+        eprintln!("[sidecar:index] restarting interrupted job {}", job_id);
+        spawn_rust_index_job(job_id, dir);
+    }
+    response
+}
+
+pub fn handle_errors(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let parsed: IndexErrorsParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+    match get_job(&parsed.job_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return err_response(
+                request.id.clone(),
+                -32004,
+                "Index errors failed",
+                Some(json!({ "reason": format!("Job not found: {}", parsed.job_id) })),
+            );
+        }
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Index errors failed",
+                Some(json!({ "reason": error })),
+            );
+        }
+    }
+    let limit = parsed
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .clamp(1, MAX_LIST_LIMIT);
+
+    let report = issues::load(&parsed.job_id);
+    let matching: Vec<&FileIssue> = report
+        .issues
+        .iter()
+        .filter(|issue| {
+            parsed
+                .outcome
+                .as_deref()
+                .is_none_or(|outcome| issue.outcome == outcome)
+        })
+        .collect();
+    let total = matching.len();
+    let page: Vec<&FileIssue> = matching
+        .into_iter()
+        .skip(parsed.offset)
+        .take(limit)
+        .collect();
+    let next_offset = (parsed.offset + page.len() < total).then_some(parsed.offset + page.len());
+
+    ok_response(
+        request.id.clone(),
+        json!({
+            "job_id": parsed.job_id,
+            "errors": page,
+            "total": total,
+            "dropped": report.dropped,
+            "offset": parsed.offset,
+            "limit": limit,
+            "next_offset": next_offset,
+        }),
+    )
+}
+
+pub fn handle_list(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let parsed: IndexListParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+    let limit = parsed
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .clamp(1, MAX_LIST_LIMIT);
+
+    let jobs = match store().lock() {
+        Ok(jobs) => jobs,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Index list failed",
+                Some(json!({ "reason": error.to_string() })),
+            );
+        }
+    };
+    let mut matching: Vec<&IndexJobStatus> = jobs
+        .values()
+        .filter(|job| {
+            parsed
+                .status
+                .as_deref()
+                .is_none_or(|status| job.status == status)
+        })
+        .collect();
+    newest_first(&mut matching);
+
+    let total = matching.len();
+    let page: Vec<&IndexJobStatus> = matching
+        .into_iter()
+        .skip(parsed.offset)
+        .take(limit)
+        .collect();
+    let next_offset = (parsed.offset + page.len() < total).then_some(parsed.offset + page.len());
+
+    ok_response(
+        request.id.clone(),
+        json!({
+            "jobs": page,
+            "total": total,
+            "offset": parsed.offset,
+            "limit": limit,
+            "next_offset": next_offset,
+        }),
+    )
+}
+
 #[derive(Debug, Deserialize)]
-struct IndexClearParams {}
+struct IndexClearParams {
+    /// Cancel running or paused jobs and wait for them to stop before clearing.
+    #[serde(default)]
+    cancel_running: bool,
+}
 
-pub fn handle_clear(request: &JsonRpcRequest) -> JsonRpcResponse {
-    let _: IndexClearParams = match parse_params(request) {
+pub async fn handle_clear(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let params: IndexClearParams = match parse_params(request) {
         Ok(parsed) => parsed,
         Err(error_response) => return error_response,
     };
 
-    let running = match list_running_index_jobs() {
+    let mut running = match list_running_index_jobs() {
         Ok(jobs) => jobs,
         Err(error) => {
             return err_response(
@@ -697,6 +1240,24 @@ pub fn handle_clear(request: &JsonRpcRequest) -> JsonRpcResponse {
             );
         }
     };
+    if !running.is_empty() && params.cancel_running {
+        for (job_id, _) in &running {
+            if let Some(control) = job_control(job_id) {
+                control.cancel();
+            }
+        }
+        running = match wait_for_jobs_to_stop(CLEAR_CANCEL_TIMEOUT).await {
+            Ok(jobs) => jobs,
+            Err(error) => {
+                return err_response(
+                    request.id.clone(),
+                    -32603,
+                    "Index clear failed",
+                    Some(json!({ "reason": error })),
+                );
+            }
+        };
+    }
     if !running.is_empty() {
         let running_jobs: Vec<serde_json::Value> = running
             .iter()
@@ -707,7 +1268,7 @@ pub fn handle_clear(request: &JsonRpcRequest) -> JsonRpcResponse {
             -32603,
             "Index clear failed",
             Some(json!({
-                "reason": "Cannot clear index while indexing job(s) are still running; wait for them to finish first.",
+                "reason": "Cannot clear index while indexing job(s) are still running; wait for them to finish or pass cancel_running.",
                 "running_jobs": running_jobs,
             })),
         );
@@ -725,24 +1286,7 @@ pub fn handle_clear(request: &JsonRpcRequest) -> JsonRpcResponse {
         }
     };
 
-    let runtime = match tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()
-    {
-        Ok(rt) => rt,
-        Err(error) => {
-            return err_response(
-                request.id.clone(),
-                -32603,
-                "Index clear failed",
-                Some(json!({
-                    "reason": format!("failed to init runtime: {}", error),
-                })),
-            );
-        }
-    };
-
-    match runtime.block_on(store.clear_search_index()) {
+    match store.clear_search_index().await {
         Ok(_) => ok_response(request.id.clone(), json!({ "ok": true })),
         Err(message) => err_response(
             request.id.clone(),
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::sidecar::rpc::indexing::adapters::embeddings::{
+    check_dimensions, embed_batched, env_dimensions, env_input_type, single, vector_from_array,
+    BatchLimits, EmbeddingClient,
+};
+use crate::sidecar::rpc::indexing::adapters::http_policy::{self, HttpPolicy};
+
+/// Cohere's `/v2/embed` API, or anything that mirrors it.
+#[derive(Clone)]
+pub struct CohereClient {
+    http: Client,
+    policy: Arc<HttpPolicy>,
+    api_key: String,
+    base_url: String,
+    model: String,
+    dimensions: Option<usize>,
+    query_input_type: Option<String>,
+    document_input_type: Option<String>,
+}
+
+impl fmt::Debug for CohereClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CohereClient")
+            .field("http", &self.http)
+            .field("api_key", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .field("dimensions", &self.dimensions)
+            .finish()
+    }
+}
+
+impl CohereClient {
+    pub fn from_env() -> Result<Self, String> {
+        let api_key = env::var("COHERE_API_KEY")
+            .map_err(|_| "COHERE_API_KEY not set".to_string())?
+            .trim()
+            .to_string();
+        if api_key.is_empty() {
+            return Err("COHERE_API_KEY is empty".to_string());
+        }
+
+        let base_url = env::var("COHERE_API_BASE_URL")
+            .unwrap_or_else(|_| "https://api.cohere.com/v2".to_string());
+        let model =
+            env::var("COHERE_EMBED_MODEL").unwrap_or_else(|_| "embed-english-v3.0".to_string());
+
+        Ok(Self {
+            http: Client::new(),
+            policy: http_policy::policy("cohere"),
+            api_key,
+            base_url,
+            model,
+            dimensions: env_dimensions("COHERE_EMBED_DIMENSIONS")?,
+            query_input_type: env_input_type("COHERE_QUERY_INPUT_TYPE", "search_query"),
+            document_input_type: env_input_type("COHERE_DOCUMENT_INPUT_TYPE", "search_document"),
+        })
+    }
+
+    async fn embed_as(
+        &self,
+        texts: Vec<&str>,
+        input_type: Option<&str>,
+    ) -> Result<Vec<Vec<f32>>, String> {
+        if texts.iter().any(|text| text.trim().is_empty()) {
+            return Err("cannot embed empty text".to_string());
+        }
+
+        let url = format!("{}/embed", self.base_url.trim_end_matches('/'));
+        let mut payload = json!({
+            "texts": texts,
+            "model": self.model,
+            "embedding_types": ["float"],
+        });
+        if let Some(input_type) = input_type {
+            payload["input_type"] = json!(input_type);
+        }
+        if let Some(dimensions) = self.dimensions {
+            payload["output_dimension"] = json!(dimensions);
+        }
+
+        let (status, body) = self
+            .policy
+            .send(|| {
+                Ok(self
+                    .http
+                    .post(&url)
+                    .bearer_auth(&self.api_key)
+                    .json(&payload))
+            })
+            .await
+            .map_err(|e| format!("Cohere request failed: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!("Cohere embed failed ({}): {}", status, body));
+        }
+
+        let parsed: Value =
+            serde_json::from_str(&body).map_err(|e| format!("Invalid Cohere JSON: {}", e))?;
+
+        let vectors = Self::extract_embeddings(&parsed).ok_or_else(|| {
+            format!(
+                "Cohere response missing embedding vector for model '{}': {}",
+                self.model, body
+            )
+        })?;
+        check_dimensions(vectors, self.dimensions, "Cohere")
+    }
+
+    /// v2 nests vectors under the requested type; v1 returns a bare list.
+    fn extract_embeddings(value: &Value) -> Option<Vec<Vec<f32>>> {
+        let embeddings = value.get("embeddings")?;
+        let list = embeddings.get("float").unwrap_or(embeddings).as_array()?;
+        list.iter().map(vector_from_array).collect()
+    }
+}
+
+#[async_trait]
+impl EmbeddingClient for CohereClient {
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>, String> {
+        self.embed_as(vec![text.trim()], self.document_input_type.as_deref())
+            .await
+            .and_then(single)
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>, String> {
+        self.embed_as(vec![text.trim()], self.query_input_type.as_deref())
+            .await
+            .and_then(single)
+    }
+
+    async fn embed_documents(&self, texts: &[String]) -> Vec<Result<Vec<f32>, String>> {
+        // Cohere caps a request at 96 texts.
+        let limits = BatchLimits {
+            max_items: 96,
+            max_tokens: 100_000,
+        }
+        .with_env();
+        embed_batched(texts, limits, |batch| {
+            self.embed_as(batch, self.document_input_type.as_deref())
+        })
+        .await
+    }
+
+    fn query_model_id(&self) -> String {
+        format!(
+            "cohere:{}:{}",
+            self.model,
+            self.dimensions.unwrap_or_default()
+        )
+    }
+
+    fn document_model_id(&self) -> String {
+        format!(
+            "cohere:{}:{}:{}",
+            self.model,
+            self.dimensions.unwrap_or_default(),
+            self.document_input_type.as_deref().unwrap_or_default()
+        )
+    }
+}
@@ -0,0 +1,246 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::sidecar::rpc::indexing::adapters::cohere::CohereClient;
+use crate::sidecar::rpc::indexing::adapters::openai::OpenAiCompatibleClient;
+use crate::sidecar::rpc::indexing::adapters::voyage::VoyageClient;
+
+#[async_trait]
+pub trait EmbeddingClient: Send + Sync + fmt::Debug {
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>, String>;
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>, String>;
+    /// Provider and model that query vectors come from, e.g.
+    /// `voyage:voyage-3-large`. Vectors from different ids are not comparable.
+    fn query_model_id(&self) -> String;
+    /// Everything that shapes a document vector, in the form
+    /// `<provider>:<model>:...`; keys the on-disk model cache.
+    fn document_model_id(&self) -> String;
+
+    /// Embeds many documents in as few requests as the provider allows.
+    /// Results line up with `texts`, so one bad input fails only itself.
+    async fn embed_documents(&self, texts: &[String]) -> Vec<Result<Vec<f32>, String>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.embed_document(text).await);
+        }
+        results
+    }
+}
+
+/// How much one embeddings request may carry.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchLimits {
+    pub max_items: usize,
+    /// Estimated, at four bytes per token, so keep it below the real limit.
+    pub max_tokens: usize,
+}
+
+impl BatchLimits {
+    /// The provider's limits, narrowed by `SIDECAR_EMBED_BATCH_SIZE` and
+    /// `SIDECAR_EMBED_BATCH_TOKENS` for servers that take less.
+    pub fn with_env(self) -> Self {
+        let read = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|raw| raw.trim().parse::<usize>().ok())
+                .filter(|value| *value > 0)
+        };
+        Self {
+            max_items: read("SIDECAR_EMBED_BATCH_SIZE")
+                .map_or(self.max_items, |items| items.min(self.max_items)),
+            max_tokens: read("SIDECAR_EMBED_BATCH_TOKENS")
+                .map_or(self.max_tokens, |tokens| tokens.min(self.max_tokens)),
+        }
+    }
+}
+
+/// Units an indexer accumulates before flushing them to the store.
+pub fn embed_flush_units() -> usize {
+    env::var("SIDECAR_EMBED_BATCH_SIZE")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(128)
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    text.len() / 4 + 1
+}
+
+/// Packs `texts` into requests within `limits` and sends each through
+/// `embed`. When a request fails, its inputs are retried one at a time so the
+/// error lands on the input that caused it.
+pub async fn embed_batched<'a, F, Fut>(
+    texts: &'a [String],
+    limits: BatchLimits,
+    embed: F,
+) -> Vec<Result<Vec<f32>, String>>
+where
+    F: Fn(Vec<&'a str>) -> Fut,
+    Fut: Future<Output = Result<Vec<Vec<f32>>, String>>,
+{
+    let mut results: Vec<Result<Vec<f32>, String>> =
+        texts.iter().map(|_| Err(String::new())).collect();
+    let mut batch: Vec<usize> = Vec::new();
+    let mut tokens = 0usize;
+    for (index, text) in texts.iter().enumerate() {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            results[index] = Err("cannot embed empty text".to_string());
+            continue;
+        }
+        let cost = estimate_tokens(trimmed);
+        if !batch.is_empty()
+            && (batch.len() >= limits.max_items || tokens + cost > limits.max_tokens)
+        {
+            send_batch(texts, &batch, &embed, &mut results).await;
+            batch.clear();
+            tokens = 0;
+        }
+        batch.push(index);
+        tokens += cost;
+    }
+    if !batch.is_empty() {
+        send_batch(texts, &batch, &embed, &mut results).await;
+    }
+    results
+}
+
+async fn send_batch<'a, F, Fut>(
+    texts: &'a [String],
+    batch: &[usize],
+    embed: &F,
+    results: &mut [Result<Vec<f32>, String>],
+) where
+    F: Fn(Vec<&'a str>) -> Fut,
+    Fut: Future<Output = Result<Vec<Vec<f32>>, String>>,
+{
+    let inputs = batch.iter().map(|&index| texts[index].trim()).collect();
+    let outcome = embed(inputs).await.and_then(|vectors| {
+        if vectors.len() == batch.len() {
+            Ok(vectors)
+        } else {
+            Err(format!(
+                "expected {} vectors, got {}",
+                batch.len(),
+                vectors.len()
+            ))
+        }
+    });
+    match outcome {
+        Ok(vectors) => {
+            for (&index, vector) in batch.iter().zip(vectors) {
+                results[index] = Ok(vector);
+            }
+        }
+        Err(error) if batch.len() == 1 => results[batch[0]] = Err(error),
+        Err(error) => {
+            eprintln!(
+                "[sidecar:embed] batch of {} failed, retrying one by one: {}",
+                batch.len(),
+                error
+            );
+            for &index in batch {
+                results[index] = embed(vec![texts[index].trim()]).await.and_then(single);
+            }
+        }
+    }
+}
+
+/// The only vector of a one-input request.
+pub fn single(vectors: Vec<Vec<f32>>) -> Result<Vec<f32>, String> {
+    let count = vectors.len();
+    let mut vectors = vectors.into_iter();
+    match (vectors.next(), vectors.next()) {
+        (Some(vector), None) => Ok(vector),
+        _ => Err(format!("expected 1 vector, got {}", count)),
+    }
+}
+
+/// Vectors from an OpenAI-shaped `{"data": [{"index", "embedding"}]}` body,
+/// in input order.
+pub fn vectors_from_data(value: &Value) -> Option<Vec<Vec<f32>>> {
+    let mut items: Vec<&Value> = value.get("data")?.as_array()?.iter().collect();
+    items.sort_by_key(|item| item.get("index").and_then(Value::as_u64));
+    items
+        .into_iter()
+        .map(|item| vector_from_array(item.get("embedding")?))
+        .collect()
+}
+
+pub fn vector_from_array(value: &Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_f64().map(|v| v as f32))
+        .collect()
+}
+
+/// Builds the provider named by `SIDECAR_EMBEDDING_PROVIDER`: `voyage`
+/// (default), `openai` for any OpenAI-compatible `/v1/embeddings` server
+/// (Ollama, llama.cpp, LM Studio, vLLM...) or `cohere`.
+///
+/// Every stored vector has to come from the same model and dimension, so
+/// switching providers means clearing and re-indexing.
+pub fn embedding_client_from_env() -> Result<Arc<dyn EmbeddingClient>, String> {
+    let provider = env::var("SIDECAR_EMBEDDING_PROVIDER")
+        .map(|raw| raw.trim().to_lowercase())
+        .unwrap_or_default();
+    match provider.as_str() {
+        "" | "voyage" => Ok(Arc::new(VoyageClient::from_env()?)),
+        "openai" | "openai-compatible" | "ollama" | "llamacpp" | "llama.cpp" | "lmstudio" => {
+            Ok(Arc::new(OpenAiCompatibleClient::from_env()?))
+        }
+        "cohere" => Ok(Arc::new(CohereClient::from_env()?)),
+        other => Err(format!(
+            "unknown SIDECAR_EMBEDDING_PROVIDER {:?}; expected voyage, openai or cohere",
+            other
+        )),
+    }
+}
+
+/// Optional output dimension from `name`; unset or empty means the model's
+/// default.
+pub fn env_dimensions(name: &str) -> Result<Option<usize>, String> {
+    match env::var(name).map(|raw| raw.trim().to_string()) {
+        Ok(raw) if !raw.is_empty() => raw
+            .parse::<usize>()
+            .ok()
+            .filter(|dims| *dims > 0)
+            .map(Some)
+            .ok_or_else(|| format!("{} must be a positive integer", name)),
+        _ => Ok(None),
+    }
+}
+
+/// Input type from `name`, defaulting to `default`. Set it empty to send none.
+pub fn env_input_type(name: &str, default: &str) -> Option<String> {
+    let value = env::var(name).unwrap_or_else(|_| default.to_string());
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Rejects vectors whose length differs from the configured dimension, which
+/// would otherwise fail later inside the vector index.
+pub fn check_dimensions(
+    vectors: Vec<Vec<f32>>,
+    expected: Option<usize>,
+    provider: &str,
+) -> Result<Vec<Vec<f32>>, String> {
+    let Some(expected) = expected else {
+        return Ok(vectors);
+    };
+    match vectors.iter().find(|vector| vector.len() != expected) {
+        Some(vector) => Err(format!(
+            "{} returned a {}-dimensional vector, expected {}",
+            provider,
+            vector.len(),
+            expected
+        )),
+        None => Ok(vectors),
+    }
+}
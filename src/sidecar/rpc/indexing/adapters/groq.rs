@@ -5,11 +5,23 @@ use reqwest::multipart::{Form, Part};
 use reqwest::Client;
 use serde_json::{json, Map, Value};
 use std::env;
+use std::sync::Arc;
+
+use crate::sidecar::rpc::indexing::adapters::http_policy::{self, HttpPolicy};
+use crate::sidecar::rpc::indexing::model_cache;
+
+const TRANSCRIPTION_MODEL: &str = "whisper-large-v3-turbo";
+/// Request options that shape a transcript, part of its cache key.
+const TRANSCRIPTION_OPTIONS: &str = "temperature=0;verbose_json;word";
+const VISION_MODEL: &str = "meta-llama/llama-4-scout-17b-16e-instruct";
+const FRAME_PROMPT: &str = "You are an expert vision assistant. Provide a concise JSON summary for the provided video frame. Respond with JSON only (no code fences). Use the schema: {\"summary\": \"<1-2 sentences>\", \"objects\": [\"...\"], \"actions\": [\"...\"], \"setting\": \"<location or scene>\", \"quality\": \"<good|low>\"}";
+const IMAGE_PROMPT: &str = "You are an expert vision assistant. Provide a concise JSON summary for the provided image. Respond with JSON only (no code fences). Use the schema: {\"summary\": \"<1-2 sentences>\", \"objects\": [\"...\"], \"actions\": [\"...\"], \"setting\": \"<location or scene>\", \"ocr\": \"<visible text or empty>\", \"quality\": \"<good|low>\"}";
 
 #[derive(Clone)]
 pub struct GroqClient {
     http: Client,
     api_key: String,
+    policy: Arc<HttpPolicy>,
 }
 
 #[async_trait]
@@ -44,6 +56,7 @@ impl GroqClient {
         Ok(Self {
             http: Client::new(),
             api_key,
+            policy: http_policy::policy("groq"),
         })
     }
 
@@ -52,39 +65,49 @@ impl GroqClient {
         chunk_key: &str,
         audio_bytes: Vec<u8>,
     ) -> Result<Value, String> {
+        let cache_key = model_cache::key(
+            model_cache::TRANSCRIPT,
+            "groq",
+            TRANSCRIPTION_MODEL,
+            TRANSCRIPTION_OPTIONS,
+            &audio_bytes,
+        );
+        if let Some(cached) = model_cache::get(&cache_key).await {
+            return Ok(cached);
+        }
+
         let file_name = format!("{}.mp3", chunk_key);
-        let part = Part::bytes(audio_bytes)
-            .file_name(file_name)
-            .mime_str("audio/mpeg")
-            .map_err(|e| e.to_string())?;
-
-        let form = Form::new()
-            .part("file", part)
-            .text("model", "whisper-large-v3-turbo")
-            .text("temperature", "0")
-            .text("response_format", "verbose_json")
-            .text("timestamp_granularities[]", "word");
-
-        let response = self
-            .http
-            .post("https://api.groq.com/openai/v1/audio/transcriptions")
-            .bearer_auth(&self.api_key)
-            .multipart(form)
-            .send()
+        // A multipart body is consumed by sending, so each attempt builds its own.
+        let (status, body) = self
+            .policy
+            .send(|| {
+                let part = Part::bytes(audio_bytes.clone())
+                    .file_name(file_name.clone())
+                    .mime_str("audio/mpeg")
+                    .map_err(|e| e.to_string())?;
+                let form = Form::new()
+                    .part("file", part)
+                    .text("model", TRANSCRIPTION_MODEL)
+                    .text("temperature", "0")
+                    .text("response_format", "verbose_json")
+                    .text("timestamp_granularities[]", "word");
+                Ok(self
+                    .http
+                    .post("https://api.groq.com/openai/v1/audio/transcriptions")
+                    .bearer_auth(&self.api_key)
+                    .multipart(form))
+            })
             .await
             .map_err(|e| format!("Groq transcription request failed: {}", e))?;
 
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .map_err(|e| format!("Groq transcription read failed: {}", e))?;
-
         if !status.is_success() {
             return Err(format!("Groq transcription failed ({}): {}", status, body));
         }
 
-        serde_json::from_str(&body).map_err(|e| format!("Invalid transcription JSON: {}", e))
+        let transcript: Value = serde_json::from_str(&body)
+            .map_err(|e| format!("Invalid transcription JSON: {}", e))?;
+        model_cache::put(&cache_key, &transcript).await;
+        Ok(transcript)
     }
 
     pub async fn summarize_image_bytes(
@@ -92,15 +115,30 @@ impl GroqClient {
         image_id: &str,
         image_bytes: Vec<u8>,
     ) -> Result<Value, String> {
+        // Only the summary is cached; the image id differs between videos
+        // that share a frame.
+        let cache_key = model_cache::key(
+            model_cache::FRAME_SUMMARY,
+            "groq",
+            VISION_MODEL,
+            FRAME_PROMPT,
+            &image_bytes,
+        );
+        if let Some(summary) = model_cache::get(&cache_key).await {
+            return Ok(json!({
+                "image": image_id,
+                "summary": summary
+            }));
+        }
+
         let data_uri = format!("data:image/jpeg;base64,{}", STANDARD.encode(image_bytes));
-        let prompt = "You are an expert vision assistant. Provide a concise JSON summary for the provided video frame. Respond with JSON only (no code fences). Use the schema: {\"summary\": \"<1-2 sentences>\", \"objects\": [\"...\"], \"actions\": [\"...\"], \"setting\": \"<location or scene>\", \"quality\": \"<good|low>\"}";
 
         let payload = json!({
-            "model": "meta-llama/llama-4-scout-17b-16e-instruct",
+            "model": VISION_MODEL,
             "messages": [{
                 "role": "user",
                 "content": [
-                    { "type": "text", "text": prompt },
+                    { "type": "text", "text": FRAME_PROMPT },
                     { "type": "image_url", "image_url": { "url": data_uri } }
                 ]
             }],
@@ -108,21 +146,18 @@ impl GroqClient {
             "temperature": 0.2
         });
 
-        let response = self
-            .http
-            .post("https://api.groq.com/openai/v1/chat/completions")
-            .bearer_auth(&self.api_key)
-            .json(&payload)
-            .send()
+        let (status, body) = self
+            .policy
+            .send(|| {
+                Ok(self
+                    .http
+                    .post("https://api.groq.com/openai/v1/chat/completions")
+                    .bearer_auth(&self.api_key)
+                    .json(&payload))
+            })
             .await
             .map_err(|e| format!("Groq vision request failed: {}", e))?;
 
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .map_err(|e| format!("Groq vision read failed: {}", e))?;
-
         if !status.is_success() {
             return Err(format!("Groq vision failed ({}): {}", status, body));
         }
@@ -155,6 +190,9 @@ impl GroqClient {
             Some(other) => json!({ "summary": other.to_string() }),
             None => json!({ "summary": "" }),
         };
+        if is_cacheable_summary(content, &summary) {
+            model_cache::put(&cache_key, &summary).await;
+        }
 
         Ok(json!({
             "image": image_id,
@@ -168,19 +206,29 @@ impl GroqClient {
         mime_hint: &str,
         image_bytes: Vec<u8>,
     ) -> Result<Value, String> {
+        let cache_key = model_cache::key(
+            model_cache::IMAGE_SUMMARY,
+            "groq",
+            VISION_MODEL,
+            &format!("{}\0{}", mime_hint, IMAGE_PROMPT),
+            &image_bytes,
+        );
+        if let Some(summary) = model_cache::get(&cache_key).await {
+            return Ok(summary);
+        }
+
         let data_uri = format!(
             "data:image/{};base64,{}",
             mime_hint,
             STANDARD.encode(image_bytes)
         );
-        let prompt = "You are an expert vision assistant. Provide a concise JSON summary for the provided image. Respond with JSON only (no code fences). Use the schema: {\"summary\": \"<1-2 sentences>\", \"objects\": [\"...\"], \"actions\": [\"...\"], \"setting\": \"<location or scene>\", \"ocr\": \"<visible text or empty>\", \"quality\": \"<good|low>\"}";
 
         let payload = json!({
-            "model": "meta-llama/llama-4-scout-17b-16e-instruct",
+            "model": VISION_MODEL,
             "messages": [{
                 "role": "user",
                 "content": [
-                    { "type": "text", "text": prompt },
+                    { "type": "text", "text": IMAGE_PROMPT },
                     { "type": "image_url", "image_url": { "url": data_uri } }
                 ]
             }],
@@ -188,21 +236,18 @@ impl GroqClient {
             "temperature": 0.2
         });
 
-        let response = self
-            .http
-            .post("https://api.groq.com/openai/v1/chat/completions")
-            .bearer_auth(&self.api_key)
-            .json(&payload)
-            .send()
+        let (status, body) = self
+            .policy
+            .send(|| {
+                Ok(self
+                    .http
+                    .post("https://api.groq.com/openai/v1/chat/completions")
+                    .bearer_auth(&self.api_key)
+                    .json(&payload))
+            })
             .await
             .map_err(|e| format!("Groq image vision request failed: {}", e))?;
 
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .map_err(|e| format!("Groq image vision read failed: {}", e))?;
-
         if !status.is_success() {
             return Err(format!("Groq image vision failed ({}): {}", status, body));
         }
@@ -249,6 +294,9 @@ impl GroqClient {
                 "quality": "",
             }),
         };
+        if is_cacheable_summary(content, &summary) {
+            model_cache::put(&cache_key, &summary).await;
+        }
 
         Ok(summary)
     }
@@ -282,6 +330,16 @@ impl TranscriptionClient for GroqClient {
     }
 }
 
+/// Only summaries read from a text reply and saying something are cached;
+/// fallbacks for odd or empty replies are asked for again next time.
+fn is_cacheable_summary(content: Option<&Value>, summary: &Value) -> bool {
+    matches!(content, Some(Value::String(_) | Value::Array(_)))
+        && summary
+            .get("summary")
+            .and_then(Value::as_str)
+            .is_some_and(|text| !text.trim().is_empty())
+}
+
 fn normalize_summary_content(content: &str) -> Value {
     let mut text = content.trim().to_string();
 
@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 
@@ -34,3 +35,30 @@ impl PathHasher for Sha256PathHasher {
         .map_err(|e| e.to_string())?
     }
 }
+
+/// Serves hashes already computed during reconciliation so indexers don't
+/// read the same file twice, falling back to SHA-256 for anything else.
+#[derive(Debug, Default)]
+pub struct CachedPathHasher {
+    known: HashMap<String, String>,
+    inner: Sha256PathHasher,
+}
+
+impl CachedPathHasher {
+    pub fn new(known: HashMap<String, String>) -> Self {
+        Self {
+            known,
+            inner: Sha256PathHasher,
+        }
+    }
+}
+
+#[async_trait]
+impl PathHasher for CachedPathHasher {
+    async fn compute_file_hash(&self, path: &str) -> Result<String, String> {
+        if let Some(hash) = self.known.get(path) {
+            return Ok(hash.clone());
+        }
+        self.inner.compute_file_hash(path).await
+    }
+}
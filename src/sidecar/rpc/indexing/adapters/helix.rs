@@ -3,20 +3,27 @@ use chrono::{SecondsFormat, Utc};
 use helix_rs::{HelixDB, HelixDBClient};
 use serde_json::{json, Value};
 use std::env;
-use std::sync::Mutex;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
+use crate::sidecar::rpc::indexing::adapters::embeddings::{
+    embedding_client_from_env, EmbeddingClient,
+};
 use crate::sidecar::rpc::indexing::adapters::store::{
-    ExistingFileRecord, ExistingImageRecord, ExistingVideoRecord, ImageIndexStore, TextIndexStore,
-    VideoIndexStore,
+    AssetCatalogStore, EmbeddingUnit, ExistingFileRecord, ExistingImageRecord, ExistingVideoRecord,
+    FileStamp, ImageIndexStore, IndexedAsset, TextIndexStore, VideoIndexStore,
 };
-use crate::sidecar::rpc::indexing::adapters::voyage::{EmbeddingClient, VoyageClient};
+use crate::sidecar::rpc::indexing::collect::extension_of;
+use crate::sidecar::rpc::indexing::lexical;
+use crate::sidecar::rpc::indexing::model_cache::{self, CacheKey};
+use crate::sidecar::rpc::indexing::reconcile::is_under_root;
 
 #[derive(Debug)]
 pub struct HelixTextStore {
     endpoint: String,
     port: u16,
     api_key: Option<String>,
-    voyage: Mutex<Option<VoyageClient>>,
+    embedder: Mutex<Option<Arc<dyn EmbeddingClient>>>,
 }
 
 impl HelixTextStore {
@@ -34,7 +41,7 @@ impl HelixTextStore {
             endpoint,
             port,
             api_key,
-            voyage: Mutex::new(None),
+            embedder: Mutex::new(None),
         })
     }
 
@@ -73,6 +80,68 @@ impl HelixTextStore {
         None
     }
 
+    fn asset_payload(content_hash: &str, kind: &str, path: &str) -> Value {
+        let stamp = FileStamp::read(path).unwrap_or_default();
+        json!({
+            "content_hash": content_hash,
+            "kind": kind,
+            "path": path,
+            "extension": extension_of(Path::new(path)),
+            "modified_at": stamp.modified_at,
+            "size_bytes": stamp.size_bytes,
+            "indexed_at": Utc::now().timestamp(),
+        })
+    }
+
+    /// Creates (or re-points) an asset node and mirrors it into the lexical index.
+    async fn create_asset(&self, content_hash: &str, kind: &str, path: &str) -> Result<(), String> {
+        let payload = Self::asset_payload(content_hash, kind, path);
+        let client = self.client();
+        let _: Value = client
+            .query("CreateAsset", &payload)
+            .await
+            .map_err(|e| e.to_string())?;
+        lexical::set_asset(
+            content_hash,
+            kind,
+            path,
+            payload["modified_at"].as_i64(),
+            payload["indexed_at"].as_i64(),
+        );
+        Ok(())
+    }
+
+    fn parse_indexed_asset(value: &Value) -> Option<IndexedAsset> {
+        let content_hash = value.get("content_hash")?.as_str()?.to_string();
+        let path = value.get("path")?.as_str()?.to_string();
+        let kind = value
+            .get("kind")
+            .and_then(Value::as_str)
+            .unwrap_or("file")
+            .to_string();
+        let stamp = match (
+            value.get("modified_at").and_then(Value::as_i64),
+            value.get("size_bytes").and_then(Value::as_i64),
+        ) {
+            (Some(modified_at), Some(size_bytes)) if size_bytes >= 0 => Some(FileStamp {
+                modified_at,
+                size_bytes,
+            }),
+            _ => None,
+        };
+        let indexed_at = value
+            .get("indexed_at")
+            .and_then(Value::as_i64)
+            .filter(|at| *at > 0);
+        Some(IndexedAsset {
+            content_hash,
+            kind,
+            path,
+            stamp,
+            indexed_at,
+        })
+    }
+
     fn current_timestamp_rfc3339() -> String {
         Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
     }
@@ -106,31 +175,175 @@ impl HelixTextStore {
         false
     }
 
-    async fn build_document_vector(&self, content: &str) -> Result<Vec<f64>, String> {
-        let voyage = {
-            let mut slot = self
-                .voyage
-                .lock()
-                .map_err(|e| format!("voyage client lock poisoned: {}", e))?;
-            match slot.as_mut() {
-                Some(client) => client.clone(),
-                None => {
-                    let client = VoyageClient::from_env()?;
-                    *slot = Some(client.clone());
-                    client
-                }
+    fn embedder(&self) -> Result<Arc<dyn EmbeddingClient>, String> {
+        let mut slot = self
+            .embedder
+            .lock()
+            .map_err(|e| format!("embedding client lock poisoned: {}", e))?;
+        match slot.as_ref() {
+            Some(client) => Ok(Arc::clone(client)),
+            None => {
+                let client = embedding_client_from_env()?;
+                *slot = Some(Arc::clone(&client));
+                Ok(client)
             }
+        }
+    }
+
+    async fn store_embedding(
+        &self,
+        asset_kind: &str,
+        unit: &EmbeddingUnit,
+        vector: Vec<f32>,
+    ) -> Result<(), String> {
+        let vector: Vec<f64> = vector.into_iter().map(f64::from).collect();
+        let payload = json!({
+            "content_hash": unit.content_hash,
+            "asset_kind": asset_kind,
+            "unit_kind": unit.unit_kind,
+            "unit_key": unit.unit_key,
+            "content": unit.content,
+            "vector": vector,
+            "created_at": Self::current_timestamp_rfc3339(),
+        });
+        let client = self.client();
+        let _: Value = client
+            .query("CreateAssetEmbeddingByHash", &payload)
+            .await
+            .map_err(|e| e.to_string())?;
+        lexical::add_unit(
+            &unit.content_hash,
+            &unit.unit_kind,
+            &unit.unit_key,
+            &unit.content,
+        );
+        Ok(())
+    }
+
+    fn embedding_cache_key(model_id: &str, content: &str) -> CacheKey {
+        let provider = model_id.split(':').next().unwrap_or(model_id);
+        model_cache::key(
+            model_cache::EMBEDDING,
+            provider,
+            model_id,
+            "",
+            content.as_bytes(),
+        )
+    }
+
+    async fn create_embedding(&self, asset_kind: &str, unit: EmbeddingUnit) -> Result<(), String> {
+        let embedder = self.embedder()?;
+        let key = Self::embedding_cache_key(&embedder.document_model_id(), &unit.content);
+        let vector = match model_cache::get_vector(&key).await {
+            Some(vector) => vector,
+            None => {
+                let vector = embedder.embed_document(&unit.content).await?;
+                model_cache::put_vector(&key, &vector).await;
+                vector
+            }
+        };
+        self.store_embedding(asset_kind, &unit, vector).await
+    }
+
+    /// Embeds all units in as few provider requests as possible, then stores
+    /// each one that got a vector.
+    async fn create_embeddings_batch(
+        &self,
+        asset_kind: &str,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        let embedder = match self.embedder() {
+            Ok(embedder) => embedder,
+            Err(error) => return units.iter().map(|_| Err(error.clone())).collect(),
         };
-        let vector = voyage.embed_document(content).await?;
-        Ok(vector.into_iter().map(f64::from).collect())
+        // Only units the model cache has not seen go to the provider.
+        let model_id = embedder.document_model_id();
+        let keys: Vec<CacheKey> = units
+            .iter()
+            .map(|unit| Self::embedding_cache_key(&model_id, &unit.content))
+            .collect();
+        let mut vectors: Vec<Option<Result<Vec<f32>, String>>> = model_cache::get_vectors(&keys)
+            .await
+            .into_iter()
+            .map(|vector| vector.map(Ok))
+            .collect();
+        let missing: Vec<usize> = (0..units.len()).filter(|&i| vectors[i].is_none()).collect();
+        if !missing.is_empty() {
+            let texts: Vec<String> = missing.iter().map(|&i| units[i].content.clone()).collect();
+            let embedded = embedder.embed_documents(&texts).await;
+            for (i, vector) in missing.into_iter().zip(embedded) {
+                if let Ok(vector) = &vector {
+                    model_cache::put_vector(&keys[i], vector).await;
+                }
+                vectors[i] = Some(vector);
+            }
+        }
+
+        let mut results = Vec::with_capacity(units.len());
+        for (unit, vector) in units.iter().zip(vectors) {
+            let vector = vector.unwrap_or_else(|| Err("embedding missing from batch".to_string()));
+            results.push(match vector {
+                Ok(vector) => self.store_embedding(asset_kind, unit, vector).await,
+                Err(error) => Err(error),
+            });
+        }
+        results
     }
 
     pub async fn clear_search_index(&self) -> Result<Value, String> {
         let client = self.client();
-        client
+        let result = client
             .query("ClearSearchIndex", &json!({}))
             .await
-            .map_err(|e| e.to_string())
+            .map_err(|e| e.to_string())?;
+        lexical::clear();
+        Ok(result)
+    }
+
+    /// Mirrors assets under `root` that were indexed before the lexical index
+    /// existed (or while it was lost) from their stored embedding units.
+    pub async fn backfill_lexical_index(&self, root: &str) -> Result<usize, String> {
+        let missing: Vec<IndexedAsset> = self
+            .list_assets()
+            .await?
+            .into_iter()
+            .filter(|asset| is_under_root(root, &asset.path))
+            .filter(|asset| !lexical::has_asset(&asset.content_hash))
+            .collect();
+
+        let client = self.client();
+        for asset in &missing {
+            let payload = json!({ "content_hash": asset.content_hash });
+            let result: Value = client
+                .query("GetAssetEmbeddingsByHash", &payload)
+                .await
+                .map_err(|e| e.to_string())?;
+            let units = result
+                .get("embeddings")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            for unit in &units {
+                let field = |name: &str| unit.get(name).and_then(Value::as_str).unwrap_or_default();
+                lexical::add_unit(
+                    &asset.content_hash,
+                    field("unit_kind"),
+                    field("unit_key"),
+                    field("content"),
+                );
+            }
+            lexical::set_asset(
+                &asset.content_hash,
+                &asset.kind,
+                &asset.path,
+                asset.stamp.map(|stamp| stamp.modified_at),
+                asset.indexed_at,
+            );
+        }
+        if !missing.is_empty() {
+            lexical::flush();
+        }
+        Ok(missing.len())
     }
 }
 
@@ -163,17 +376,7 @@ impl TextIndexStore for HelixTextStore {
         kind: &str,
         path: &str,
     ) -> Result<(), String> {
-        let payload = json!({
-            "content_hash": content_hash,
-            "kind": kind,
-            "path": path,
-        });
-        let client = self.client();
-        let _: Value = client
-            .query("CreateAsset", &payload)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.create_asset(content_hash, kind, path).await
     }
 
     async fn create_file_asset_embeddings(
@@ -183,21 +386,18 @@ impl TextIndexStore for HelixTextStore {
         unit_key: &str,
         content: &str,
     ) -> Result<(), String> {
-        let vector = self.build_document_vector(content).await?;
-        let payload = json!({
-            "content_hash": content_hash,
-            "unit_kind": unit_kind,
-            "unit_key": unit_key,
-            "content": content,
-            "vector": vector,
-            "created_at": Self::current_timestamp_rfc3339(),
-        });
-        let client = self.client();
-        let _: Value = client
-            .query("CreateAssetEmbeddingByHash", &payload)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.create_embedding(
+            "file",
+            EmbeddingUnit::new(content_hash, unit_kind, unit_key, content),
+        )
+        .await
+    }
+
+    async fn create_file_asset_embeddings_batch(
+        &self,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        self.create_embeddings_batch("file", units).await
     }
 }
 
@@ -230,17 +430,7 @@ impl ImageIndexStore for HelixTextStore {
         kind: &str,
         path: &str,
     ) -> Result<(), String> {
-        let payload = json!({
-            "content_hash": content_hash,
-            "kind": kind,
-            "path": path,
-        });
-        let client = self.client();
-        let _: Value = client
-            .query("CreateAsset", &payload)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.create_asset(content_hash, kind, path).await
     }
 
     async fn create_image_asset_embeddings(
@@ -250,21 +440,18 @@ impl ImageIndexStore for HelixTextStore {
         unit_key: &str,
         content: &str,
     ) -> Result<(), String> {
-        let vector = self.build_document_vector(content).await?;
-        let payload = json!({
-            "content_hash": content_hash,
-            "unit_kind": unit_kind,
-            "unit_key": unit_key,
-            "content": content,
-            "vector": vector,
-            "created_at": Self::current_timestamp_rfc3339(),
-        });
-        let client = self.client();
-        let _: Value = client
-            .query("CreateAssetEmbeddingByHash", &payload)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.create_embedding(
+            "image",
+            EmbeddingUnit::new(content_hash, unit_kind, unit_key, content),
+        )
+        .await
+    }
+
+    async fn create_image_asset_embeddings_batch(
+        &self,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        self.create_embeddings_batch("image", units).await
     }
 }
 
@@ -315,17 +502,7 @@ impl VideoIndexStore for HelixTextStore {
         kind: &str,
         path: &str,
     ) -> Result<(), String> {
-        let payload = json!({
-            "content_hash": content_hash,
-            "kind": kind,
-            "path": path,
-        });
-        let client = self.client();
-        let _: Value = client
-            .query("CreateAsset", &payload)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.create_asset(content_hash, kind, path).await
     }
 
     async fn create_video_asset_embeddings(
@@ -335,20 +512,94 @@ impl VideoIndexStore for HelixTextStore {
         unit_key: &str,
         content: &str,
     ) -> Result<(), String> {
-        let vector = self.build_document_vector(content).await?;
+        self.create_embedding(
+            "video",
+            EmbeddingUnit::new(content_hash, unit_kind, unit_key, content),
+        )
+        .await
+    }
+
+    async fn create_video_asset_embeddings_batch(
+        &self,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        self.create_embeddings_batch("video", units).await
+    }
+}
+
+#[async_trait]
+impl AssetCatalogStore for HelixTextStore {
+    async fn list_assets(&self) -> Result<Vec<IndexedAsset>, String> {
+        let client = self.client();
+        let result: Value = client
+            .query("ListAssets", &json!({}))
+            .await
+            .map_err(|e| e.to_string())
+            .or_else(|error| {
+                if Self::is_not_found_error(&error) {
+                    Ok(Value::Null)
+                } else {
+                    Err(error)
+                }
+            })?;
+
+        Ok(result
+            .get("assets")
+            .and_then(Value::as_array)
+            .map(|assets| {
+                assets
+                    .iter()
+                    .filter_map(Self::parse_indexed_asset)
+                    .collect::<Vec<IndexedAsset>>()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn asset_is_complete(&self, asset: &IndexedAsset) -> Result<bool, String> {
+        if asset.kind == "video" {
+            return self.video_asset_has_embeddings(&asset.content_hash).await;
+        }
+        Ok(true)
+    }
+
+    async fn update_asset_location(
+        &self,
+        content_hash: &str,
+        path: &str,
+        stamp: FileStamp,
+    ) -> Result<(), String> {
         let payload = json!({
             "content_hash": content_hash,
-            "unit_kind": unit_kind,
-            "unit_key": unit_key,
-            "content": content,
-            "vector": vector,
-            "created_at": Self::current_timestamp_rfc3339(),
+            "path": path,
+            "extension": extension_of(Path::new(path)),
+            "modified_at": stamp.modified_at,
+            "size_bytes": stamp.size_bytes,
         });
         let client = self.client();
         let _: Value = client
-            .query("CreateAssetEmbeddingByHash", &payload)
+            .query("UpdateAssetLocation", &payload)
             .await
             .map_err(|e| e.to_string())?;
+        lexical::set_asset(content_hash, "", path, Some(stamp.modified_at), None);
+        Ok(())
+    }
+
+    async fn delete_asset(&self, content_hash: &str) -> Result<(), String> {
+        let payload = json!({ "content_hash": content_hash });
+        let client = self.client();
+        client
+            .query::<_, Value>("DeleteAssetByHash", &payload)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+            .or_else(|error| {
+                if Self::is_not_found_error(&error) {
+                    Ok(())
+                } else {
+                    Err(error)
+                }
+            })?;
+        lexical::remove_asset(content_hash);
         Ok(())
     }
 }
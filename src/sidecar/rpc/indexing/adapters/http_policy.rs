@@ -0,0 +1,388 @@
+//! Retry, rate limiting and circuit breaking for model provider calls.
+//!
+//! Each provider has one process-wide policy, so indexing jobs and search
+//! requests share its request budget and see the same outage state.
+//! Transport errors, 429s and 5xx responses are retried with jittered
+//! exponential backoff, honouring `Retry-After`. Enough consecutive failures
+//! open the provider's circuit: calls then fail fast until a cooldown passes,
+//! then a single trial call decides whether it closes or opens again.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_RETRIES: u32 = 4;
+const DEFAULT_BACKOFF_MS: u64 = 500;
+const DEFAULT_BACKOFF_MAX_MS: u64 = 30_000;
+const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+const DEFAULT_BREAKER_COOLDOWN_MS: u64 = 30_000;
+
+/// A provider whose circuit is open or on trial, as shown on index jobs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderOutage {
+    pub provider: String,
+    /// `open` while calls fail fast, `half_open` while a trial call decides.
+    pub state: String,
+    pub since: String,
+    pub consecutive_failures: u32,
+    pub last_error: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PolicyConfig {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    breaker_threshold: u32,
+    breaker_cooldown: Duration,
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    env::var(name)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+}
+
+impl PolicyConfig {
+    fn from_env() -> Self {
+        Self {
+            max_retries: env_u64("SIDECAR_HTTP_MAX_RETRIES")
+                .map_or(DEFAULT_MAX_RETRIES, |retries| retries as u32),
+            base_delay: Duration::from_millis(
+                env_u64("SIDECAR_HTTP_BACKOFF_MS").unwrap_or(DEFAULT_BACKOFF_MS),
+            ),
+            max_delay: Duration::from_millis(
+                env_u64("SIDECAR_HTTP_BACKOFF_MAX_MS").unwrap_or(DEFAULT_BACKOFF_MAX_MS),
+            ),
+            breaker_threshold: env_u64("SIDECAR_HTTP_BREAKER_THRESHOLD")
+                .map_or(DEFAULT_BREAKER_THRESHOLD, |threshold| threshold as u32)
+                .max(1),
+            breaker_cooldown: Duration::from_millis(
+                env_u64("SIDECAR_HTTP_BREAKER_COOLDOWN_MS").unwrap_or(DEFAULT_BREAKER_COOLDOWN_MS),
+            ),
+        }
+    }
+}
+
+/// Requests per minute with a small burst; zero means unlimited.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u64) -> Self {
+        let capacity = (per_minute as f64 / 10.0).clamp(1.0, 10.0);
+        Self {
+            capacity,
+            tokens: capacity,
+            per_sec: per_minute as f64 / 60.0,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or says how long until one is free.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        if self.per_sec <= 0.0 {
+            return None;
+        }
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    half_open: bool,
+    since: Option<DateTime<Utc>>,
+    last_error: String,
+}
+
+pub struct HttpPolicy {
+    provider: &'static str,
+    config: PolicyConfig,
+    bucket: Mutex<TokenBucket>,
+    breaker: Mutex<Breaker>,
+}
+
+enum Attempt {
+    Response(StatusCode, String),
+    Retry(String, Option<Duration>),
+}
+
+static POLICIES: OnceLock<Mutex<HashMap<&'static str, Arc<HttpPolicy>>>> = OnceLock::new();
+
+fn policies() -> &'static Mutex<HashMap<&'static str, Arc<HttpPolicy>>> {
+    POLICIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Default request budget: Groq's free tier allows about 30 requests a
+/// minute; Voyage's paid tier far more. Others are unlimited unless set.
+fn default_requests_per_minute(provider: &str) -> u64 {
+    match provider {
+        "groq" => 30,
+        "voyage" => 300,
+        _ => 0,
+    }
+}
+
+/// The shared policy for `provider`, created on first use. The budget comes
+/// from `SIDECAR_<PROVIDER>_REQUESTS_PER_MINUTE`.
+pub fn policy(provider: &'static str) -> Arc<HttpPolicy> {
+    let mut policies = policies()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let policy = policies.entry(provider).or_insert_with(|| {
+        let per_minute = env_u64(&format!(
+            "SIDECAR_{}_REQUESTS_PER_MINUTE",
+            provider.to_ascii_uppercase()
+        ))
+        .unwrap_or_else(|| default_requests_per_minute(provider));
+        Arc::new(HttpPolicy {
+            provider,
+            config: PolicyConfig::from_env(),
+            bucket: Mutex::new(TokenBucket::new(per_minute)),
+            breaker: Mutex::new(Breaker::default()),
+        })
+    });
+    Arc::clone(policy)
+}
+
+/// Providers currently failing fast or on trial.
+pub fn outages() -> Vec<ProviderOutage> {
+    let policies: Vec<Arc<HttpPolicy>> = policies()
+        .lock()
+        .map(|policies| policies.values().cloned().collect())
+        .unwrap_or_default();
+    let mut outages: Vec<ProviderOutage> = policies
+        .iter()
+        .filter_map(|policy| policy.outage())
+        .collect();
+    outages.sort_by(|a, b| a.provider.cmp(&b.provider));
+    outages
+}
+
+/// `Retry-After` as delay seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let raw = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = raw.parse::<f64>() {
+        // Negative, NaN and out-of-range values are ignored rather than trusted.
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = DateTime::parse_from_rfc2822(raw).ok()?.with_timezone(&Utc);
+    Some((at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Uniform in [0, 1), from the per-instance random keys of `RandomState`.
+fn jitter() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+fn short(body: &str) -> &str {
+    let end = body
+        .char_indices()
+        .nth(300)
+        .map_or(body.len(), |(index, _)| index);
+    &body[..end]
+}
+
+impl HttpPolicy {
+    fn lock_breaker(&self) -> std::sync::MutexGuard<'_, Breaker> {
+        self.breaker
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn outage(&self) -> Option<ProviderOutage> {
+        let breaker = self.lock_breaker();
+        let state = if breaker.half_open {
+            "half_open"
+        } else if breaker.open_until.is_some() {
+            "open"
+        } else {
+            return None;
+        };
+        Some(ProviderOutage {
+            provider: self.provider.to_string(),
+            state: state.to_string(),
+            since: breaker
+                .since
+                .map(|since| since.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default(),
+            consecutive_failures: breaker.consecutive_failures,
+            last_error: breaker.last_error.clone(),
+        })
+    }
+
+    /// Fails fast while the circuit is open. After the cooldown one call goes
+    /// through as a trial and the rest keep failing fast until it reports;
+    /// a trial that never does (its caller gave up) is replaced after another
+    /// cooldown.
+    fn admit(&self) -> Result<(), String> {
+        let mut breaker = self.lock_breaker();
+        let Some(until) = breaker.open_until else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if now < until {
+            if breaker.half_open {
+                return Err(format!(
+                    "{} is unavailable after {} consecutive failures; a trial call is checking whether it recovered (last error: {})",
+                    self.provider, breaker.consecutive_failures, breaker.last_error
+                ));
+            }
+            return Err(format!(
+                "{} is unavailable after {} consecutive failures; retrying in {}s (last error: {})",
+                self.provider,
+                breaker.consecutive_failures,
+                (until - now).as_secs() + 1,
+                breaker.last_error
+            ));
+        }
+        breaker.open_until = Some(now + self.config.breaker_cooldown);
+        breaker.half_open = true;
+        Ok(())
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.lock_breaker();
+        if breaker.since.is_some() {
+            eprintln!(
+                "[sidecar:http] {} recovered after {} consecutive failures",
+                self.provider, breaker.consecutive_failures
+            );
+        }
+        *breaker = Breaker::default();
+    }
+
+    /// Counts a failed attempt and opens the circuit at the threshold, or at
+    /// once when a trial call fails. Returns whether it is now open.
+    fn record_failure(&self, error: &str) -> bool {
+        let mut breaker = self.lock_breaker();
+        breaker.consecutive_failures += 1;
+        breaker.last_error = short(error).to_string();
+        if !breaker.half_open && breaker.consecutive_failures < self.config.breaker_threshold {
+            return false;
+        }
+        if breaker.since.is_none() {
+            breaker.since = Some(Utc::now());
+            eprintln!(
+                "[sidecar:http] {} circuit open after {} consecutive failures: {}",
+                self.provider, breaker.consecutive_failures, breaker.last_error
+            );
+        }
+        breaker.half_open = false;
+        breaker.open_until = Some(Instant::now() + self.config.breaker_cooldown);
+        true
+    }
+
+    async fn acquire(&self) {
+        loop {
+            let wait = self
+                .bucket
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .take(Instant::now());
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Half to all of the capped exponential delay for `attempt`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .config
+            .base_delay
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.config.max_delay);
+        exp.mul_f64(0.5 + jitter() / 2.0)
+    }
+
+    async fn attempt(&self, request: RequestBuilder) -> Attempt {
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(error) => return Attempt::Retry(error.to_string(), None),
+        };
+        let status = response.status();
+        let wait = retry_after(response.headers());
+        match response.text().await {
+            Ok(body) if is_retryable(status) => {
+                Attempt::Retry(format!("{}: {}", status, short(&body)), wait)
+            }
+            Ok(body) => Attempt::Response(status, body),
+            Err(error) => Attempt::Retry(format!("response read failed: {}", error), None),
+        }
+    }
+
+    /// Sends the request `build` makes, retrying retryable failures. Returns
+    /// the final status and body for the caller to interpret; `Err` only when
+    /// no response arrived or the circuit is open.
+    pub async fn send<F>(&self, build: F) -> Result<(StatusCode, String), String>
+    where
+        F: Fn() -> Result<RequestBuilder, String>,
+    {
+        let mut attempt = 0u32;
+        loop {
+            self.admit()?;
+            self.acquire().await;
+            let (error, wait) = match self.attempt(build()?).await {
+                Attempt::Response(status, body) => {
+                    self.record_success();
+                    return Ok((status, body));
+                }
+                Attempt::Retry(error, wait) => (error, wait),
+            };
+            let open = self.record_failure(&error);
+            if open || attempt >= self.config.max_retries {
+                return Err(format!(
+                    "{} failed after {} attempt(s): {}",
+                    self.provider,
+                    attempt + 1,
+                    error
+                ));
+            }
+            let delay = wait.map_or_else(
+                || self.backoff(attempt),
+                |wait| wait.min(self.config.max_delay),
+            );
+            eprintln!(
+                "[sidecar:http] {} attempt {} failed, retrying in {}ms: {}",
+                self.provider,
+                attempt + 1,
+                delay.as_millis(),
+                short(&error)
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
@@ -1,5 +1,10 @@
+pub mod cohere;
+pub mod embeddings;
 pub mod groq;
 pub mod hash;
 pub mod helix;
+pub mod http_policy;
+pub mod openai;
+pub mod rerank;
 pub mod store;
 pub mod voyage;
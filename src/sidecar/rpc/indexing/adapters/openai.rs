@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::sidecar::rpc::indexing::adapters::embeddings::{
+    check_dimensions, embed_batched, env_dimensions, single, vectors_from_data, BatchLimits,
+    EmbeddingClient,
+};
+use crate::sidecar::rpc::indexing::adapters::http_policy::{self, HttpPolicy};
+
+/// Any server speaking the OpenAI `/v1/embeddings` API: OpenAI itself, or a
+/// local Ollama, llama.cpp server, LM Studio or vLLM.
+#[derive(Clone)]
+pub struct OpenAiCompatibleClient {
+    http: Client,
+    policy: Arc<HttpPolicy>,
+    api_key: Option<String>,
+    base_url: String,
+    model: String,
+    dimensions: Option<usize>,
+    query_prefix: String,
+    document_prefix: String,
+}
+
+impl fmt::Debug for OpenAiCompatibleClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenAiCompatibleClient")
+            .field("http", &self.http)
+            .field("api_key", &self.api_key.as_ref().map(|_| "[REDACTED]"))
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .field("dimensions", &self.dimensions)
+            .finish()
+    }
+}
+
+impl OpenAiCompatibleClient {
+    pub fn from_env() -> Result<Self, String> {
+        // Local servers usually take no key, so a missing one is not an error.
+        let api_key = env::var("OPENAI_EMBED_API_KEY")
+            .or_else(|_| env::var("OPENAI_API_KEY"))
+            .ok()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty());
+
+        let base_url = env::var("OPENAI_EMBED_BASE_URL")
+            .map(|url| url.trim().to_string())
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+        let model = env::var("OPENAI_EMBED_MODEL")
+            .map(|model| model.trim().to_string())
+            .ok()
+            .filter(|model| !model.is_empty())
+            .unwrap_or_else(|| "text-embedding-3-small".to_string());
+
+        // Instruction-tuned local models (nomic, e5, bge) expect the role as
+        // a text prefix because the API has no input type field.
+        Ok(Self {
+            http: Client::new(),
+            policy: http_policy::policy("openai"),
+            api_key,
+            base_url,
+            model,
+            dimensions: env_dimensions("OPENAI_EMBED_DIMENSIONS")?,
+            query_prefix: env::var("OPENAI_EMBED_QUERY_PREFIX").unwrap_or_default(),
+            document_prefix: env::var("OPENAI_EMBED_DOCUMENT_PREFIX").unwrap_or_default(),
+        })
+    }
+
+    async fn embed_with_prefix(
+        &self,
+        texts: Vec<&str>,
+        prefix: &str,
+    ) -> Result<Vec<Vec<f32>>, String> {
+        if texts.iter().any(|text| text.trim().is_empty()) {
+            return Err("cannot embed empty text".to_string());
+        }
+
+        let url = format!("{}/embeddings", self.base_url.trim_end_matches('/'));
+        let inputs: Vec<String> = texts
+            .iter()
+            .map(|text| format!("{}{}", prefix, text.trim()))
+            .collect();
+        let mut payload = json!({
+            "input": inputs,
+            "model": self.model,
+        });
+        if let Some(dimensions) = self.dimensions {
+            payload["dimensions"] = json!(dimensions);
+        }
+
+        let (status, body) = self
+            .policy
+            .send(|| {
+                let request = self.http.post(&url).json(&payload);
+                Ok(match &self.api_key {
+                    Some(api_key) => request.bearer_auth(api_key),
+                    None => request,
+                })
+            })
+            .await
+            .map_err(|e| format!("Embeddings request to {} failed: {}", self.base_url, e))?;
+
+        if !status.is_success() {
+            return Err(format!("Embeddings failed ({}): {}", status, body));
+        }
+
+        let parsed: Value =
+            serde_json::from_str(&body).map_err(|e| format!("Invalid embeddings JSON: {}", e))?;
+
+        let vectors = vectors_from_data(&parsed).ok_or_else(|| {
+            format!(
+                "Embeddings response missing vector for model '{}': {}",
+                self.model, body
+            )
+        })?;
+        check_dimensions(vectors, self.dimensions, "Embeddings server")
+    }
+}
+
+#[async_trait]
+impl EmbeddingClient for OpenAiCompatibleClient {
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>, String> {
+        self.embed_with_prefix(vec![text], &self.document_prefix)
+            .await
+            .and_then(single)
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>, String> {
+        self.embed_with_prefix(vec![text], &self.query_prefix)
+            .await
+            .and_then(single)
+    }
+
+    async fn embed_documents(&self, texts: &[String]) -> Vec<Result<Vec<f32>, String>> {
+        // OpenAI allows 2048 inputs per request; local servers often fewer,
+        // which SIDECAR_EMBED_BATCH_SIZE narrows further.
+        let limits = BatchLimits {
+            max_items: 256,
+            max_tokens: 100_000,
+        }
+        .with_env();
+        embed_batched(texts, limits, |batch| {
+            self.embed_with_prefix(batch, &self.document_prefix)
+        })
+        .await
+    }
+
+    fn query_model_id(&self) -> String {
+        format!(
+            "openai:{}:{}:{}:{}",
+            self.base_url.trim_end_matches('/'),
+            self.model,
+            self.dimensions.unwrap_or_default(),
+            self.query_prefix
+        )
+    }
+
+    fn document_model_id(&self) -> String {
+        format!(
+            "openai:{}:{}:{}:{}",
+            self.base_url.trim_end_matches('/'),
+            self.model,
+            self.dimensions.unwrap_or_default(),
+            self.document_prefix
+        )
+    }
+}
@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::sidecar::rpc::indexing::adapters::http_policy::{self, HttpPolicy};
+
+/// Rescores candidate texts against a query.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Short name reported in search responses.
+    fn name(&self) -> &'static str;
+    /// Relevance of each document to `query` in `[0, 1]`, parallel to
+    /// `documents`. Higher is better.
+    async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f64>, String>;
+}
+
+#[derive(Clone)]
+pub struct VoyageReranker {
+    http: Client,
+    policy: Arc<HttpPolicy>,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl fmt::Debug for VoyageReranker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VoyageReranker")
+            .field("http", &self.http)
+            .field("api_key", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+impl VoyageReranker {
+    pub fn from_env() -> Result<Self, String> {
+        let api_key = env::var("VOYAGE_API_KEY")
+            .map_err(|_| "VOYAGE_API_KEY not set".to_string())?
+            .trim()
+            .to_string();
+        if api_key.is_empty() {
+            return Err("VOYAGE_API_KEY is empty".to_string());
+        }
+
+        let base_url = env::var("VOYAGE_API_BASE_URL")
+            .unwrap_or_else(|_| "https://api.voyageai.com/v1".to_string());
+        let model = env::var("VOYAGE_RERANK_MODEL").unwrap_or_else(|_| "rerank-2".to_string());
+
+        Ok(Self {
+            http: Client::new(),
+            policy: http_policy::policy("voyage"),
+            api_key,
+            base_url,
+            model,
+        })
+    }
+
+    fn extract_scores(value: &Value, count: usize) -> Option<Vec<f64>> {
+        // Results come back sorted by relevance; `index` points into the input.
+        let mut scores = vec![0.0; count];
+        let mut seen = 0;
+        for item in value.get("data")?.as_array()? {
+            let index = item.get("index")?.as_u64()? as usize;
+            let score = item.get("relevance_score")?.as_f64()?;
+            *scores.get_mut(index)? = score;
+            seen += 1;
+        }
+        (seen == count).then_some(scores)
+    }
+}
+
+#[async_trait]
+impl Reranker for VoyageReranker {
+    fn name(&self) -> &'static str {
+        "voyage"
+    }
+
+    async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f64>, String> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = query.trim();
+        if query.is_empty() {
+            return Err("cannot rerank against an empty query".to_string());
+        }
+
+        let url = format!("{}/rerank", self.base_url.trim_end_matches('/'));
+        let payload = json!({
+            "query": query,
+            "documents": documents,
+            "model": self.model,
+            "truncation": true,
+        });
+
+        let (status, body) = self
+            .policy
+            .send(|| {
+                Ok(self
+                    .http
+                    .post(&url)
+                    .bearer_auth(&self.api_key)
+                    .json(&payload))
+            })
+            .await
+            .map_err(|e| format!("Voyage rerank request failed: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!("Voyage rerank failed ({}): {}", status, body));
+        }
+
+        let parsed: Value =
+            serde_json::from_str(&body).map_err(|e| format!("Invalid Voyage JSON: {}", e))?;
+
+        Self::extract_scores(&parsed, documents.len()).ok_or_else(|| {
+            format!(
+                "Voyage rerank response missing scores for model '{}': {}",
+                self.model, body
+            )
+        })
+    }
+}
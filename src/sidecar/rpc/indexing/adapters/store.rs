@@ -15,6 +15,26 @@ pub struct ExistingVideoRecord {
     pub asset_id: String,
 }
 
+/// One unit waiting to be embedded and stored under its asset.
+#[derive(Debug, Clone)]
+pub struct EmbeddingUnit {
+    pub content_hash: String,
+    pub unit_kind: String,
+    pub unit_key: String,
+    pub content: String,
+}
+
+impl EmbeddingUnit {
+    pub fn new(content_hash: &str, unit_kind: &str, unit_key: &str, content: &str) -> Self {
+        Self {
+            content_hash: content_hash.to_string(),
+            unit_kind: unit_kind.to_string(),
+            unit_key: unit_key.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
 #[async_trait]
 pub trait TextIndexStore: Send + Sync {
     async fn get_file_by_hash(
@@ -36,6 +56,27 @@ pub trait TextIndexStore: Send + Sync {
         unit_key: &str,
         content: &str,
     ) -> Result<(), String>;
+
+    /// Stores many units, embedding them in batches. Results line up with
+    /// `units`.
+    async fn create_file_asset_embeddings_batch(
+        &self,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        let mut results = Vec::with_capacity(units.len());
+        for unit in units {
+            results.push(
+                self.create_file_asset_embeddings(
+                    &unit.content_hash,
+                    &unit.unit_kind,
+                    &unit.unit_key,
+                    &unit.content,
+                )
+                .await,
+            );
+        }
+        results
+    }
 }
 
 #[async_trait]
@@ -59,6 +100,27 @@ pub trait ImageIndexStore: Send + Sync {
         unit_key: &str,
         content: &str,
     ) -> Result<(), String>;
+
+    /// Stores many units, embedding them in batches. Results line up with
+    /// `units`.
+    async fn create_image_asset_embeddings_batch(
+        &self,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        let mut results = Vec::with_capacity(units.len());
+        for unit in units {
+            results.push(
+                self.create_image_asset_embeddings(
+                    &unit.content_hash,
+                    &unit.unit_kind,
+                    &unit.unit_key,
+                    &unit.content,
+                )
+                .await,
+            );
+        }
+        results
+    }
 }
 
 #[async_trait]
@@ -83,4 +145,77 @@ pub trait VideoIndexStore: Send + Sync {
         unit_key: &str,
         content: &str,
     ) -> Result<(), String>;
+
+    /// Stores many units, embedding them in batches. Results line up with
+    /// `units`.
+    async fn create_video_asset_embeddings_batch(
+        &self,
+        units: &[EmbeddingUnit],
+    ) -> Vec<Result<(), String>> {
+        let mut results = Vec::with_capacity(units.len());
+        for unit in units {
+            results.push(
+                self.create_video_asset_embeddings(
+                    &unit.content_hash,
+                    &unit.unit_kind,
+                    &unit.unit_key,
+                    &unit.content,
+                )
+                .await,
+            );
+        }
+        results
+    }
+}
+
+/// Size and mtime of a file at indexing time, stored on the `Asset` node so
+/// re-indexing can skip unchanged files without hashing them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileStamp {
+    pub modified_at: i64,
+    pub size_bytes: i64,
+}
+
+impl FileStamp {
+    pub fn read(path: &str) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified_at = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs() as i64;
+        Some(Self {
+            modified_at,
+            size_bytes: metadata.len() as i64,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedAsset {
+    pub content_hash: String,
+    pub kind: String,
+    pub path: String,
+    pub stamp: Option<FileStamp>,
+    /// Unix seconds; `None` for assets stored before it was recorded.
+    pub indexed_at: Option<i64>,
+}
+
+#[async_trait]
+pub trait AssetCatalogStore: Send + Sync {
+    async fn list_assets(&self) -> Result<Vec<IndexedAsset>, String>;
+
+    /// Whether the asset finished indexing; incomplete assets are re-indexed
+    /// even when their stamp is unchanged.
+    async fn asset_is_complete(&self, asset: &IndexedAsset) -> Result<bool, String>;
+
+    async fn update_asset_location(
+        &self,
+        content_hash: &str,
+        path: &str,
+        stamp: FileStamp,
+    ) -> Result<(), String>;
+
+    async fn delete_asset(&self, content_hash: &str) -> Result<(), String>;
 }
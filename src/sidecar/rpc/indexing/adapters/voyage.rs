@@ -3,14 +3,25 @@ use reqwest::Client;
 use serde_json::{json, Value};
 use std::env;
 use std::fmt;
+use std::sync::Arc;
+
+use crate::sidecar::rpc::indexing::adapters::embeddings::{
+    check_dimensions, embed_batched, env_dimensions, env_input_type, single, vectors_from_data,
+    BatchLimits, EmbeddingClient,
+};
+use crate::sidecar::rpc::indexing::adapters::http_policy::{self, HttpPolicy};
 
 #[derive(Clone)]
 pub struct VoyageClient {
     http: Client,
+    policy: Arc<HttpPolicy>,
     api_key: String,
     base_url: String,
     embedding_model: String,
     retrieval_model: String,
+    dimensions: Option<usize>,
+    query_input_type: Option<String>,
+    document_input_type: Option<String>,
 }
 
 impl fmt::Debug for VoyageClient {
@@ -21,16 +32,11 @@ impl fmt::Debug for VoyageClient {
             .field("base_url", &self.base_url)
             .field("embedding_model", &self.embedding_model)
             .field("retrieval_model", &self.retrieval_model)
+            .field("dimensions", &self.dimensions)
             .finish()
     }
 }
 
-#[async_trait]
-pub trait EmbeddingClient: Send + Sync {
-    async fn embed_document(&self, text: &str) -> Result<Vec<f32>, String>;
-    async fn embed_query(&self, text: &str) -> Result<Vec<f32>, String>;
-}
-
 impl VoyageClient {
     pub fn from_env() -> Result<Self, String> {
         let api_key = env::var("VOYAGE_API_KEY")
@@ -49,13 +55,18 @@ impl VoyageClient {
             env::var("VOYAGE_EMBED_MODEL").unwrap_or_else(|_| "voyage-3-large".to_string());
         let retrieval_model =
             env::var("VOYAGE_RETRIEVAL_MODEL").unwrap_or_else(|_| "voyage-3-large".to_string());
+        let dimensions = env_dimensions("VOYAGE_EMBED_DIMENSIONS")?;
 
         Ok(Self {
             http: Client::new(),
+            policy: http_policy::policy("voyage"),
             api_key,
             base_url,
             embedding_model,
             retrieval_model,
+            dimensions,
+            query_input_type: env_input_type("VOYAGE_QUERY_INPUT_TYPE", "query"),
+            document_input_type: env_input_type("VOYAGE_DOCUMENT_INPUT_TYPE", "document"),
         })
     }
 
@@ -69,37 +80,38 @@ impl VoyageClient {
 
     async fn embed_with_model(
         &self,
-        text: &str,
+        texts: Vec<&str>,
         model: &str,
-        input_type: &'static str,
-    ) -> Result<Vec<f32>, String> {
-        let trimmed = text.trim();
-        if trimmed.is_empty() {
+        input_type: Option<&str>,
+    ) -> Result<Vec<Vec<f32>>, String> {
+        if texts.iter().any(|text| text.trim().is_empty()) {
             return Err("cannot embed empty text".to_string());
         }
 
         let url = format!("{}/embeddings", self.base_url.trim_end_matches('/'));
-        let payload = json!({
-            "input": [trimmed],
+        let mut payload = json!({
+            "input": texts,
             "model": model,
-            "input_type": input_type,
         });
+        if let Some(input_type) = input_type {
+            payload["input_type"] = json!(input_type);
+        }
+        if let Some(dimensions) = self.dimensions {
+            payload["output_dimension"] = json!(dimensions);
+        }
 
-        let response = self
-            .http
-            .post(url)
-            .bearer_auth(&self.api_key)
-            .json(&payload)
-            .send()
+        let (status, body) = self
+            .policy
+            .send(|| {
+                Ok(self
+                    .http
+                    .post(&url)
+                    .bearer_auth(&self.api_key)
+                    .json(&payload))
+            })
             .await
             .map_err(|e| format!("Voyage request failed: {}", e))?;
 
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .map_err(|e| format!("Voyage response read failed: {}", e))?;
-
         if !status.is_success() {
             return Err(format!("Voyage embeddings failed ({}): {}", status, body));
         }
@@ -107,40 +119,68 @@ impl VoyageClient {
         let parsed: Value =
             serde_json::from_str(&body).map_err(|e| format!("Invalid Voyage JSON: {}", e))?;
 
-        Self::extract_embedding(&parsed).ok_or_else(|| {
+        let vectors = vectors_from_data(&parsed).ok_or_else(|| {
             format!(
                 "Voyage response missing embedding vector for model '{}': {}",
                 model, body
             )
-        })
-    }
-
-    fn extract_embedding(value: &Value) -> Option<Vec<f32>> {
-        let array = value.get("data")?.as_array()?;
-        let first = array.first()?;
-        let embedding = first.get("embedding")?.as_array()?;
-
-        let mut out = Vec::with_capacity(embedding.len());
-        for item in embedding {
-            if let Some(v) = item.as_f64() {
-                out.push(v as f32);
-            } else {
-                return None;
-            }
-        }
-        Some(out)
+        })?;
+        check_dimensions(vectors, self.dimensions, "Voyage")
     }
 }
 
 #[async_trait]
 impl EmbeddingClient for VoyageClient {
     async fn embed_document(&self, text: &str) -> Result<Vec<f32>, String> {
-        self.embed_with_model(text, &self.embedding_model, "document")
-            .await
+        self.embed_with_model(
+            vec![text.trim()],
+            &self.embedding_model,
+            self.document_input_type.as_deref(),
+        )
+        .await
+        .and_then(single)
     }
 
     async fn embed_query(&self, text: &str) -> Result<Vec<f32>, String> {
-        self.embed_with_model(text, &self.retrieval_model, "query")
-            .await
+        self.embed_with_model(
+            vec![text.trim()],
+            &self.retrieval_model,
+            self.query_input_type.as_deref(),
+        )
+        .await
+        .and_then(single)
+    }
+
+    async fn embed_documents(&self, texts: &[String]) -> Vec<Result<Vec<f32>, String>> {
+        // voyage-3-large takes up to 1000 inputs and 120k tokens per request.
+        let limits = BatchLimits {
+            max_items: 1000,
+            max_tokens: 100_000,
+        }
+        .with_env();
+        embed_batched(texts, limits, |batch| {
+            self.embed_with_model(
+                batch,
+                &self.embedding_model,
+                self.document_input_type.as_deref(),
+            )
+        })
+        .await
+    }
+
+    fn query_model_id(&self) -> String {
+        match self.dimensions {
+            Some(dimensions) => format!("voyage:{}:{}", self.retrieval_model, dimensions),
+            None => format!("voyage:{}", self.retrieval_model),
+        }
+    }
+
+    fn document_model_id(&self) -> String {
+        format!(
+            "voyage:{}:{}:{}",
+            self.embedding_model,
+            self.dimensions.unwrap_or_default(),
+            self.document_input_type.as_deref().unwrap_or_default()
+        )
     }
 }
@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::sidecar::rpc::indexing::ignore::IgnoreRules;
+
+const FILE_TYPES_CONFIG: &str = "config/file_types.json";
+
+pub fn normalize_extension(ext: &str) -> String {
+    let ext = ext.trim().to_lowercase();
+    if ext.is_empty() {
+        return ext;
+    }
+    if ext.starts_with('.') {
+        ext
+    } else {
+        format!(".{}", ext)
+    }
+}
+
+fn read_config(path: &str) -> Option<serde_json::Value> {
+    let raw = fs::read_to_string(Path::new(path)).ok()?;
+    serde_json::from_str::<serde_json::Value>(&raw).ok()
+}
+
+fn string_list(value: Option<&serde_json::Value>, normalize: fn(&str) -> String) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .map(normalize)
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default()
+}
+
+fn defaults(values: &[&str]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct FileTypeConfig {
+    pub text: Vec<String>,
+    pub image: Vec<String>,
+    pub video: Vec<String>,
+}
+
+impl FileTypeConfig {
+    pub fn load() -> Self {
+        let parsed = read_config(FILE_TYPES_CONFIG);
+        let section = |key: &str, fallback: &[&str]| {
+            let values = string_list(
+                parsed.as_ref().and_then(|p| p.get(key)),
+                normalize_extension,
+            );
+            if values.is_empty() {
+                defaults(fallback)
+            } else {
+                values
+            }
+        };
+
+        Self {
+            text: section("text", &[".text", ".txt"]),
+            image: section("image", &[".jpeg", ".jpg", ".png", ".webp"]),
+            video: section("video", &[".mp4", ".mov"]),
+        }
+    }
+
+    /// Asset kind (`file`, `image` or `video`) a path is indexed as, if any.
+    pub fn kind_of(&self, path: &Path) -> Option<&'static str> {
+        let ext = extension_of(path);
+        if self.text.contains(&ext) {
+            Some("file")
+        } else if self.image.contains(&ext) {
+            Some("image")
+        } else if self.video.contains(&ext) {
+            Some("video")
+        } else {
+            None
+        }
+    }
+}
+
+pub fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|s| format!(".{}", s.to_lowercase()))
+        .unwrap_or_default()
+}
+
+/// Walks `root` (or checks it, if it is a single file) and returns every file
+/// whose extension is in `exts` and that the ignore rules let through.
+pub fn collect_files(root: &str, exts: &[String], ignore: &IgnoreRules) -> Vec<String> {
+    let ext_set: HashSet<String> = exts.iter().map(|ext| normalize_extension(ext)).collect();
+
+    ignore
+        .walk(Path::new(root))
+        .files
+        .into_iter()
+        .filter(|path| ext_set.contains(&extension_of(path)))
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
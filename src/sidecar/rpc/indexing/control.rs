@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub const JOB_CANCELLED: &str = "Job cancelled";
+
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Cooperative cancel/pause flags shared between an RPC handler and the thread
+/// doing the work. Nothing is interrupted mid-step: workers call
+/// [`JobControl::checkpoint`] between files and between ffmpeg/Groq steps.
+#[derive(Debug, Default)]
+pub struct JobControl {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+}
+
+impl JobControl {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Waits while the job is paused and fails with [`JOB_CANCELLED`] once it
+    /// has been cancelled (including while paused).
+    pub async fn checkpoint(&self) -> Result<(), String> {
+        loop {
+            if self.is_cancelled() {
+                return Err(JOB_CANCELLED.to_string());
+            }
+            if !self.is_paused() {
+                return Ok(());
+            }
+            tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::sidecar::rpc::indexing::collect::{extension_of, normalize_extension};
+
+const IGNORE_CONFIG: &str = "config/ignore.json";
+
+/// Per-directory ignore files, applied in this order so `.searchignore` can
+/// override (or re-include) anything a `.gitignore` excludes.
+pub const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".searchignore"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnorePattern {
+    segments: Vec<String>,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl IgnorePattern {
+    /// Parses one gitignore line. Returns `None` for blanks and comments.
+    fn parse(line: &str) -> Option<Self> {
+        let mut pattern = line.trim_end_matches(['\r', '\n']);
+        if !pattern.ends_with("\\ ") {
+            pattern = pattern.trim_end();
+        }
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return None;
+        }
+
+        let negated = pattern.starts_with('!');
+        if negated || pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..];
+        }
+
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        // A slash anywhere but the end ties the pattern to the ignore file's directory.
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            segments: pattern.split('/').map(ToString::to_string).collect(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, relative: &[&str], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            return match_segments(&self.segments, relative);
+        }
+        relative
+            .last()
+            .is_some_and(|name| glob_match_segment(&self.segments[0], name))
+    }
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, remaining)) => {
+                glob_match_segment(first, name) && match_segments(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches a single path component against a glob supporting `*`, `?`,
+/// `[...]` classes (with `!`/`^` negation and ranges) and `\` escapes.
+pub fn glob_match_segment(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0usize, 0usize);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    while p < pattern.len() && pattern[p] == '*' {
+                        p += 1;
+                    }
+                    backtrack = Some((p, t));
+                    continue;
+                }
+                '?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, next)) = match_class(pattern, p, text[t]) {
+                        if matched {
+                            p = next;
+                            t += 1;
+                            continue;
+                        }
+                    } else if text[t] == '[' {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+                '\\' if p + 1 < pattern.len() && pattern[p + 1] == text[t] => {
+                    p += 2;
+                    t += 1;
+                    continue;
+                }
+                '\\' if p + 1 < pattern.len() => {}
+                c if c == text[t] => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        match backtrack {
+            Some((star_p, star_t)) => {
+                p = star_p;
+                t = star_t + 1;
+                backtrack = Some((star_p, star_t + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Returns whether `c` is in the class starting at `pattern[start] == '['`, and
+/// the index just past the closing `]`. `None` means the class is unterminated.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        if pattern[i] == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+
+        let low = pattern[i];
+        if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            let high = pattern[i + 2];
+            if low <= c && c <= high {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if low == c {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Default)]
+struct PatternList {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl PatternList {
+    fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            patterns: lines.into_iter().filter_map(IgnorePattern::parse).collect(),
+        }
+    }
+
+    /// Last matching pattern wins: `Some(true)` ignores, `Some(false)` re-includes.
+    fn decide(&self, relative: &[&str], is_dir: bool) -> Option<bool> {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(relative, is_dir))
+            .map(|pattern| !pattern.negated)
+    }
+}
+
+/// Ignore configuration shared by `fs.walkTextBatch` and every `index.start`
+/// pass: extension and file-name lists, gitignore-style patterns from
+/// `config/ignore.json`, and per-directory `.gitignore` / `.searchignore` files.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    extensions: HashSet<String>,
+    file_names: HashSet<String>,
+    patterns: PatternList,
+    respect_ignore_files: bool,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        Self {
+            extensions: HashSet::new(),
+            file_names: HashSet::new(),
+            patterns: PatternList::default(),
+            respect_ignore_files: true,
+        }
+    }
+}
+
+fn config_string_list(parsed: &serde_json::Value, key: &str) -> Vec<String> {
+    parsed
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default()
+}
+
+impl IgnoreRules {
+    pub fn load() -> Self {
+        let Some(parsed) = fs::read_to_string(IGNORE_CONFIG)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        else {
+            return Self::default();
+        };
+
+        let mut extensions = config_string_list(&parsed, "ignore_extensions");
+        extensions.extend(config_string_list(&parsed, "ignore"));
+        let mut rules = Self::default().with_extra(
+            extensions,
+            config_string_list(&parsed, "ignore_files"),
+            config_string_list(&parsed, "patterns"),
+        );
+        if let Some(respect) = parsed.get("respect_ignore_files").and_then(|v| v.as_bool()) {
+            rules.respect_ignore_files = respect;
+        }
+        rules
+    }
+
+    pub fn with_extra(
+        mut self,
+        extensions: Vec<String>,
+        file_names: Vec<String>,
+        patterns: Vec<String>,
+    ) -> Self {
+        self.extensions.extend(
+            extensions
+                .iter()
+                .map(|ext| normalize_extension(ext))
+                .filter(|ext| !ext.is_empty()),
+        );
+        self.file_names.extend(
+            file_names
+                .iter()
+                .map(|name| name.trim().to_lowercase())
+                .filter(|name| !name.is_empty()),
+        );
+        self.patterns
+            .patterns
+            .extend(PatternList::parse(patterns.iter().map(String::as_str)).patterns);
+        self
+    }
+
+    fn ignores_file_name(&self, path: &Path) -> bool {
+        let base_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        if self.file_names.contains(&base_name) {
+            return true;
+        }
+        // `.env` has no extension as far as `Path` is concerned, so dotfiles are
+        // also checked against the extension list by their full name.
+        if base_name.starts_with('.') && self.extensions.contains(&base_name) {
+            return true;
+        }
+        self.extensions.contains(&extension_of(path))
+    }
+
+    /// Walks `root` honoring every rule, skipping ignored directories entirely.
+    pub fn walk(&self, root: &Path) -> FilteredWalk {
+        let mut matcher = IgnoreMatcher::new(self, root);
+        let mut walk = FilteredWalk::default();
+
+        if root.is_file() {
+            if matcher.is_ignored(root, false) {
+                walk.ignored += 1;
+            } else {
+                walk.files.push(root.to_path_buf());
+            }
+            return walk;
+        }
+
+        let mut entries = WalkDir::new(root).into_iter();
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let path = entry.path();
+            let is_dir = entry.file_type().is_dir();
+            if entry.depth() > 0 && matcher.is_ignored(path, is_dir) {
+                walk.ignored += 1;
+                if is_dir {
+                    entries.skip_current_dir();
+                }
+                continue;
+            }
+            if path.is_file() {
+                walk.files.push(path.to_path_buf());
+            }
+        }
+
+        walk
+    }
+
+    /// Checks a single path below `root` without walking, e.g. for watcher events.
+    pub fn is_ignored(&self, root: &Path, path: &Path) -> bool {
+        let mut matcher = IgnoreMatcher::new(self, root);
+        let Ok(relative) = path.strip_prefix(root) else {
+            return matcher.is_ignored(path, path.is_dir());
+        };
+        // Ignoring a directory excludes everything beneath it, so every ancestor
+        // between `root` and `path` has to be checked as well.
+        let mut current = root.to_path_buf();
+        let components: Vec<_> = relative.components().collect();
+        for (idx, component) in components.iter().enumerate() {
+            current.push(component);
+            let is_dir = idx + 1 < components.len() || current.is_dir();
+            if matcher.is_ignored(&current, is_dir) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FilteredWalk {
+    pub files: Vec<PathBuf>,
+    /// Ignored files plus ignored directories (each directory counts once).
+    pub ignored: usize,
+}
+
+struct IgnoreMatcher<'a> {
+    rules: &'a IgnoreRules,
+    root: PathBuf,
+    dir_rules: HashMap<PathBuf, Option<PatternList>>,
+}
+
+impl<'a> IgnoreMatcher<'a> {
+    fn new(rules: &'a IgnoreRules, root: &Path) -> Self {
+        let root = if root.is_file() {
+            root.parent().map(Path::to_path_buf).unwrap_or_default()
+        } else {
+            root.to_path_buf()
+        };
+        Self {
+            rules,
+            root,
+            dir_rules: HashMap::new(),
+        }
+    }
+
+    fn load_dir_rules(&mut self, dir: &Path) -> Option<&PatternList> {
+        if !self.dir_rules.contains_key(dir) {
+            let mut lines = Vec::new();
+            if self.rules.respect_ignore_files {
+                for name in IGNORE_FILE_NAMES {
+                    if let Ok(raw) = fs::read_to_string(dir.join(name)) {
+                        lines.extend(raw.lines().map(ToString::to_string));
+                    }
+                }
+            }
+            let list = PatternList::parse(lines.iter().map(String::as_str));
+            let entry = (!list.patterns.is_empty()).then_some(list);
+            self.dir_rules.insert(dir.to_path_buf(), entry);
+        }
+        self.dir_rules.get(dir).and_then(Option::as_ref)
+    }
+
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        if !is_dir && self.rules.ignores_file_name(path) {
+            return true;
+        }
+
+        let root = self.root.clone();
+        let relative = path.strip_prefix(&root).unwrap_or(path);
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let parts: Vec<&str> = components.iter().map(String::as_str).collect();
+        if parts.is_empty() {
+            return false;
+        }
+
+        let mut ignored = self.rules.patterns.decide(&parts, is_dir).unwrap_or(false);
+
+        // Deeper ignore files take precedence, mirroring git.
+        let mut dir = root;
+        for depth in 0..parts.len() {
+            if let Some(list) = self.load_dir_rules(&dir) {
+                if let Some(decision) = list.decide(&parts[depth..], is_dir) {
+                    ignored = decision;
+                }
+            }
+            dir.push(parts[depth]);
+        }
+
+        ignored
+    }
+}
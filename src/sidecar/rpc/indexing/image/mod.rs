@@ -1,6 +1,8 @@
+use crate::sidecar::rpc::indexing::adapters::embeddings::embed_flush_units;
 use crate::sidecar::rpc::indexing::adapters::groq::TranscriptionClient;
 use crate::sidecar::rpc::indexing::adapters::hash::PathHasher;
-use crate::sidecar::rpc::indexing::adapters::store::ImageIndexStore;
+use crate::sidecar::rpc::indexing::adapters::store::{EmbeddingUnit, ImageIndexStore};
+use crate::sidecar::rpc::indexing::control::JobControl;
 use crate::sidecar::rpc::indexing::embedding::build_embedding_text;
 use async_trait::async_trait;
 use serde_json::Value;
@@ -8,6 +10,9 @@ use std::fs;
 use std::path::Path;
 use uuid::Uuid;
 
+/// Unit kind of the embedded `build_embedding_text` summary of an image.
+pub const IMAGE_CAPTION_UNIT_KIND: &str = "image_caption";
+
 #[derive(Clone, Debug)]
 pub struct ImageIndexResult {
     pub content_hash: Option<String>,
@@ -15,6 +20,8 @@ pub struct ImageIndexResult {
     pub path: String,
     pub indexed: bool,
     pub error: Option<String>,
+    /// Whether this run created the asset, so a failure may roll it back.
+    pub created: bool,
 }
 
 #[async_trait]
@@ -81,10 +88,55 @@ fn normalize_paths(file_paths: Vec<String>) -> Vec<String> {
         .collect()
 }
 
+/// A unit waiting for the next batch, with the image result it reports to.
+struct PendingUnit {
+    result_index: usize,
+    /// Set on the caption unit, which decides whether the image indexed; the
+    /// path unit only warns.
+    image_id: Option<String>,
+    unit: EmbeddingUnit,
+}
+
+async fn flush_pending(
+    store: &dyn ImageIndexStore,
+    pending: &mut Vec<PendingUnit>,
+    results: &mut [ImageIndexResult],
+) {
+    if pending.is_empty() {
+        return;
+    }
+    let units: Vec<EmbeddingUnit> = pending.iter().map(|p| p.unit.clone()).collect();
+    let outcomes = store.create_image_asset_embeddings_batch(&units).await;
+    for (unit, outcome) in pending.drain(..).zip(outcomes) {
+        let result = &mut results[unit.result_index];
+        match (unit.image_id, outcome) {
+            (Some(image_id), Ok(())) => eprintln!(
+                "[sidecar:index:image] indexed {} successfully (image_id={})",
+                result.path, image_id
+            ),
+            (Some(image_id), Err(error)) => {
+                eprintln!(
+                    "[sidecar:index:image] failed to create image embeddings for {} (image_id={}): {}",
+                    result.path, image_id, error
+                );
+                result.indexed = false;
+                result.error = Some(error);
+            }
+            (None, Ok(())) => {}
+            (None, Err(error)) => eprintln!(
+                "[sidecar:index:image] warning: failed to create path embedding for {}: {}",
+                result.path, error
+            ),
+        }
+    }
+}
+
 async fn index_images_with_deps<D>(
     file_paths: Vec<String>,
     deps: &D,
+    hasher: &dyn PathHasher,
     store: &dyn ImageIndexStore,
+    control: &JobControl,
 ) -> Vec<ImageIndexResult>
 where
     D: ImageIndexerDeps,
@@ -94,11 +146,15 @@ where
         return Vec::new();
     }
 
+    let flush_units = embed_flush_units();
     let mut results = Vec::new();
-
-    let hasher = crate::sidecar::rpc::indexing::adapters::hash::Sha256PathHasher;
+    let mut pending: Vec<PendingUnit> = Vec::new();
 
     for path in paths {
+        if control.checkpoint().await.is_err() {
+            break;
+        }
+
         let normalized_path = normalize_path(&path);
         let path_obj = Path::new(&normalized_path);
         eprintln!("[sidecar:index:image] processing {}", normalized_path);
@@ -114,6 +170,7 @@ where
                 kind: "image".to_string(),
                 indexed: false,
                 error: Some("Path not found".to_string()),
+                created: false,
             });
             continue;
         }
@@ -127,6 +184,7 @@ where
                     kind: "image".to_string(),
                     indexed: false,
                     error: Some(error.to_string()),
+                    created: false,
                 });
                 continue;
             }
@@ -141,6 +199,7 @@ where
                     kind: "image".to_string(),
                     indexed: false,
                     error: Some(error),
+                    created: false,
                 });
                 continue;
             }
@@ -168,6 +227,7 @@ where
                 kind: "image".to_string(),
                 indexed: false,
                 error: Some("Duplicate content hash".to_string()),
+                created: false,
             });
             continue;
         }
@@ -190,6 +250,7 @@ where
                     kind: "image".to_string(),
                     indexed: false,
                     error: Some(error),
+                    created: false,
                 });
                 continue;
             }
@@ -211,32 +272,30 @@ where
                 kind: "image".to_string(),
                 indexed: false,
                 error: Some(error),
+                created: false,
             });
             continue;
         }
 
-        if let Err(error) = store
-            .create_image_asset_embeddings(
+        let result_index = results.len();
+        results.push(ImageIndexResult {
+            path: normalized_path.clone(),
+            content_hash: Some(content_hash.clone()),
+            kind: "image".to_string(),
+            indexed: true,
+            error: None,
+            created: true,
+        });
+        pending.push(PendingUnit {
+            result_index,
+            image_id: Some(image_id),
+            unit: EmbeddingUnit::new(
                 &content_hash,
-                "image_caption",
-                "image_caption",
+                IMAGE_CAPTION_UNIT_KIND,
+                IMAGE_CAPTION_UNIT_KIND,
                 &embedding_text,
-            )
-            .await
-        {
-            eprintln!(
-                "[sidecar:index:image] failed to create image embeddings for {} (image_id={}): {}",
-                normalized_path, image_id, error
-            );
-            results.push(ImageIndexResult {
-                path: normalized_path,
-                content_hash: Some(content_hash.clone()),
-                kind: "image".to_string(),
-                indexed: false,
-                error: Some(error),
-            });
-            continue;
-        }
+            ),
+        });
 
         let filename_text = Path::new(&normalized_path)
             .file_stem()
@@ -244,46 +303,33 @@ where
             .unwrap_or_default()
             .replace(['#', '_', '-', '.'], " ");
         if !filename_text.trim().is_empty() {
-            if let Err(error) = store
-                .create_image_asset_embeddings(
-                    &content_hash,
-                    "file_path",
-                    "file_path",
-                    &filename_text,
-                )
-                .await
-            {
-                eprintln!(
-                    "[sidecar:index:image] warning: failed to create path embedding for {}: {}",
-                    normalized_path, error
-                );
-            }
+            pending.push(PendingUnit {
+                result_index,
+                image_id: None,
+                unit: EmbeddingUnit::new(&content_hash, "file_path", "file_path", &filename_text),
+            });
         }
 
-        eprintln!(
-            "[sidecar:index:image] indexed {} successfully (image_id={})",
-            normalized_path, image_id
-        );
-        results.push(ImageIndexResult {
-            path: normalized_path,
-            content_hash: Some(content_hash),
-            kind: "image".to_string(),
-            indexed: true,
-            error: None,
-        });
+        if pending.len() >= flush_units {
+            flush_pending(store, &mut pending, &mut results).await;
+        }
     }
 
+    flush_pending(store, &mut pending, &mut results).await;
+
     results
 }
 
 pub async fn image_indexer_with_sidecar<C>(
     file_paths: Vec<String>,
     groq: &C,
+    hasher: &dyn PathHasher,
     store: &dyn ImageIndexStore,
+    control: &JobControl,
 ) -> Vec<ImageIndexResult>
 where
     C: TranscriptionClient + Clone + 'static,
 {
     let deps = SidecarImageIndexerDeps { groq: groq.clone() };
-    index_images_with_deps(file_paths, &deps, store).await
+    index_images_with_deps(file_paths, &deps, hasher, store, control).await
 }
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::sidecar::data::{data_dir, write_atomic};
+use crate::sidecar::rpc::fs::{BINARY_CONTENT, FILE_TOO_LARGE};
+use crate::sidecar::rpc::indexing::control::JOB_CANCELLED;
+use crate::sidecar::rpc::indexing::text::DUPLICATE_CONTENT_HASH;
+
+const DEFAULT_ISSUE_LIMIT: usize = 5000;
+
+/// One file a job failed on or deliberately skipped. Files skipped because
+/// reconciliation found them unchanged or moved are not issues and are only
+/// counted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileIssue {
+    pub path: String,
+    pub kind: String,
+    /// Job phase the file was in, e.g. `index_text`.
+    pub phase: String,
+    /// `failed` or `skipped`.
+    pub outcome: String,
+    pub category: String,
+    pub message: String,
+}
+
+impl FileIssue {
+    pub fn failed(kind: &str, phase: &str, path: &str, message: &str) -> Self {
+        Self::new("failed", kind, phase, path, message)
+    }
+
+    pub fn skipped(kind: &str, phase: &str, path: &str, message: &str) -> Self {
+        Self::new("skipped", kind, phase, path, message)
+    }
+
+    fn new(outcome: &str, kind: &str, phase: &str, path: &str, message: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            kind: kind.to_string(),
+            phase: phase.to_string(),
+            outcome: outcome.to_string(),
+            category: classify_error(message).to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Buckets an indexer error message so clients can group failures without
+/// parsing provider-specific text.
+pub fn classify_error(message: &str) -> &'static str {
+    match message {
+        DUPLICATE_CONTENT_HASH => return "duplicate",
+        FILE_TOO_LARGE => return "too_large",
+        BINARY_CONTENT => return "binary",
+        JOB_CANCELLED => return "cancelled",
+        _ => {}
+    }
+
+    let lower = message.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+    if has(&["path not found", "no such file", "not found"]) {
+        "not_found"
+    } else if has(&["permission denied", "access is denied"]) {
+        "permission"
+    } else if has(&["timed out", "timeout"]) {
+        "timeout"
+    } else if has(&["ffmpeg", "ffprobe", "chunk processing", "chunk task"]) {
+        "media"
+    } else if has(&["groq", "transcri", "vision"]) {
+        "model"
+    } else if has(&["voyage", "embed"]) {
+        "embedding"
+    } else if has(&["helix", "store", "query"]) {
+        "store"
+    } else if has(&["no searchable chunks"]) {
+        "no_content"
+    } else {
+        "other"
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct JobIssues {
+    pub issues: Vec<FileIssue>,
+    /// Issues not kept because the job hit `SIDECAR_JOB_ISSUE_LIMIT`.
+    pub dropped: usize,
+}
+
+static JOB_ISSUES: OnceLock<Mutex<HashMap<String, JobIssues>>> = OnceLock::new();
+
+fn job_issues() -> &'static Mutex<HashMap<String, JobIssues>> {
+    JOB_ISSUES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn issue_limit() -> usize {
+    env::var("SIDECAR_JOB_ISSUE_LIMIT")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_ISSUE_LIMIT)
+}
+
+fn issues_file(job_id: &str) -> PathBuf {
+    data_dir()
+        .join("job_issues")
+        .join(format!("{}.json", job_id))
+}
+
+/// Starts a fresh report, e.g. when an interrupted job is re-run from the top.
+pub fn reset(job_id: &str) {
+    let mut all = job_issues().lock().unwrap_or_else(|e| e.into_inner());
+    all.insert(job_id.to_string(), JobIssues::default());
+}
+
+pub fn record(job_id: &str, issue: FileIssue) {
+    let limit = issue_limit();
+    let mut all = job_issues().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = all.entry(job_id.to_string()).or_default();
+    if entry.issues.len() < limit {
+        entry.issues.push(issue);
+    } else {
+        entry.dropped += 1;
+    }
+}
+
+/// Writes the job's report to the data dir once the job stops, so it is still
+/// available after a restart.
+pub fn flush(job_id: &str) {
+    let report = {
+        let all = job_issues().lock().unwrap_or_else(|e| e.into_inner());
+        all.get(job_id).cloned().unwrap_or_default()
+    };
+    let result = serde_json::to_vec(&report)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| write_atomic(&issues_file(job_id), &bytes));
+    if let Err(error) = result {
+        eprintln!(
+            "[sidecar:index] failed to persist issue report for {}: {}",
+            job_id, error
+        );
+    }
+}
+
+/// Drops the in-memory and on-disk report of a job removed from history.
+pub fn forget(job_id: &str) {
+    job_issues()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(job_id);
+    let _ = fs::remove_file(issues_file(job_id));
+}
+
+/// The job's report, loaded from disk if this process hasn't seen the job run.
+pub fn load(job_id: &str) -> JobIssues {
+    let mut all = job_issues().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(report) = all.get(job_id) {
+        return report.clone();
+    }
+    let report = fs::read_to_string(issues_file(job_id))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<JobIssues>(&raw).ok())
+        .unwrap_or_default();
+    all.insert(job_id.to_string(), report.clone());
+    report
+}
@@ -0,0 +1,499 @@
+//! BM25 index over the text of embedding units and asset paths.
+//!
+//! Helix only answers nearest-neighbour queries, which are weak at exact
+//! identifiers, error codes and file names. The store adapter mirrors every
+//! unit it embeds into this index so `search.query` can rank those literally.
+//! The index lives in memory and is persisted under the data dir; saves work
+//! from a snapshot on the blocking pool, so the lock only covers the change.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::sidecar::data::{data_file, write_atomic};
+use crate::sidecar::rpc::indexing::text::chunk::CHUNK_UNIT_KIND;
+
+const INDEX_FILE: &str = "lexical_index.json";
+const PERSIST_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_TOKEN_CHARS: usize = 64;
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+/// Chunk text kept per unit for snippets; chunks are rarely longer.
+const MAX_STORED_TEXT_CHARS: usize = 4_000;
+
+/// Unit kind of the synthetic document holding an asset's full path.
+pub const PATH_UNIT_KIND: &str = "path";
+
+/// Unit kinds that are bookkeeping or already covered by the path document.
+const SKIPPED_UNIT_KINDS: &[&str] = &["file_path", "video_index_state"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LexicalUnit {
+    unit_kind: String,
+    unit_key: String,
+    terms: HashMap<String, u32>,
+    /// Text of a file chunk as it was indexed, so hits need not reread the
+    /// file. Absent in indexes written before it was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip)]
+    len: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LexicalAsset {
+    kind: String,
+    path: String,
+    #[serde(default)]
+    modified_at: Option<i64>,
+    #[serde(default)]
+    indexed_at: Option<i64>,
+    units: Vec<LexicalUnit>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LexicalIndex {
+    assets: HashMap<String, LexicalAsset>,
+    /// term -> content_hash -> unit position -> term frequency. Rebuilt on load.
+    #[serde(skip)]
+    postings: HashMap<String, HashMap<String, HashMap<usize, u32>>>,
+    #[serde(skip)]
+    doc_count: usize,
+    #[serde(skip)]
+    total_len: u64,
+}
+
+/// Best-scoring unit of one asset for a lexical query.
+#[derive(Debug, Clone)]
+pub struct LexicalHit {
+    pub content_hash: String,
+    pub kind: String,
+    pub path: String,
+    pub modified_at: Option<i64>,
+    pub indexed_at: Option<i64>,
+    pub unit_kind: String,
+    pub unit_key: String,
+    /// Indexed text of the matched unit, kept for file chunks only.
+    pub text: Option<String>,
+    pub score: f64,
+}
+
+static INDEX: OnceLock<Mutex<LexicalIndex>> = OnceLock::new();
+static LAST_PERSIST: Mutex<Option<Instant>> = Mutex::new(None);
+/// Bumped by every change to the index.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Generation on disk; held while writing so saves land in order.
+static SAVED: Mutex<u64> = Mutex::new(0);
+
+fn index() -> &'static Mutex<LexicalIndex> {
+    INDEX.get_or_init(|| Mutex::new(load_index()))
+}
+
+fn load_index() -> LexicalIndex {
+    let path = data_file(INDEX_FILE);
+    let Ok(bytes) = fs::read(&path) else {
+        return LexicalIndex::default();
+    };
+    match serde_json::from_slice::<LexicalIndex>(&bytes) {
+        Ok(mut index) => {
+            index.rebuild_postings();
+            eprintln!(
+                "[sidecar:lexical] restored {} asset(s) from {}",
+                index.assets.len(),
+                path.display()
+            );
+            index
+        }
+        Err(error) => {
+            eprintln!(
+                "[sidecar:lexical] ignoring unreadable {}: {}",
+                path.display(),
+                error
+            );
+            LexicalIndex::default()
+        }
+    }
+}
+
+/// The persisted part of the index at one generation.
+struct Snapshot {
+    generation: u64,
+    assets: HashMap<String, LexicalAsset>,
+}
+
+#[derive(Serialize)]
+struct PersistedIndex<'a> {
+    assets: &'a HashMap<String, LexicalAsset>,
+}
+
+impl Snapshot {
+    fn take(index: &LexicalIndex) -> Self {
+        Self {
+            generation: GENERATION.load(Ordering::SeqCst),
+            assets: index.assets.clone(),
+        }
+    }
+
+    /// Writes the snapshot unless a newer one is already on disk.
+    fn write(self) {
+        let mut saved = SAVED.lock().unwrap_or_else(|e| e.into_inner());
+        if *saved >= self.generation {
+            return;
+        }
+        let result = serde_json::to_vec(&PersistedIndex {
+            assets: &self.assets,
+        })
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| write_atomic(&data_file(INDEX_FILE), &bytes));
+        match result {
+            Ok(()) => *saved = self.generation,
+            Err(error) => eprintln!("[sidecar:lexical] failed to persist index: {}", error),
+        }
+    }
+
+    /// Writes on the blocking pool, or right here outside a runtime.
+    fn write_in_background(self) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || self.write());
+            }
+            Err(_) => self.write(),
+        }
+    }
+}
+
+/// Whether the throttled save is due, claiming it if so.
+fn save_due() -> bool {
+    let mut last = LAST_PERSIST.lock().unwrap_or_else(|e| e.into_inner());
+    if last.is_some_and(|at| at.elapsed() < PERSIST_INTERVAL) {
+        return false;
+    }
+    *last = Some(Instant::now());
+    true
+}
+
+fn with_index<T>(mutate: bool, apply: impl FnOnce(&mut LexicalIndex) -> T) -> T {
+    let (value, snapshot) = {
+        let mut guard = index().lock().unwrap_or_else(|e| e.into_inner());
+        let value = apply(&mut guard);
+        if mutate {
+            GENERATION.fetch_add(1, Ordering::SeqCst);
+        }
+        let snapshot = (mutate && save_due()).then(|| Snapshot::take(&guard));
+        (value, snapshot)
+    };
+    if let Some(snapshot) = snapshot {
+        snapshot.write_in_background();
+    }
+    value
+}
+
+/// Lowercased terms of `text`. Identifiers are kept whole and additionally
+/// split at `_` and camelCase boundaries, so `parseConfigFile` matches both
+/// itself and `config`.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        let word = word.trim_matches('_');
+        if word.is_empty() || word.chars().count() > MAX_TOKEN_CHARS {
+            continue;
+        }
+        let parts = split_identifier(word);
+        if parts.len() > 1 {
+            tokens.extend(parts);
+        }
+        tokens.push(word.to_lowercase());
+    }
+    tokens
+}
+
+fn split_identifier(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for piece in word.split('_').filter(|piece| !piece.is_empty()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for c in piece.chars() {
+            if c.is_uppercase() && prev_lower && !current.is_empty() {
+                parts.push(std::mem::take(&mut current).to_lowercase());
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            current.push(c);
+        }
+        if !current.is_empty() {
+            parts.push(current.to_lowercase());
+        }
+    }
+    parts
+}
+
+fn term_counts(text: &str) -> (HashMap<String, u32>, u32) {
+    let mut terms = HashMap::new();
+    let mut len = 0u32;
+    for token in tokenize(text) {
+        *terms.entry(token).or_insert(0) += 1;
+        len += 1;
+    }
+    (terms, len)
+}
+
+impl LexicalIndex {
+    fn rebuild_postings(&mut self) {
+        self.postings.clear();
+        self.doc_count = 0;
+        self.total_len = 0;
+        let hashes: Vec<String> = self.assets.keys().cloned().collect();
+        for hash in hashes {
+            if let Some(asset) = self.assets.get_mut(&hash) {
+                for unit in &mut asset.units {
+                    unit.len = unit.terms.values().sum();
+                }
+            }
+            self.post_asset(&hash);
+        }
+    }
+
+    fn post_asset(&mut self, content_hash: &str) {
+        let count = self.assets.get(content_hash).map_or(0, |a| a.units.len());
+        for position in 0..count {
+            self.post_unit(content_hash, position);
+        }
+    }
+
+    fn post_unit(&mut self, content_hash: &str, position: usize) {
+        let Some(unit) = self
+            .assets
+            .get(content_hash)
+            .and_then(|asset| asset.units.get(position))
+        else {
+            return;
+        };
+        self.doc_count += 1;
+        self.total_len += u64::from(unit.len);
+        for (term, tf) in &unit.terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .entry(content_hash.to_string())
+                .or_default()
+                .insert(position, *tf);
+        }
+    }
+
+    fn unpost_unit(&mut self, content_hash: &str, position: usize) {
+        let Some(unit) = self
+            .assets
+            .get(content_hash)
+            .and_then(|asset| asset.units.get(position))
+        else {
+            return;
+        };
+        self.doc_count = self.doc_count.saturating_sub(1);
+        self.total_len = self.total_len.saturating_sub(u64::from(unit.len));
+        for term in unit.terms.keys() {
+            let Some(by_asset) = self.postings.get_mut(term) else {
+                continue;
+            };
+            if let Some(units) = by_asset.get_mut(content_hash) {
+                units.remove(&position);
+                if units.is_empty() {
+                    by_asset.remove(content_hash);
+                }
+            }
+            if by_asset.is_empty() {
+                self.postings.remove(term);
+            }
+        }
+    }
+
+    /// Replaces the unit with the same kind and key, or appends a new one.
+    /// Positions never shift, so only the touched unit is re-posted.
+    fn put_unit(&mut self, content_hash: &str, unit_kind: &str, unit_key: &str, text: &str) {
+        let (terms, len) = term_counts(text);
+        let unit = LexicalUnit {
+            unit_kind: unit_kind.to_string(),
+            unit_key: unit_key.to_string(),
+            terms,
+            text: (unit_kind == CHUNK_UNIT_KIND)
+                .then(|| text.chars().take(MAX_STORED_TEXT_CHARS).collect()),
+            len,
+        };
+        let existing = self.assets.get(content_hash).and_then(|asset| {
+            asset
+                .units
+                .iter()
+                .position(|u| u.unit_kind == unit_kind && u.unit_key == unit_key)
+        });
+        let position = match existing {
+            Some(position) => {
+                self.unpost_unit(content_hash, position);
+                let asset = self.assets.entry(content_hash.to_string()).or_default();
+                asset.units[position] = unit;
+                position
+            }
+            None => {
+                let asset = self.assets.entry(content_hash.to_string()).or_default();
+                asset.units.push(unit);
+                asset.units.len() - 1
+            }
+        };
+        self.post_unit(content_hash, position);
+    }
+
+    fn remove(&mut self, content_hash: &str) {
+        let count = self.assets.get(content_hash).map_or(0, |a| a.units.len());
+        for position in 0..count {
+            self.unpost_unit(content_hash, position);
+        }
+        self.assets.remove(content_hash);
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        accept: &dyn Fn(&LexicalHit) -> bool,
+    ) -> Vec<LexicalHit> {
+        let mut query_terms = tokenize(query);
+        query_terms.sort();
+        query_terms.dedup();
+        if query_terms.is_empty() || self.doc_count == 0 {
+            return Vec::new();
+        }
+
+        let doc_count = self.doc_count as f64;
+        let avg_len = (self.total_len as f64 / doc_count).max(1.0);
+        let mut scores: HashMap<(&str, usize), f64> = HashMap::new();
+        for term in &query_terms {
+            let Some(by_asset) = self.postings.get(term) else {
+                continue;
+            };
+            let df = by_asset.values().map(HashMap::len).sum::<usize>() as f64;
+            let idf = (1.0 + (doc_count - df + 0.5) / (df + 0.5)).ln();
+            for (hash, units) in by_asset {
+                let Some(asset) = self.assets.get(hash) else {
+                    continue;
+                };
+                for (&position, &tf) in units {
+                    let len = asset.units.get(position).map_or(0, |u| u.len) as f64;
+                    let tf = f64::from(tf);
+                    let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * len / avg_len);
+                    *scores.entry((hash.as_str(), position)).or_insert(0.0) +=
+                        idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+                }
+            }
+        }
+
+        let mut best: HashMap<&str, (usize, f64)> = HashMap::new();
+        for ((hash, position), score) in scores {
+            match best.get(hash) {
+                Some((_, current)) if *current >= score => {}
+                _ => {
+                    best.insert(hash, (position, score));
+                }
+            }
+        }
+
+        let mut hits: Vec<LexicalHit> = best
+            .into_iter()
+            .filter_map(|(hash, (position, score))| {
+                let asset = self.assets.get(hash).filter(|a| !a.path.is_empty())?;
+                let unit = asset.units.get(position)?;
+                Some(LexicalHit {
+                    content_hash: hash.to_string(),
+                    kind: asset.kind.clone(),
+                    path: asset.path.clone(),
+                    modified_at: asset.modified_at,
+                    indexed_at: asset.indexed_at,
+                    unit_kind: unit.unit_kind.clone(),
+                    unit_key: unit.unit_key.clone(),
+                    text: unit.text.clone(),
+                    score,
+                })
+            })
+            .filter(|hit| accept(hit))
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Records (or moves) an asset and re-indexes its path document. Empty or
+/// `None` fields keep what the index already has.
+pub fn set_asset(
+    content_hash: &str,
+    kind: &str,
+    path: &str,
+    modified_at: Option<i64>,
+    indexed_at: Option<i64>,
+) {
+    with_index(true, |index| {
+        let asset = index.assets.entry(content_hash.to_string()).or_default();
+        if !kind.is_empty() {
+            asset.kind = kind.to_string();
+        }
+        asset.path = path.to_string();
+        asset.modified_at = modified_at.or(asset.modified_at);
+        asset.indexed_at = indexed_at.or(asset.indexed_at);
+        index.put_unit(content_hash, PATH_UNIT_KIND, PATH_UNIT_KIND, path);
+    });
+}
+
+/// Mirrors one embedded unit. An asset first seen through its units stays out
+/// of results until its path is known, via `set_asset` or a backfill.
+pub fn add_unit(content_hash: &str, unit_kind: &str, unit_key: &str, content: &str) {
+    if SKIPPED_UNIT_KINDS.contains(&unit_kind) {
+        return;
+    }
+    with_index(true, |index| {
+        index.put_unit(content_hash, unit_kind, unit_key, content);
+    });
+}
+
+pub fn remove_asset(content_hash: &str) {
+    with_index(true, |index| {
+        index.remove(content_hash);
+    });
+}
+
+/// Whether the asset is searchable, i.e. indexed together with its path.
+pub fn has_asset(content_hash: &str) -> bool {
+    with_index(false, |index| {
+        index
+            .assets
+            .get(content_hash)
+            .is_some_and(|asset| !asset.path.is_empty())
+    })
+}
+
+pub fn clear() {
+    let snapshot = {
+        let mut guard = index().lock().unwrap_or_else(|e| e.into_inner());
+        *guard = LexicalIndex::default();
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        Snapshot::take(&guard)
+    };
+    snapshot.write_in_background();
+}
+
+/// Writes pending changes now instead of waiting for the next throttled save.
+/// Blocks on the write, so call it off the async workers.
+pub fn flush() {
+    let Some(index) = INDEX.get() else {
+        return;
+    };
+    let snapshot = {
+        let guard = index.lock().unwrap_or_else(|e| e.into_inner());
+        Snapshot::take(&guard)
+    };
+    snapshot.write();
+}
+
+/// Up to `limit` assets ranked by BM25, best first. `accept` filters assets
+/// before the cut, so a narrow filter still fills the page.
+pub fn search(query: &str, limit: usize, accept: &dyn Fn(&LexicalHit) -> bool) -> Vec<LexicalHit> {
+    with_index(false, |index| index.search(query, limit, accept))
+}
@@ -1,5 +1,12 @@
 pub mod adapters;
+pub mod collect;
+pub mod control;
 pub mod embedding;
+pub mod ignore;
 pub mod image;
+pub mod issues;
+pub mod lexical;
+pub mod model_cache;
+pub mod reconcile;
 pub mod text;
 pub mod video;
@@ -0,0 +1,461 @@
+//! Content-addressed on-disk cache of model outputs.
+//!
+//! Clearing the index or pointing at a fresh Helix instance would otherwise
+//! pay again for every embedding, transcription and frame summary. Entries
+//! are keyed by a hash of the input together with the provider, model and
+//! prompt version, so an unchanged input is served from disk and any change
+//! to how it is processed misses. Files live under
+//! `<data dir>/model_cache/<kind>/<provider>/`; the least recently used are
+//! evicted past `SIDECAR_MODEL_CACHE_MAX_MB` (0 disables the cache).
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, Once, OnceLock};
+use std::time::SystemTime;
+
+use crate::sidecar::data::{data_file, write_atomic};
+use crate::sidecar::protocol::{
+    err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
+};
+
+const CACHE_DIR: &str = "model_cache";
+const DEFAULT_MAX_MB: u64 = 1024;
+
+pub const EMBEDDING: &str = "embedding";
+pub const TRANSCRIPT: &str = "transcript";
+pub const FRAME_SUMMARY: &str = "frame_summary";
+pub const IMAGE_SUMMARY: &str = "image_summary";
+const KINDS: &[&str] = &[EMBEDDING, TRANSCRIPT, FRAME_SUMMARY, IMAGE_SUMMARY];
+
+/// Where one model output lives; built with [`key`].
+#[derive(Debug, Clone)]
+pub struct CacheKey {
+    kind: &'static str,
+    provider: String,
+    model: String,
+    digest: String,
+}
+
+impl CacheKey {
+    /// Path relative to the cache dir, also the index key.
+    fn relative(&self) -> String {
+        format!("{}/{}/{}.json", self.kind, self.provider, self.digest)
+    }
+}
+
+/// Keys `input` processed by `provider`'s `model`. `version` covers anything
+/// else that shapes the output, such as the prompt or input type.
+pub fn key(
+    kind: &'static str,
+    provider: &str,
+    model: &str,
+    version: &str,
+    input: &[u8],
+) -> CacheKey {
+    let mut hasher = Sha256::new();
+    for part in [
+        kind.as_bytes(),
+        provider.as_bytes(),
+        model.as_bytes(),
+        version.as_bytes(),
+    ] {
+        hasher.update(part);
+        hasher.update([0u8]);
+    }
+    hasher.update(input);
+    let provider: String = provider
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    CacheKey {
+        kind,
+        provider,
+        model: model.to_string(),
+        digest: format!("{:x}", hasher.finalize()),
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    kind: String,
+    provider: String,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// Index of the files on disk. Its lock only guards this bookkeeping; file
+/// reads, writes and deletes happen outside it.
+#[derive(Debug, Default)]
+struct ModelCache {
+    max_bytes: u64,
+    total_bytes: u64,
+    entries: HashMap<String, Entry>,
+    hits: u64,
+    misses: u64,
+    writes: u64,
+    evictions: u64,
+}
+
+static CACHE: OnceLock<Mutex<ModelCache>> = OnceLock::new();
+static SCAN: Once = Once::new();
+
+fn cache_dir() -> PathBuf {
+    data_file(CACHE_DIR)
+}
+
+/// The index, rebuilt from the files of earlier runs on first use. Blocks on
+/// that scan, so only call it off the async workers.
+fn lock() -> MutexGuard<'static, ModelCache> {
+    let cache = CACHE.get_or_init(|| {
+        let max_mb = env::var("SIDECAR_MODEL_CACHE_MAX_MB")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MAX_MB);
+        Mutex::new(ModelCache {
+            max_bytes: max_mb.saturating_mul(1024 * 1024),
+            ..ModelCache::default()
+        })
+    });
+    let guard = || {
+        cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    };
+    SCAN.call_once(|| {
+        if !guard().enabled() {
+            return;
+        }
+        let found = scan();
+        let doomed = {
+            let mut cache = guard();
+            for (relative, entry) in found {
+                // Entries written while the scan ran are already indexed.
+                if !cache.entries.contains_key(&relative) {
+                    cache.total_bytes += entry.size;
+                    cache.entries.insert(relative, entry);
+                }
+            }
+            cache.evict()
+        };
+        remove_files(&doomed);
+    });
+    guard()
+}
+
+fn read_dir_names(path: &PathBuf) -> Vec<String> {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Entries for the files left by earlier runs.
+fn scan() -> Vec<(String, Entry)> {
+    let root = cache_dir();
+    let mut found = Vec::new();
+    for kind in read_dir_names(&root) {
+        for provider in read_dir_names(&root.join(&kind)) {
+            let dir = root.join(&kind).join(&provider);
+            for name in read_dir_names(&dir) {
+                if !name.ends_with(".json") {
+                    continue;
+                }
+                let Ok(meta) = fs::metadata(dir.join(&name)) else {
+                    continue;
+                };
+                found.push((
+                    format!("{}/{}/{}", kind, provider, name),
+                    Entry {
+                        kind: kind.clone(),
+                        provider: provider.clone(),
+                        size: meta.len(),
+                        last_used: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    },
+                ));
+            }
+        }
+    }
+    found
+}
+
+fn remove_files(relatives: &[String]) {
+    let root = cache_dir();
+    for relative in relatives {
+        let _ = fs::remove_file(root.join(relative));
+    }
+}
+
+impl ModelCache {
+    fn enabled(&self) -> bool {
+        self.max_bytes > 0
+    }
+
+    /// Drops `relative` from the index; the caller deletes the file.
+    fn forget(&mut self, relative: &str) -> Option<Entry> {
+        let entry = self.entries.remove(relative)?;
+        self.total_bytes = self.total_bytes.saturating_sub(entry.size);
+        Some(entry)
+    }
+
+    /// Forgets the least recently used entries until the cache fits, and
+    /// returns them for the caller to delete.
+    fn evict(&mut self) -> Vec<String> {
+        if self.total_bytes <= self.max_bytes {
+            return Vec::new();
+        }
+        let mut by_age: Vec<(SystemTime, String)> = self
+            .entries
+            .iter()
+            .map(|(relative, entry)| (entry.last_used, relative.clone()))
+            .collect();
+        by_age.sort_unstable();
+        let mut doomed = Vec::new();
+        for (_, relative) in by_age {
+            if self.total_bytes <= self.max_bytes {
+                break;
+            }
+            self.forget(&relative);
+            self.evictions += 1;
+            doomed.push(relative);
+        }
+        doomed
+    }
+}
+
+fn lookup(key: &CacheKey) -> Option<Value> {
+    if !lock().enabled() {
+        return None;
+    }
+    let relative = key.relative();
+    let path = cache_dir().join(&relative);
+    let value = fs::read(&path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+        .and_then(|mut stored| stored.get_mut("value").map(Value::take));
+    let Some(value) = value else {
+        // A file removed or corrupted behind our back is just a miss.
+        let mut cache = lock();
+        cache.misses += 1;
+        if cache.forget(&relative).is_some() {
+            drop(cache);
+            let _ = fs::remove_file(&path);
+        }
+        return None;
+    };
+    let now = SystemTime::now();
+    // The file's mtime carries recency across restarts.
+    if let Ok(file) = File::options().append(true).open(&path) {
+        let _ = file.set_modified(now);
+    }
+    let mut cache = lock();
+    cache.hits += 1;
+    if let Some(entry) = cache.entries.get_mut(&relative) {
+        entry.last_used = now;
+    }
+    Some(value)
+}
+
+fn store(key: &CacheKey, value: &Value) {
+    if !lock().enabled() {
+        return;
+    }
+    let stored = json!({
+        "kind": key.kind,
+        "provider": key.provider,
+        "model": key.model,
+        "created_at": chrono::Utc::now().timestamp(),
+        "value": value,
+    });
+    let bytes = stored.to_string().into_bytes();
+    let relative = key.relative();
+    if let Err(error) = write_atomic(&cache_dir().join(&relative), &bytes) {
+        eprintln!("[sidecar:model_cache] failed to write entry: {}", error);
+        return;
+    }
+    let doomed = {
+        let mut cache = lock();
+        cache.forget(&relative);
+        cache.total_bytes += bytes.len() as u64;
+        cache.entries.insert(
+            relative,
+            Entry {
+                kind: key.kind.to_string(),
+                provider: key.provider.clone(),
+                size: bytes.len() as u64,
+                last_used: SystemTime::now(),
+            },
+        );
+        cache.writes += 1;
+        cache.evict()
+    };
+    remove_files(&doomed);
+}
+
+fn as_vector(value: Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_f64().map(|v| v as f32))
+        .collect()
+}
+
+/// The cached output for `key`, if any. Disk access runs on the blocking pool.
+pub async fn get(key: &CacheKey) -> Option<Value> {
+    let key = key.clone();
+    tokio::task::spawn_blocking(move || lookup(&key))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Stores `value` as the output for `key`, evicting old entries past the
+/// size limit. Failures are logged; the cache is only an optimization.
+pub async fn put(key: &CacheKey, value: &Value) {
+    let key = key.clone();
+    let value = value.clone();
+    let _ = tokio::task::spawn_blocking(move || store(&key, &value)).await;
+}
+
+/// Cached vectors for `keys`, looked up in one trip to the blocking pool.
+pub async fn get_vectors(keys: &[CacheKey]) -> Vec<Option<Vec<f32>>> {
+    let owned = keys.to_vec();
+    tokio::task::spawn_blocking(move || {
+        owned
+            .iter()
+            .map(|key| lookup(key).and_then(as_vector))
+            .collect()
+    })
+    .await
+    .unwrap_or_else(|_| vec![None; keys.len()])
+}
+
+pub async fn get_vector(key: &CacheKey) -> Option<Vec<f32>> {
+    as_vector(get(key).await?)
+}
+
+pub async fn put_vector(key: &CacheKey, vector: &[f32]) {
+    put(key, &json!(vector)).await;
+}
+
+fn stats() -> Value {
+    let cache = lock();
+    let mut kinds: Map<String, Value> = Map::new();
+    let mut providers: Map<String, Value> = Map::new();
+    for entry in cache.entries.values() {
+        for (group, name) in [(&mut kinds, &entry.kind), (&mut providers, &entry.provider)] {
+            let slot = group
+                .entry(name.clone())
+                .or_insert_with(|| json!({ "entries": 0, "bytes": 0 }));
+            slot["entries"] = json!(slot["entries"].as_u64().unwrap_or(0) + 1);
+            slot["bytes"] = json!(slot["bytes"].as_u64().unwrap_or(0) + entry.size);
+        }
+    }
+    let lookups = cache.hits + cache.misses;
+    json!({
+        "enabled": cache.enabled(),
+        "dir": cache_dir().to_string_lossy(),
+        "entries": cache.entries.len(),
+        "bytes": cache.total_bytes,
+        "max_bytes": cache.max_bytes,
+        "hits": cache.hits,
+        "misses": cache.misses,
+        "hit_rate": if lookups == 0 { 0.0 } else { cache.hits as f64 / lookups as f64 },
+        "writes": cache.writes,
+        "evictions": cache.evictions,
+        "kinds": kinds,
+        "providers": providers,
+    })
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PurgeParams {
+    /// Only entries of this kind; all kinds when omitted.
+    kind: Option<String>,
+    /// Only entries from this provider; all providers when omitted.
+    provider: Option<String>,
+}
+
+pub fn handle_stats(request: &JsonRpcRequest) -> JsonRpcResponse {
+    ok_response(request.id.clone(), stats())
+}
+
+pub fn handle_purge(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let parsed: PurgeParams = if request.params.as_ref().is_none_or(Value::is_null) {
+        PurgeParams::default()
+    } else {
+        match parse_params(request) {
+            Ok(parsed) => parsed,
+            Err(error_response) => return error_response,
+        }
+    };
+    let kind = parsed
+        .kind
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty());
+    if let Some(kind) = kind {
+        if !KINDS.contains(&kind) {
+            return err_response(
+                request.id.clone(),
+                -32602,
+                "Invalid params",
+                Some(json!({
+                    "reason": format!("unknown kind {:?}; expected one of {}", kind, KINDS.join(", "))
+                })),
+            );
+        }
+    }
+    let provider = parsed
+        .provider
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+
+    let (doomed, freed, remaining) = {
+        let mut cache = lock();
+        let doomed: Vec<String> = cache
+            .entries
+            .iter()
+            .filter(|(_, entry)| kind.is_none_or(|kind| entry.kind == kind))
+            .filter(|(_, entry)| provider.is_none_or(|provider| entry.provider == provider))
+            .map(|(relative, _)| relative.clone())
+            .collect();
+        let mut freed = 0u64;
+        for relative in &doomed {
+            if let Some(entry) = cache.forget(relative) {
+                freed += entry.size;
+            }
+        }
+        (doomed, freed, cache.entries.len())
+    };
+    remove_files(&doomed);
+    eprintln!(
+        "[sidecar:model_cache] purged {} entries ({} bytes)",
+        doomed.len(),
+        freed
+    );
+    ok_response(
+        request.id.clone(),
+        json!({
+            "removed": doomed.len(),
+            "freed_bytes": freed,
+            "remaining_entries": remaining,
+        }),
+    )
+}
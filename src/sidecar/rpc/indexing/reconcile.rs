@@ -0,0 +1,379 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::sidecar::rpc::indexing::adapters::hash::PathHasher;
+use crate::sidecar::rpc::indexing::adapters::store::{AssetCatalogStore, FileStamp, IndexedAsset};
+
+#[derive(Debug, Default, Clone)]
+pub struct KindPlan {
+    pub to_index: Vec<String>,
+    pub unchanged: usize,
+    pub moved: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub plans: HashMap<String, KindPlan>,
+    /// Hashes computed while reconciling, keyed by path, so indexers can reuse them.
+    pub hashes: HashMap<String, String>,
+    /// `(from, to)` for every asset whose path was updated in place.
+    pub moves: Vec<(String, String)>,
+    /// Assets dropped because their file changed content.
+    pub replaced: usize,
+    /// Paths whose asset was dropped because the file is gone (or now ignored).
+    pub removed_paths: Vec<String>,
+    pub errors: Vec<(String, String)>,
+}
+
+impl ReconcileReport {
+    pub fn take_plan(&mut self, kind: &str) -> KindPlan {
+        self.plans.remove(kind).unwrap_or_default()
+    }
+}
+
+pub fn normalize_root(root: &str) -> String {
+    let normalized = root.replace('\\', "/");
+    let trimmed = normalized.trim_end_matches('/');
+    if trimmed.is_empty() {
+        normalized
+    } else {
+        trimmed.to_string()
+    }
+}
+
+pub fn is_under_root(root: &str, path: &str) -> bool {
+    path == root
+        || path
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.starts_with('/') || root.ends_with('/'))
+}
+
+/// A file its stamp did not vouch for, with the hash of its content.
+struct HashedFile {
+    path: String,
+    content_hash: String,
+    stamp: Option<FileStamp>,
+}
+
+struct Reconciler<'a> {
+    hasher: &'a dyn PathHasher,
+    catalog: &'a dyn AssetCatalogStore,
+    /// Assets this run is responsible for; anything left unclaimed may be deleted.
+    by_path: HashMap<String, IndexedAsset>,
+    by_hash: HashMap<String, IndexedAsset>,
+    /// Hashes whose asset has been matched to a file on disk this run.
+    claimed: HashSet<String>,
+    /// Content hashes of every file hashed this run.
+    on_disk: HashSet<String>,
+    /// Hashes of assets whose path now holds other content, free to move to
+    /// another file that still holds theirs.
+    released: HashSet<String>,
+    report: ReconcileReport,
+}
+
+impl<'a> Reconciler<'a> {
+    async fn load(
+        hasher: &'a dyn PathHasher,
+        catalog: &'a dyn AssetCatalogStore,
+        in_scope: impl Fn(&IndexedAsset) -> bool,
+    ) -> Result<Self, String> {
+        let assets = catalog.list_assets().await?;
+        let by_path = assets
+            .iter()
+            .filter(|asset| in_scope(asset))
+            .map(|asset| (asset.path.clone(), asset.clone()))
+            .collect();
+        let by_hash = assets
+            .into_iter()
+            .map(|asset| (asset.content_hash.clone(), asset))
+            .collect();
+        Ok(Self {
+            hasher,
+            catalog,
+            by_path,
+            by_hash,
+            claimed: HashSet::new(),
+            on_disk: HashSet::new(),
+            released: HashSet::new(),
+            report: ReconcileReport::default(),
+        })
+    }
+
+    /// First half of reconciling a file: keeps it if its stamp vouches for
+    /// it, otherwise hashes it. Every file of a run is surveyed before any is
+    /// settled, so no asset is dropped while another file still holds it.
+    async fn survey_file(&mut self, plan: &mut KindPlan, path: String) -> Option<HashedFile> {
+        let stamp = FileStamp::read(&path);
+
+        if let Some(asset) = self.by_path.get(&path) {
+            if stamp.is_some() && asset.stamp == stamp {
+                match self.catalog.asset_is_complete(asset).await {
+                    Ok(true) => {
+                        self.claimed.insert(asset.content_hash.clone());
+                        plan.unchanged += 1;
+                        return None;
+                    }
+                    Ok(false) => {}
+                    Err(error) => self.report.errors.push((path.clone(), error)),
+                }
+            }
+        }
+
+        let content_hash = match self.hasher.compute_file_hash(&path).await {
+            Ok(hash) => hash,
+            Err(_) => {
+                // The indexer hashes again and reports the failure per file.
+                plan.to_index.push(path);
+                return None;
+            }
+        };
+        self.report
+            .hashes
+            .insert(path.clone(), content_hash.clone());
+        self.on_disk.insert(content_hash.clone());
+        if let Some(asset) = self.by_path.get(&path) {
+            if asset.content_hash != content_hash {
+                self.released.insert(asset.content_hash.clone());
+            }
+        }
+        Some(HashedFile {
+            path,
+            content_hash,
+            stamp,
+        })
+    }
+
+    /// Second half of reconciling a file: matches its hash to the asset at
+    /// its path or to one it can move, dropping the asset its path used to
+    /// hold unless another file of this run still holds that content.
+    async fn settle_file(
+        &mut self,
+        plan: &mut KindPlan,
+        file: HashedFile,
+        vanished: &dyn Fn(&IndexedAsset) -> bool,
+    ) {
+        let HashedFile {
+            path,
+            content_hash,
+            stamp,
+        } = file;
+
+        if let Some(asset) = self.by_path.get(&path).cloned() {
+            if asset.content_hash == content_hash {
+                self.claimed.insert(content_hash.clone());
+                let complete = self
+                    .catalog
+                    .asset_is_complete(&asset)
+                    .await
+                    .unwrap_or(false);
+                if !complete {
+                    plan.to_index.push(path);
+                    return;
+                }
+                if let Some(stamp) = stamp {
+                    if let Err(error) = self
+                        .catalog
+                        .update_asset_location(&content_hash, &path, stamp)
+                        .await
+                    {
+                        self.report.errors.push((path.clone(), error));
+                    }
+                }
+                plan.unchanged += 1;
+                return;
+            }
+
+            let held_elsewhere = self.claimed.contains(&asset.content_hash)
+                || self.on_disk.contains(&asset.content_hash);
+            if !held_elsewhere {
+                match self.catalog.delete_asset(&asset.content_hash).await {
+                    Ok(()) => {
+                        eprintln!(
+                            "[sidecar:index:reconcile] content changed for {}, dropped stale asset {}",
+                            path, asset.content_hash
+                        );
+                        self.report.replaced += 1;
+                        self.by_hash.remove(&asset.content_hash);
+                    }
+                    Err(error) => self.report.errors.push((path.clone(), error)),
+                }
+            }
+            self.by_path.remove(&path);
+        }
+
+        if let Some(asset) = self.by_hash.get(&content_hash).cloned() {
+            let movable = vanished(&asset) || self.released.contains(&content_hash);
+            if movable && !self.claimed.contains(&content_hash) {
+                match self
+                    .catalog
+                    .update_asset_location(&content_hash, &path, stamp.unwrap_or_default())
+                    .await
+                {
+                    Ok(()) => {
+                        eprintln!("[sidecar:index:reconcile] moved {} -> {}", asset.path, path);
+                        self.claimed.insert(content_hash.clone());
+                        self.by_path.remove(&asset.path);
+                        self.report.moves.push((asset.path.clone(), path.clone()));
+                        self.by_hash.insert(
+                            content_hash.clone(),
+                            IndexedAsset {
+                                path,
+                                stamp,
+                                ..asset
+                            },
+                        );
+                        plan.moved += 1;
+                        return;
+                    }
+                    Err(error) => self.report.errors.push((path.clone(), error)),
+                }
+            }
+        }
+
+        plan.to_index.push(path);
+    }
+
+    /// Deletes every in-scope asset that was not claimed and that `keep` rejects.
+    async fn remove_unclaimed(&mut self, keep: impl Fn(&IndexedAsset) -> bool) {
+        let stale: Vec<IndexedAsset> = self
+            .by_path
+            .values()
+            .filter(|asset| !keep(asset) && !self.claimed.contains(&asset.content_hash))
+            .cloned()
+            .collect();
+
+        for asset in stale {
+            match self.catalog.delete_asset(&asset.content_hash).await {
+                Ok(()) => {
+                    eprintln!(
+                        "[sidecar:index:reconcile] removed asset for missing file {}",
+                        asset.path
+                    );
+                    self.report.removed_paths.push(asset.path);
+                }
+                Err(error) => self.report.errors.push((asset.path, error)),
+            }
+        }
+    }
+}
+
+/// Drops assets created for files that failed part-way through indexing, so
+/// the next run doesn't mistake them for unchanged files. Pass only hashes the
+/// failed run created itself: another path may share an asset that was
+/// already there.
+pub async fn drop_partial_assets<'a>(
+    catalog: &dyn AssetCatalogStore,
+    content_hashes: impl Iterator<Item = &'a str>,
+) {
+    for content_hash in content_hashes {
+        if let Err(error) = catalog.delete_asset(content_hash).await {
+            eprintln!(
+                "[sidecar:index] failed to roll back partial asset {}: {}",
+                content_hash, error
+            );
+        }
+    }
+}
+
+/// Reconciles the collected files of one `index.start` root against the assets
+/// already in the store:
+///
+/// - files whose size and mtime match their asset are left alone without hashing,
+/// - files whose content hash belongs to an asset at a vanished path, or at a
+///   path that now holds other content, are moved,
+/// - files whose content changed have their stale asset and embeddings
+///   dropped, unless another collected file still holds that content,
+/// - assets under `root` with no collected file are deleted.
+///
+/// Everything else is returned in the per-kind plans for the indexers.
+pub async fn reconcile(
+    root: &str,
+    files_by_kind: Vec<(&str, Vec<String>)>,
+    hasher: &dyn PathHasher,
+    catalog: &dyn AssetCatalogStore,
+) -> Result<ReconcileReport, String> {
+    let root = normalize_root(root);
+    let collected: HashSet<String> = files_by_kind
+        .iter()
+        .flat_map(|(_, files)| files.iter().cloned())
+        .collect();
+
+    let mut reconciler =
+        Reconciler::load(hasher, catalog, |asset| is_under_root(&root, &asset.path)).await?;
+    let vanished = |asset: &IndexedAsset| {
+        !collected.contains(&asset.path)
+            && (is_under_root(&root, &asset.path) || !Path::new(&asset.path).exists())
+    };
+
+    let mut surveyed = Vec::new();
+    for (kind, files) in files_by_kind {
+        let mut plan = KindPlan::default();
+        let mut hashed = Vec::new();
+        for path in files {
+            hashed.extend(reconciler.survey_file(&mut plan, path).await);
+        }
+        surveyed.push((kind, plan, hashed));
+    }
+    for (kind, mut plan, hashed) in surveyed {
+        for file in hashed {
+            reconciler.settle_file(&mut plan, file, &vanished).await;
+        }
+        reconciler.report.plans.insert(kind.to_string(), plan);
+    }
+
+    reconciler
+        .remove_unclaimed(|asset| collected.contains(&asset.path))
+        .await;
+    Ok(reconciler.report)
+}
+
+/// Reconciles a batch of individually changed paths, e.g. from the watcher.
+/// `changed` holds files that exist and passed filtering; `removed` holds
+/// paths that no longer exist (or are now ignored). Renames arrive as one of
+/// each and are turned into a move.
+pub async fn reconcile_changes(
+    changed: Vec<(&str, String)>,
+    removed: Vec<String>,
+    hasher: &dyn PathHasher,
+    catalog: &dyn AssetCatalogStore,
+) -> Result<ReconcileReport, String> {
+    let touched: HashSet<String> = changed
+        .iter()
+        .map(|(_, path)| path.clone())
+        .chain(removed.iter().cloned())
+        .collect();
+    let removed: Vec<String> = removed.iter().map(|path| normalize_root(path)).collect();
+
+    // A removed directory arrives as a single path, so match its descendants too.
+    let was_removed = |path: &str| {
+        removed
+            .iter()
+            .any(|removed_path| is_under_root(removed_path, path))
+    };
+
+    let mut reconciler = Reconciler::load(hasher, catalog, |asset| {
+        touched.contains(&asset.path) || was_removed(&asset.path)
+    })
+    .await?;
+    let vanished =
+        |asset: &IndexedAsset| was_removed(&asset.path) || !Path::new(&asset.path).exists();
+
+    let mut plans: HashMap<String, KindPlan> = HashMap::new();
+    let mut hashed = Vec::new();
+    for (kind, path) in changed {
+        let plan = plans.entry(kind.to_string()).or_default();
+        if let Some(file) = reconciler.survey_file(plan, path).await {
+            hashed.push((kind, file));
+        }
+    }
+    for (kind, file) in hashed {
+        let plan = plans.entry(kind.to_string()).or_default();
+        reconciler.settle_file(plan, file, &vanished).await;
+    }
+    reconciler.report.plans = plans;
+
+    reconciler
+        .remove_unclaimed(|asset| !was_removed(&asset.path))
+        .await;
+    Ok(reconciler.report)
+}
@@ -0,0 +1,250 @@
+use std::env;
+
+pub const CHUNK_UNIT_KIND: &str = "file_chunk";
+
+const DEFAULT_CHUNK_CHARS: usize = 2_000;
+const DEFAULT_CHUNK_OVERLAP_CHARS: usize = 200;
+const MIN_CHUNK_CHARS: usize = 200;
+
+/// Chunk budgets, counted in chars so non-ASCII text gets chunks as long as
+/// ASCII text does.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkConfig {
+    pub max_chars: usize,
+    pub overlap_chars: usize,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self {
+            max_chars: DEFAULT_CHUNK_CHARS,
+            overlap_chars: DEFAULT_CHUNK_OVERLAP_CHARS,
+        }
+    }
+}
+
+impl ChunkConfig {
+    pub fn from_env() -> Self {
+        let max_chars = env::var("SIDECAR_TEXT_CHUNK_CHARS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_CHUNK_CHARS)
+            .max(MIN_CHUNK_CHARS);
+        let overlap_chars = env::var("SIDECAR_TEXT_CHUNK_OVERLAP_CHARS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_CHUNK_OVERLAP_CHARS)
+            // Overlap must leave room for new content, otherwise chunking never advances.
+            .min(max_chars / 2);
+        Self {
+            max_chars,
+            overlap_chars,
+        }
+    }
+}
+
+/// One embeddable slice of a text file. Byte offsets are half-open and line
+/// numbers are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    pub index: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSpan {
+    pub index: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+impl TextChunk {
+    pub fn span(&self) -> ChunkSpan {
+        ChunkSpan {
+            index: self.index,
+            byte_start: self.byte_start,
+            byte_end: self.byte_end,
+            line_start: self.line_start,
+            line_end: self.line_end,
+        }
+    }
+
+    pub fn unit_key(&self) -> String {
+        self.span().unit_key()
+    }
+}
+
+impl ChunkSpan {
+    /// Encodes the span as `chunk_{index}:b{start}-{end}:l{start}-{end}` so search
+    /// results can point back into the file without another store lookup.
+    pub fn unit_key(&self) -> String {
+        format!(
+            "chunk_{}:b{}-{}:l{}-{}",
+            self.index, self.byte_start, self.byte_end, self.line_start, self.line_end
+        )
+    }
+
+    pub fn parse_unit_key(unit_key: &str) -> Option<Self> {
+        let mut parts = unit_key.split(':');
+        let index = parts.next()?.strip_prefix("chunk_")?.parse().ok()?;
+        let (byte_start, byte_end) = parse_range(parts.next()?.strip_prefix('b')?)?;
+        let (line_start, line_end) = parse_range(parts.next()?.strip_prefix('l')?)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            index,
+            byte_start,
+            byte_end,
+            line_start,
+            line_end,
+        })
+    }
+}
+
+fn parse_range(value: &str) -> Option<(usize, usize)> {
+    let (start, end) = value.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    start: usize,
+    end: usize,
+    chars: usize,
+    line: usize,
+    // A paragraph or heading starts here, which makes it a preferred cut point.
+    boundary: bool,
+    heading: bool,
+}
+
+impl Segment {
+    fn len(&self) -> usize {
+        self.chars
+    }
+}
+
+fn is_heading(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && trimmed[hashes..].starts_with([' ', '\t'])
+}
+
+fn split_segments(content: &str, max_chars: usize) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut offset = 0usize;
+    let mut previous_blank = true;
+
+    for (line_idx, line) in content.split_inclusive('\n').enumerate() {
+        let blank = line.trim().is_empty();
+        let heading = !blank && is_heading(line);
+        let boundary = !blank && (previous_blank || heading);
+        previous_blank = blank;
+
+        // Very long lines (minified files, logs) are split into windows of
+        // `max_chars` chars so no single segment can exceed the chunk budget.
+        let mut piece_start = 0usize;
+        let mut first_piece = true;
+        while piece_start < line.len() {
+            let rest = &line[piece_start..];
+            let (chars, piece_len) = rest
+                .char_indices()
+                .nth(max_chars)
+                .map_or((rest.chars().count(), rest.len()), |(end, _)| {
+                    (max_chars, end)
+                });
+            let piece_end = piece_start + piece_len;
+            segments.push(Segment {
+                start: offset + piece_start,
+                end: offset + piece_end,
+                chars,
+                line: line_idx + 1,
+                boundary: boundary && first_piece,
+                heading: heading && first_piece,
+            });
+            first_piece = false;
+            piece_start = piece_end;
+        }
+
+        offset += line.len();
+    }
+
+    segments
+}
+
+/// Splits `content` into overlapping chunks. Cuts prefer headings and paragraph
+/// breaks, fall back to line windows, and only split inside a line when the
+/// line alone exceeds `max_chars`.
+pub fn chunk_text(content: &str, config: &ChunkConfig) -> Vec<TextChunk> {
+    let max_chars = config.max_chars.max(1);
+    let segments = split_segments(content, max_chars);
+    let mut chunks = Vec::new();
+    let mut start = 0usize;
+
+    while start < segments.len() {
+        let mut end = start;
+        let mut len = 0usize;
+        let mut last_boundary: Option<(usize, usize)> = None;
+        let mut hit_size_limit = false;
+
+        while end < segments.len() {
+            let segment = segments[end];
+            if end > start {
+                if len + segment.len() > max_chars {
+                    hit_size_limit = true;
+                    break;
+                }
+                if segment.heading && len >= max_chars / 4 {
+                    break;
+                }
+                if segment.boundary {
+                    last_boundary = Some((end, len));
+                }
+            }
+            len += segment.len();
+            end += 1;
+        }
+
+        if hit_size_limit {
+            if let Some((boundary, boundary_len)) = last_boundary {
+                if boundary_len >= max_chars / 2 {
+                    end = boundary;
+                }
+            }
+        }
+
+        let byte_start = segments[start].start;
+        let byte_end = segments[end - 1].end;
+        let text = &content[byte_start..byte_end];
+        if !text.trim().is_empty() {
+            chunks.push(TextChunk {
+                index: chunks.len(),
+                byte_start,
+                byte_end,
+                line_start: segments[start].line,
+                line_end: segments[end - 1].line,
+                text: text.to_string(),
+            });
+        }
+
+        if end >= segments.len() {
+            break;
+        }
+
+        let mut next = end;
+        let mut overlap = 0usize;
+        while next > start + 1 && overlap + segments[next - 1].len() <= config.overlap_chars {
+            next -= 1;
+            overlap += segments[next].len();
+        }
+        start = next;
+    }
+
+    chunks
+}
@@ -1,6 +1,13 @@
-use crate::sidecar::rpc::fs::walk_and_get_files_content;
+pub mod chunk;
+
+use crate::sidecar::rpc::fs::{
+    read_text_file, text_max_file_bytes, BINARY_CONTENT, FILE_TOO_LARGE,
+};
+use crate::sidecar::rpc::indexing::adapters::embeddings::embed_flush_units;
 use crate::sidecar::rpc::indexing::adapters::hash::PathHasher;
-use crate::sidecar::rpc::indexing::adapters::store::TextIndexStore;
+use crate::sidecar::rpc::indexing::adapters::store::{EmbeddingUnit, TextIndexStore};
+use crate::sidecar::rpc::indexing::control::JobControl;
+use chunk::{chunk_text, ChunkConfig, CHUNK_UNIT_KIND};
 use std::path::Path;
 
 #[derive(Debug, Clone)]
@@ -10,6 +17,21 @@ pub struct TextIndexResult {
     pub content_hash: Option<String>,
     pub path: String,
     pub error: Option<String>,
+    /// Whether this run created the asset, so a failure may roll it back.
+    pub created: bool,
+}
+
+pub const DUPLICATE_CONTENT_HASH: &str = "Duplicate content hash";
+
+impl TextIndexResult {
+    /// Duplicates, oversized files and binary files are expected outcomes of a
+    /// pass and are counted as skips rather than errors.
+    pub fn is_skipped(&self) -> bool {
+        matches!(
+            self.error.as_deref(),
+            Some(DUPLICATE_CONTENT_HASH) | Some(FILE_TOO_LARGE) | Some(BINARY_CONTENT)
+        )
+    }
 }
 
 fn normalize_paths(file_paths: Vec<String>) -> Vec<String> {
@@ -20,133 +42,186 @@ fn normalize_paths(file_paths: Vec<String>) -> Vec<String> {
         .collect()
 }
 
+/// A unit waiting for the next batch, with the file result it reports to.
+struct PendingUnit {
+    result_index: usize,
+    /// Chunk label for error messages; `None` for the path unit, whose
+    /// failure is only a warning.
+    chunk: Option<String>,
+    unit: EmbeddingUnit,
+}
+
+async fn flush_pending(
+    store: &dyn TextIndexStore,
+    pending: &mut Vec<PendingUnit>,
+    results: &mut [TextIndexResult],
+) {
+    if pending.is_empty() {
+        return;
+    }
+    let units: Vec<EmbeddingUnit> = pending.iter().map(|p| p.unit.clone()).collect();
+    let outcomes = store.create_file_asset_embeddings_batch(&units).await;
+    for (unit, outcome) in pending.drain(..).zip(outcomes) {
+        let Err(error) = outcome else {
+            continue;
+        };
+        let result = &mut results[unit.result_index];
+        match unit.chunk {
+            Some(chunk) => {
+                // Report the first chunk that failed.
+                if result.error.is_none() {
+                    result.indexed = false;
+                    result.error = Some(format!("{} failed: {}", chunk, error));
+                }
+            }
+            None => eprintln!(
+                "[sidecar:index:text] warning: failed to create path embedding for {}: {}",
+                result.path, error
+            ),
+        }
+    }
+}
+
 pub async fn file_indexer(
     file_paths: Vec<String>,
     hasher: &dyn PathHasher,
     store: &dyn TextIndexStore,
+    control: &JobControl,
 ) -> Vec<TextIndexResult> {
     let paths = normalize_paths(file_paths);
     if paths.is_empty() {
         return Vec::new();
     }
 
+    let chunk_config = ChunkConfig::from_env();
+    let max_file_bytes = text_max_file_bytes();
+    let flush_units = embed_flush_units();
     let mut results: Vec<TextIndexResult> = Vec::new();
+    let mut pending: Vec<PendingUnit> = Vec::new();
+
+    for file_path in paths {
+        if control.checkpoint().await.is_err() {
+            break;
+        }
 
-    for path in &paths {
-        let files_content = match walk_and_get_files_content(path.clone()) {
+        let content = match read_text_file(&file_path, max_file_bytes) {
             Ok(content) => content,
             Err(error) => {
                 results.push(TextIndexResult {
-                    path: path.clone(),
+                    path: file_path,
                     indexed: false,
                     kind: "file".to_string(),
                     content_hash: None,
                     error: Some(error),
+                    created: false,
                 });
                 continue;
             }
         };
 
-        for (file_path, content) in files_content {
-            let content_hash = match hasher.compute_file_hash(&file_path).await {
-                Ok(hash) => hash,
-                Err(error) => {
-                    results.push(TextIndexResult {
-                        path: file_path,
-                        indexed: false,
-                        kind: "file".to_string(),
-                        content_hash: None,
-                        error: Some(error),
-                    });
-                    continue;
-                }
-            };
-
-            let existing = match store.get_file_by_hash(&content_hash).await {
-                Ok(existing) => existing,
-                Err(error) => {
-                    results.push(TextIndexResult {
-                        indexed: false,
-                        kind: "file".to_string(),
-                        path: file_path,
-                        content_hash: Some(content_hash.clone()),
-                        error: Some(format!("store lookup failed: {}", error)),
-                    });
-                    continue;
-                }
-            };
-
-            if let Some(_record) = existing {
-                results.push(TextIndexResult {
-                    indexed: false,
-                    kind: "file".to_string(),
-                    path: file_path,
-                    content_hash: Some(content_hash),
-                    error: Some("Duplicate content hash".to_string()),
-                });
-                continue;
-            }
-
-            let kind = "file";
-            if let Err(error) = store
-                .create_file_asset(&content_hash, kind, &file_path)
-                .await
-            {
+        let content_hash = match hasher.compute_file_hash(&file_path).await {
+            Ok(hash) => hash,
+            Err(error) => {
                 results.push(TextIndexResult {
                     path: file_path,
                     indexed: false,
-                    kind: kind.to_string(),
-                    content_hash: Some(content_hash.clone()),
+                    kind: "file".to_string(),
+                    content_hash: None,
                     error: Some(error),
+                    created: false,
                 });
                 continue;
             }
+        };
 
-            if let Err(error) = store
-                .create_file_asset_embeddings(&content_hash, "file_body", "file_body", &content)
-                .await
-            {
+        let existing = match store.get_file_by_hash(&content_hash).await {
+            Ok(existing) => existing,
+            Err(error) => {
                 results.push(TextIndexResult {
-                    path: file_path,
                     indexed: false,
-                    kind: kind.to_string(),
+                    kind: "file".to_string(),
+                    path: file_path,
                     content_hash: Some(content_hash.clone()),
-                    error: Some(error),
+                    error: Some(format!("store lookup failed: {}", error)),
+                    created: false,
                 });
                 continue;
             }
+        };
 
-            let filename_text = Path::new(&file_path)
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or_default()
-                .replace(['#', '_', '-', '.'], " ");
-            if !filename_text.trim().is_empty() {
-                if let Err(error) = store
-                    .create_file_asset_embeddings(
-                        &content_hash,
-                        "file_path",
-                        "file_path",
-                        &filename_text,
-                    )
-                    .await
-                {
-                    eprintln!(
-                        "[sidecar:index:text] warning: failed to create path embedding for {}: {}",
-                        file_path, error
-                    );
-                }
-            }
+        if let Some(_record) = existing {
+            results.push(TextIndexResult {
+                indexed: false,
+                kind: "file".to_string(),
+                path: file_path,
+                content_hash: Some(content_hash),
+                error: Some(DUPLICATE_CONTENT_HASH.to_string()),
+                created: false,
+            });
+            continue;
+        }
 
+        let kind = "file";
+        if let Err(error) = store
+            .create_file_asset(&content_hash, kind, &file_path)
+            .await
+        {
             results.push(TextIndexResult {
                 path: file_path,
-                indexed: true,
+                indexed: false,
                 kind: kind.to_string(),
-                content_hash: Some(content_hash),
-                error: None,
+                content_hash: Some(content_hash.clone()),
+                error: Some(error),
+                created: false,
+            });
+            continue;
+        }
+
+        let result_index = results.len();
+        results.push(TextIndexResult {
+            path: file_path.clone(),
+            indexed: true,
+            kind: kind.to_string(),
+            content_hash: Some(content_hash.clone()),
+            error: None,
+            created: true,
+        });
+        for chunk in chunk_text(&content, &chunk_config) {
+            pending.push(PendingUnit {
+                result_index,
+                chunk: Some(format!(
+                    "chunk {} (lines {}-{})",
+                    chunk.index, chunk.line_start, chunk.line_end
+                )),
+                unit: EmbeddingUnit::new(
+                    &content_hash,
+                    CHUNK_UNIT_KIND,
+                    &chunk.unit_key(),
+                    &chunk.text,
+                ),
+            });
+        }
+
+        let filename_text = Path::new(&file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .replace(['#', '_', '-', '.'], " ");
+        if !filename_text.trim().is_empty() {
+            pending.push(PendingUnit {
+                result_index,
+                chunk: None,
+                unit: EmbeddingUnit::new(&content_hash, "file_path", "file_path", &filename_text),
             });
         }
+
+        if pending.len() >= flush_units {
+            flush_pending(store, &mut pending, &mut results).await;
+        }
     }
 
+    // Files already created still get their units when the job stops early.
+    flush_pending(store, &mut pending, &mut results).await;
+
     results
 }
@@ -1,5 +1,7 @@
+use crate::sidecar::rpc::indexing::adapters::embeddings::embed_flush_units;
 use crate::sidecar::rpc::indexing::adapters::groq::TranscriptionClient;
-use crate::sidecar::rpc::indexing::adapters::store::VideoIndexStore;
+use crate::sidecar::rpc::indexing::adapters::store::{EmbeddingUnit, VideoIndexStore};
+use crate::sidecar::rpc::indexing::control::JobControl;
 use crate::sidecar::rpc::indexing::embedding::build_embedding_text;
 use async_trait::async_trait;
 use serde_json::Value;
@@ -9,6 +11,10 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use tokio::task::JoinSet;
 
+pub mod span;
+
+use span::{VideoSpan, FRAME_SUMMARY_UNIT_KIND, TRANSCRIPT_UNIT_KIND};
+
 #[derive(Clone, Debug)]
 pub struct VideoIndexResult {
     pub content_hash: Option<String>,
@@ -23,6 +29,9 @@ struct ChunkArtifact {
     chunk_path: String,
     audio_path: Option<String>,
     thumbnail_paths: Vec<String>,
+    /// Measured length of the chunk; ffmpeg cuts at keyframes, so chunks are
+    /// only roughly `chunk_duration_secs` long.
+    duration_secs: Option<f64>,
 }
 
 #[async_trait]
@@ -41,12 +50,20 @@ trait VideoIndexerDeps: Send + Sync {
         thumbnails_dir: String,
     ) -> Result<Vec<ChunkArtifact>, String>;
 
-    async fn generate_transcripts(&self, artifacts: &[ChunkArtifact]) -> HashMap<String, Value>;
+    /// Transcripts by chunk; fails if any chunk's transcription failed, so
+    /// the video is retried instead of indexed with gaps.
+    async fn generate_transcripts(
+        &self,
+        artifacts: &[ChunkArtifact],
+        control: &JobControl,
+    ) -> Result<HashMap<String, Value>, String>;
 
+    /// Frame summaries by chunk; fails like `generate_transcripts`.
     async fn generate_frame_summaries(
         &self,
         artifacts: &[ChunkArtifact],
-    ) -> HashMap<String, Vec<Value>>;
+        control: &JobControl,
+    ) -> Result<HashMap<String, Vec<Value>>, String>;
 }
 
 #[derive(Clone)]
@@ -86,15 +103,20 @@ where
         build_chunk_artifacts(chunk_paths, audio_dir, thumbnails_dir).await
     }
 
-    async fn generate_transcripts(&self, artifacts: &[ChunkArtifact]) -> HashMap<String, Value> {
-        generate_transcripts(&self.groq, artifacts).await
+    async fn generate_transcripts(
+        &self,
+        artifacts: &[ChunkArtifact],
+        control: &JobControl,
+    ) -> Result<HashMap<String, Value>, String> {
+        generate_transcripts(&self.groq, artifacts, control).await
     }
 
     async fn generate_frame_summaries(
         &self,
         artifacts: &[ChunkArtifact],
-    ) -> HashMap<String, Vec<Value>> {
-        generate_frame_summaries(&self.groq, artifacts).await
+        control: &JobControl,
+    ) -> Result<HashMap<String, Vec<Value>>, String> {
+        generate_frame_summaries(&self.groq, artifacts, control).await
     }
 }
 
@@ -102,6 +124,17 @@ fn normalize_path(path: &str) -> String {
     path.replace('\\', "/")
 }
 
+pub const DEFAULT_CHUNK_DURATION_SECS: f64 = 30.0;
+
+/// Working directory for chunks, audio and thumbnails produced while indexing.
+pub fn default_output_dir() -> String {
+    std::env::current_dir()
+        .map(|d| d.join("videos").join("output_indexer"))
+        .unwrap_or_else(|_| Path::new("videos/output_indexer").to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
 fn infer_thumbnail_cache_dir(output_dir: &str) -> PathBuf {
     Path::new(output_dir).join("thumbnail_cache")
 }
@@ -142,6 +175,49 @@ fn cache_video_thumbnail(
     Ok(Some(normalize_path(&target.to_string_lossy())))
 }
 
+/// Name of the cached preview for one chunk, next to the whole-video one.
+pub fn chunk_thumbnail_file_name(content_hash: &str, chunk_index: usize) -> String {
+    format!("{}_chunk_{}.jpg", content_hash, chunk_index)
+}
+
+/// Caches each chunk's middle frame (falling back to any frame) so search
+/// hits can show the matching moment rather than the video's first chunk.
+fn cache_chunk_thumbnails(
+    content_hash: &str,
+    output_dir: &str,
+    artifacts: &[ChunkArtifact],
+) -> Result<(), String> {
+    if content_hash.trim().is_empty() {
+        return Ok(());
+    }
+    let cache_dir = infer_thumbnail_cache_dir(output_dir);
+    fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+
+    for (chunk_index, artifact) in artifacts.iter().enumerate() {
+        let source = artifact
+            .thumbnail_paths
+            .get(1)
+            .into_iter()
+            .chain(artifact.thumbnail_paths.iter())
+            .find(|path| Path::new(path).exists());
+        let Some(source) = source else {
+            continue;
+        };
+        let target = cache_dir.join(chunk_thumbnail_file_name(content_hash, chunk_index));
+        if !target.exists() {
+            fs::copy(source, &target).map_err(|e| {
+                format!(
+                    "failed to cache chunk thumbnail from {} to {}: {}",
+                    source,
+                    target.to_string_lossy(),
+                    e
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
 fn check_video_duration(video_path: &str) -> Result<f64, String> {
     let output = Command::new("ffprobe")
         .arg("-v")
@@ -284,12 +360,16 @@ fn extract_audio(chunk_path: &str, audio_dir: &str) -> Result<Option<String>, St
     Ok(Some(output_path))
 }
 
-fn extract_thumbnails(chunk_path: &str, chunk_thumbs_dir: &str) -> Result<Vec<String>, String> {
+fn extract_thumbnails(
+    chunk_path: &str,
+    chunk_thumbs_dir: &str,
+    duration: Option<f64>,
+) -> Result<Vec<String>, String> {
     let normalized_chunk_path = normalize_path(chunk_path);
     let normalized_thumbs_dir = normalize_path(chunk_thumbs_dir);
     fs::create_dir_all(&normalized_thumbs_dir).map_err(|e| e.to_string())?;
 
-    let duration = check_video_duration(&normalized_chunk_path).unwrap_or(3.0);
+    let duration = duration.unwrap_or(3.0);
     let (start_ts, middle_ts, end_ts) = if duration.is_finite() && duration > 0.0 {
         let epsilon = 0.1_f64;
         let end_offset = 0.2_f64;
@@ -353,13 +433,15 @@ async fn build_chunk_artifacts(
                 .to_string();
             let chunk_thumb_dir =
                 format!("{}/{}", normalize_path(&thumbnails_dir_clone), chunk_name);
+            let duration_secs = check_video_duration(&normalize_path(&chunk_path)).ok();
             let audio = extract_audio(&chunk_path, &audio_dir_clone)?;
-            let thumbs = extract_thumbnails(&chunk_path, &chunk_thumb_dir)?;
+            let thumbs = extract_thumbnails(&chunk_path, &chunk_thumb_dir, duration_secs)?;
 
             Ok::<ChunkArtifact, String>(ChunkArtifact {
                 chunk_path,
                 audio_path: audio,
                 thumbnail_paths: thumbs,
+                duration_secs,
             })
         });
     }
@@ -376,7 +458,38 @@ async fn build_chunk_artifacts(
     Ok(artifacts)
 }
 
-async fn generate_transcripts<C>(groq: &C, artifacts: &[ChunkArtifact]) -> HashMap<String, Value>
+/// Model calls that failed after the HTTP policy's retries.
+#[derive(Default)]
+struct Failures {
+    count: usize,
+    first: Option<String>,
+}
+
+impl Failures {
+    fn record(&mut self, item: &str, error: String) {
+        eprintln!("[sidecar:index:video] {} failed: {}", item, error);
+        self.count += 1;
+        self.first.get_or_insert(error);
+    }
+
+    fn into_result(self, what: &str, total: usize) -> Result<(), String> {
+        match self.first {
+            Some(first) => Err(format!(
+                "{} of {} {} failed: {}",
+                self.count, total, what, first
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Stops between request batches once `control` is cancelled; the caller's
+/// next checkpoint turns the partial map into a cancellation.
+async fn generate_transcripts<C>(
+    groq: &C,
+    artifacts: &[ChunkArtifact],
+    control: &JobControl,
+) -> Result<HashMap<String, Value>, String>
 where
     C: TranscriptionClient + Clone + 'static,
 {
@@ -397,7 +510,11 @@ where
     }
 
     let mut map = HashMap::new();
+    let mut failures = Failures::default();
     for batch in audio_items.chunks(4) {
+        if control.checkpoint().await.is_err() {
+            break;
+        }
         let mut set = JoinSet::new();
         for (key, bytes) in batch {
             let client = groq.clone();
@@ -410,18 +527,24 @@ where
         }
 
         while let Some(joined) = set.join_next().await {
-            if let Ok((key, Ok(payload))) = joined {
-                map.insert(key, payload);
+            match joined {
+                Ok((key, Ok(payload))) => {
+                    map.insert(key, payload);
+                }
+                Ok((key, Err(error))) => failures.record(&key, error),
+                Err(error) => failures.record("task", error.to_string()),
             }
         }
     }
-    map
+    failures.into_result("transcripts", audio_items.len())?;
+    Ok(map)
 }
 
 async fn generate_frame_summaries<C>(
     groq: &C,
     artifacts: &[ChunkArtifact],
-) -> HashMap<String, Vec<Value>>
+    control: &JobControl,
+) -> Result<HashMap<String, Vec<Value>>, String>
 where
     C: TranscriptionClient + Clone + 'static,
 {
@@ -441,7 +564,11 @@ where
         }
     }
 
+    let mut failures = Failures::default();
     for batch in flat_items.chunks(4) {
+        if control.checkpoint().await.is_err() {
+            break;
+        }
         let mut set = JoinSet::new();
         for (chunk_stem, idx, bytes) in batch {
             let client = groq.clone();
@@ -451,18 +578,41 @@ where
 
             set.spawn(async move {
                 let result = client.summarize_image_bytes(&image_id, bytes_clone).await;
-                (chunk_stem_clone, result)
+                (chunk_stem_clone, image_id, result)
             });
         }
 
         while let Some(joined) = set.join_next().await {
-            if let Ok((chunk_stem, Ok(entry))) = joined {
-                grouped.entry(chunk_stem).or_default().push(entry);
+            match joined {
+                Ok((chunk_stem, _, Ok(entry))) => {
+                    grouped.entry(chunk_stem).or_default().push(entry);
+                }
+                Ok((_, image_id, Err(error))) => failures.record(&image_id, error),
+                Err(error) => failures.record("task", error.to_string()),
             }
         }
     }
 
-    grouped
+    failures.into_result("frame summaries", flat_items.len())?;
+    Ok(grouped)
+}
+
+/// Offset of the first and last spoken segment within a chunk, in seconds.
+fn transcript_bounds(transcript_payload: &Value) -> Option<(f64, f64)> {
+    let segments = transcript_payload.get("segments")?.as_array()?;
+    let times = |key: &'static str| {
+        segments
+            .iter()
+            .filter_map(move |segment| segment.get(key).and_then(Value::as_f64))
+            .filter(|t| t.is_finite() && *t >= 0.0)
+    };
+    let start = times("start").reduce(f64::min)?;
+    let end = times("end").reduce(f64::max)?;
+    (end >= start).then_some((start, end))
+}
+
+fn secs_to_ms(secs: f64) -> u64 {
+    (secs.max(0.0) * 1000.0).round() as u64
 }
 
 fn extract_transcript_text(transcript_payload: &Value) -> String {
@@ -496,10 +646,13 @@ async fn index_video_with_deps<D>(
     chunk_duration_secs: f64,
     deps: &D,
     store: &dyn VideoIndexStore,
+    control: &JobControl,
 ) -> Result<VideoIndexResult, String>
 where
     D: VideoIndexerDeps,
 {
+    control.checkpoint().await?;
+
     let existing = match store.get_video_by_hash(content_hash).await {
         Ok(existing) => existing,
         Err(error) => {
@@ -558,6 +711,7 @@ where
         .chunk_video_if_needed(video_path, &chunks_dir, chunk_duration_secs)
         .await?;
 
+    control.checkpoint().await?;
     let artifacts = deps
         .build_chunk_artifacts(chunk_paths, audio_dir.clone(), thumbnails_dir.clone())
         .await?;
@@ -568,9 +722,18 @@ where
             video_path, error
         );
     }
+    if let Err(error) = cache_chunk_thumbnails(content_hash, &normalized_out_dir, &artifacts) {
+        eprintln!(
+            "[sidecar:index:video] warning: failed to cache chunk thumbnails for {}: {}",
+            video_path, error
+        );
+    }
 
-    let transcripts = deps.generate_transcripts(&artifacts).await;
-    let frame_summaries = deps.generate_frame_summaries(&artifacts).await;
+    control.checkpoint().await?;
+    let transcripts = deps.generate_transcripts(&artifacts, control).await?;
+    control.checkpoint().await?;
+    let frame_summaries = deps.generate_frame_summaries(&artifacts, control).await?;
+    control.checkpoint().await?;
 
     let filename_text = Path::new(video_path)
         .file_stem()
@@ -581,8 +744,20 @@ where
 
     let mut transcript_idx = 0usize;
     let mut frame_idx = 0usize;
+    let mut chunk_start_secs = 0.0_f64;
+
+    for (chunk_index, artifact) in artifacts.iter().enumerate() {
+        let chunk_secs = artifact
+            .duration_secs
+            .filter(|d| d.is_finite() && *d > 0.0)
+            .unwrap_or(chunk_duration_secs);
+        let chunk_span = VideoSpan {
+            chunk_index,
+            start_ms: secs_to_ms(chunk_start_secs),
+            end_ms: secs_to_ms(chunk_start_secs + chunk_secs),
+        };
+        chunk_start_secs += chunk_secs;
 
-    for artifact in &artifacts {
         if let Some(audio_path) = &artifact.audio_path {
             let audio_stem = Path::new(audio_path)
                 .file_stem()
@@ -592,9 +767,19 @@ where
             if let Some(transcript_payload) = transcripts.get(audio_stem) {
                 let transcript_text = extract_transcript_text(transcript_payload);
                 if !transcript_text.is_empty() {
+                    // Narrow to the speech itself when Whisper reports segment times.
+                    let span = match transcript_bounds(transcript_payload) {
+                        Some((start, end)) => VideoSpan {
+                            start_ms: (chunk_span.start_ms + secs_to_ms(start))
+                                .min(chunk_span.end_ms),
+                            end_ms: (chunk_span.start_ms + secs_to_ms(end)).min(chunk_span.end_ms),
+                            ..chunk_span
+                        },
+                        None => chunk_span,
+                    };
                     embedding_units.push((
-                        "video_transcript",
-                        format!("video_transcript_{}", transcript_idx),
+                        TRANSCRIPT_UNIT_KIND,
+                        span.unit_key(TRANSCRIPT_UNIT_KIND),
                         transcript_text,
                     ));
                     transcript_idx += 1;
@@ -617,8 +802,8 @@ where
                 .join(" | ");
             if !embedding_text.is_empty() {
                 embedding_units.push((
-                    "video_frame_summary",
-                    format!("video_frame_summary_{}", frame_idx),
+                    FRAME_SUMMARY_UNIT_KIND,
+                    chunk_span.unit_key(FRAME_SUMMARY_UNIT_KIND),
                     embedding_text,
                 ));
                 frame_idx += 1;
@@ -646,23 +831,31 @@ where
             .await?;
     }
 
-    for (unit_kind, unit_key, content) in &embedding_units {
-        if *unit_kind == "file_path" {
-            if let Err(error) = store
-                .create_video_asset_embeddings(content_hash, unit_kind, unit_key, content)
-                .await
-            {
+    let units: Vec<EmbeddingUnit> = embedding_units
+        .iter()
+        .map(|(unit_kind, unit_key, content)| {
+            EmbeddingUnit::new(content_hash, unit_kind, unit_key, content)
+        })
+        .collect();
+    let mut first_error: Option<String> = None;
+    for chunk in units.chunks(embed_flush_units()) {
+        let outcomes = store.create_video_asset_embeddings_batch(chunk).await;
+        for (unit, outcome) in chunk.iter().zip(outcomes) {
+            let Err(error) = outcome else {
+                continue;
+            };
+            if unit.unit_kind == "file_path" {
                 eprintln!(
                     "[sidecar:index:video] warning: failed to create path embedding for {}: {}",
                     video_path, error
                 );
+            } else if first_error.is_none() {
+                first_error = Some(error);
             }
-            continue;
         }
-
-        store
-            .create_video_asset_embeddings(content_hash, unit_kind, unit_key, content)
-            .await?;
+    }
+    if let Some(error) = first_error {
+        return Err(error);
     }
 
     store
@@ -690,6 +883,7 @@ pub async fn index_video_with_sidecar<C>(
     chunk_duration_secs: f64,
     groq: &C,
     store: &dyn VideoIndexStore,
+    control: &JobControl,
 ) -> Result<VideoIndexResult, String>
 where
     C: TranscriptionClient + Clone + 'static,
@@ -702,6 +896,7 @@ where
         chunk_duration_secs,
         &deps,
         store,
+        control,
     )
     .await
 }
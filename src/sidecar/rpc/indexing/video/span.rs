@@ -0,0 +1,45 @@
+pub const TRANSCRIPT_UNIT_KIND: &str = "video_transcript";
+pub const FRAME_SUMMARY_UNIT_KIND: &str = "video_frame_summary";
+
+/// Stretch of a video one embedding unit describes. Times are absolute
+/// milliseconds from the start of the video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoSpan {
+    pub chunk_index: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl VideoSpan {
+    /// Encodes the span as `{unit_kind}_{chunk}:t{start_ms}-{end_ms}` so search
+    /// results can seek into the video without another store lookup.
+    pub fn unit_key(&self, unit_kind: &str) -> String {
+        format!(
+            "{}_{}:t{}-{}",
+            unit_kind, self.chunk_index, self.start_ms, self.end_ms
+        )
+    }
+
+    /// Parses keys written by `unit_key`. The older `{unit_kind}_{n}` keys
+    /// give no span: `n` only advanced for chunks that produced a unit, so it
+    /// names neither the chunk nor its time once any chunk came up empty.
+    pub fn parse_unit_key(unit_key: &str) -> Option<Self> {
+        let rest = [TRANSCRIPT_UNIT_KIND, FRAME_SUMMARY_UNIT_KIND]
+            .iter()
+            .find_map(|kind| unit_key.strip_prefix(kind)?.strip_prefix('_'))?;
+
+        let (chunk, times) = rest.split_once(':')?;
+        let chunk_index: usize = chunk.parse().ok()?;
+        let (start, end) = times.strip_prefix('t')?.split_once('-')?;
+        let start_ms: u64 = start.parse().ok()?;
+        let end_ms: u64 = end.parse().ok()?;
+        if end_ms < start_ms {
+            return None;
+        }
+        Some(Self {
+            chunk_index,
+            start_ms,
+            end_ms,
+        })
+    }
+}
@@ -3,3 +3,4 @@ pub mod health;
 pub mod index;
 pub mod indexing;
 pub mod search;
+pub mod watch;
@@ -0,0 +1,264 @@
+//! `search.byImage`: finds indexed images and video frames that look like a
+//! given picture. The picture is described by the same vision prompt image
+//! indexing uses, and that description is matched against stored captions
+//! and frame summaries only.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::ImageFormat;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use super::filters::{SearchFilterParams, SearchFilters};
+use super::{
+    check_min_score, cursor, embed_query, finish_page, helix_client, nearest_assets,
+    rank_candidates, snippet, NearestScope, SearchMode, SearchPage, UnitCache,
+};
+use crate::sidecar::protocol::{
+    err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
+};
+use crate::sidecar::rpc::indexing::adapters::groq::GroqClient;
+use crate::sidecar::rpc::indexing::embedding::build_embedding_text;
+use crate::sidecar::rpc::indexing::image::{mime_hint_from_path, IMAGE_CAPTION_UNIT_KIND};
+use crate::sidecar::rpc::indexing::video::span::FRAME_SUMMARY_UNIT_KIND;
+
+/// Larger uploads are refused before they reach the vision model.
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+const SEARCHED_UNIT_KINDS: &[&str] = &[IMAGE_CAPTION_UNIT_KIND, FRAME_SUMMARY_UNIT_KIND];
+
+#[derive(Debug, Deserialize)]
+struct SearchByImageParams {
+    /// Image file on disk...
+    #[serde(default)]
+    path: Option<String>,
+    /// ...or its bytes, base64 or as a `data:` URI. Exactly one of the two.
+    #[serde(default)]
+    base64: Option<String>,
+    #[serde(default)]
+    min_score: Option<f64>,
+    #[serde(default)]
+    filters: SearchFilterParams,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+enum ImageInputError {
+    Invalid(String),
+    NotFound(String),
+}
+
+static GROQ: OnceLock<GroqClient> = OnceLock::new();
+
+fn groq_client() -> Result<&'static GroqClient, String> {
+    if let Some(client) = GROQ.get() {
+        return Ok(client);
+    }
+    let client = GroqClient::from_env()?;
+    Ok(GROQ.get_or_init(|| client))
+}
+
+/// Mime subtype the vision prompt expects, from the bytes themselves.
+fn mime_hint_from_bytes(bytes: &[u8]) -> Option<&'static str> {
+    match image::guess_format(bytes).ok()? {
+        ImageFormat::Jpeg => Some("jpeg"),
+        ImageFormat::Png => Some("png"),
+        ImageFormat::WebP => Some("webp"),
+        ImageFormat::Gif => Some("gif"),
+        ImageFormat::Bmp => Some("bmp"),
+        ImageFormat::Tiff => Some("tiff"),
+        _ => None,
+    }
+}
+
+/// Reads the image and its mime hint from whichever input was given.
+fn load_image(
+    path: Option<&str>,
+    encoded: Option<&str>,
+) -> Result<(Vec<u8>, &'static str), ImageInputError> {
+    let bytes = match (path, encoded) {
+        (Some(path), None) => {
+            let path = Path::new(path);
+            if !path.is_file() {
+                return Err(ImageInputError::NotFound(format!(
+                    "{} does not exist",
+                    path.display()
+                )));
+            }
+            let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+            if size > MAX_IMAGE_BYTES as u64 {
+                return Err(ImageInputError::Invalid(format!(
+                    "image is larger than {} bytes",
+                    MAX_IMAGE_BYTES
+                )));
+            }
+            let bytes = fs::read(path).map_err(|e| {
+                ImageInputError::Invalid(format!("failed to read {}: {}", path.display(), e))
+            })?;
+            let hint = mime_hint_from_bytes(&bytes).unwrap_or_else(|| mime_hint_from_path(path));
+            return Ok((bytes, hint));
+        }
+        (None, Some(encoded)) => {
+            // Accept `data:image/png;base64,...` as well as bare base64.
+            let payload = encoded
+                .split_once(";base64,")
+                .map_or(encoded, |(_, payload)| payload);
+            let payload: String = payload.split_whitespace().collect();
+            if payload.len() / 4 * 3 > MAX_IMAGE_BYTES {
+                return Err(ImageInputError::Invalid(format!(
+                    "image is larger than {} bytes",
+                    MAX_IMAGE_BYTES
+                )));
+            }
+            STANDARD
+                .decode(payload)
+                .map_err(|e| ImageInputError::Invalid(format!("base64 is invalid: {}", e)))?
+        }
+        _ => {
+            return Err(ImageInputError::Invalid(
+                "pass exactly one of path or base64".to_string(),
+            ));
+        }
+    };
+    let hint = mime_hint_from_bytes(&bytes).ok_or_else(|| {
+        ImageInputError::Invalid("bytes are not a supported image format".to_string())
+    })?;
+    Ok((bytes, hint))
+}
+
+pub async fn handle_by_image(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let parsed: SearchByImageParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+    let invalid = |reason: String| {
+        err_response(
+            request.id.clone(),
+            -32602,
+            "Invalid params",
+            Some(json!({ "reason": reason })),
+        )
+    };
+
+    if let Err(reason) = check_min_score(parsed.min_score) {
+        return invalid(reason);
+    }
+    let filters = match SearchFilters::from_params(&parsed.filters) {
+        Ok(filters) => filters,
+        Err(reason) => return invalid(reason),
+    };
+    let path = parsed
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string);
+    let encoded = parsed
+        .base64
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(str::to_string);
+    // Reading and decoding up to `MAX_IMAGE_BYTES` stays off the async workers.
+    let loaded =
+        tokio::task::spawn_blocking(move || load_image(path.as_deref(), encoded.as_deref())).await;
+    let (bytes, mime_hint) = match loaded {
+        Ok(Ok(loaded)) => loaded,
+        Ok(Err(ImageInputError::Invalid(reason))) => return invalid(reason),
+        Ok(Err(ImageInputError::NotFound(reason))) => {
+            return err_response(
+                request.id.clone(),
+                -32004,
+                "Image not found",
+                Some(json!({ "reason": reason })),
+            );
+        }
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Image search failed",
+                Some(json!({ "reason": format!("image load task failed: {}", error) })),
+            );
+        }
+    };
+
+    let fingerprint = cursor::fingerprint(format!(
+        "by_image|{}|{:?}|{:?}",
+        cursor::fingerprint(&bytes),
+        parsed.min_score,
+        filters
+    ));
+    let page = match SearchPage::from_params(parsed.limit, parsed.cursor.as_deref(), fingerprint) {
+        Ok(page) => page,
+        Err(reason) => return invalid(reason),
+    };
+
+    let started = Instant::now();
+    match search_by_image(bytes, mime_hint, parsed.min_score, &filters, &page).await {
+        Ok(result) => {
+            eprintln!(
+                "[sidecar:search] image search completed in {}ms with {} results",
+                started.elapsed().as_millis(),
+                result["results"].as_array().map_or(0, Vec::len)
+            );
+            ok_response(request.id.clone(), result)
+        }
+        Err(message) => {
+            eprintln!(
+                "[sidecar:search] image search failed in {}ms: {}",
+                started.elapsed().as_millis(),
+                message
+            );
+            err_response(
+                request.id.clone(),
+                -32603,
+                "Image search failed",
+                Some(json!({ "reason": message })),
+            )
+        }
+    }
+}
+
+async fn search_by_image(
+    bytes: Vec<u8>,
+    mime_hint: &str,
+    min_score: Option<f64>,
+    filters: &SearchFilters,
+    page: &SearchPage,
+) -> Result<Value, String> {
+    let summary = groq_client()?
+        .summarize_index_image_bytes("query", mime_hint, bytes)
+        .await?;
+    let caption = build_embedding_text(&summary);
+    if caption.trim().is_empty() {
+        return Err("the vision model returned no description".to_string());
+    }
+
+    let client = helix_client()?;
+    let vector = embed_query(&caption).await?;
+    let scope = NearestScope {
+        unit_kinds: SEARCHED_UNIT_KINDS,
+        ..NearestScope::default()
+    };
+    let depth = page.depth();
+    let nearest = nearest_assets(&client, &vector, filters, &scope, depth).await?;
+
+    let candidates = rank_candidates(SearchMode::Semantic, nearest.hits, Vec::new());
+    let terms = snippet::query_terms(&caption);
+    let mut units = UnitCache::default();
+    let (results, next_cursor) = finish_page(candidates, min_score, page, &terms, &mut units).await;
+
+    Ok(json!({
+        "query": caption,
+        "mode": SearchMode::Semantic,
+        "results": results,
+        "next_cursor": next_cursor,
+        "truncated": nearest.truncated,
+    }))
+}
@@ -0,0 +1,45 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// What a `search.query` cursor carries. Clients treat the encoded form as
+/// opaque; the fingerprint ties it to the query, mode, filters and threshold
+/// that produced it, so it cannot be replayed against a different search.
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorState {
+    offset: usize,
+    fingerprint: String,
+}
+
+/// Short digest of everything that shapes a result list except the page size.
+pub fn fingerprint(search: impl AsRef<[u8]>) -> String {
+    let digest = Sha256::digest(search.as_ref());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn encode(offset: usize, fingerprint: &str) -> String {
+    let state = CursorState {
+        offset,
+        fingerprint: fingerprint.to_string(),
+    };
+    let json = serde_json::to_vec(&state).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+/// Offset the cursor points at, if it was issued for this search and lies
+/// within `max_offset`.
+pub fn decode(cursor: &str, fingerprint: &str, max_offset: usize) -> Result<usize, String> {
+    let state = URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<CursorState>(&bytes).ok())
+        .ok_or_else(|| "cursor is malformed".to_string())?;
+    if state.fingerprint != fingerprint {
+        return Err("cursor was issued for a different search".to_string());
+    }
+    if state.offset > max_offset {
+        return Err(format!("cursor offset is beyond {} results", max_offset));
+    }
+    Ok(state.offset)
+}
@@ -0,0 +1,205 @@
+use chrono::DateTime;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::sidecar::rpc::indexing::collect::{extension_of, normalize_extension};
+use crate::sidecar::rpc::indexing::reconcile::{is_under_root, normalize_root};
+
+const KINDS: &[&str] = &["file", "image", "video"];
+
+/// Unix seconds or an RFC 3339 timestamp.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TimeBound {
+    Unix(i64),
+    Text(String),
+}
+
+impl TimeBound {
+    fn to_unix(&self, field: &str) -> Result<i64, String> {
+        match self {
+            TimeBound::Unix(seconds) => Ok(*seconds),
+            TimeBound::Text(text) => DateTime::parse_from_rfc3339(text.trim())
+                .map(|at| at.timestamp())
+                .map_err(|e| format!("{} must be unix seconds or RFC 3339: {}", field, e)),
+        }
+    }
+}
+
+/// `filters` param of `search.query`. Every field is optional; set fields
+/// must all match. Date bounds are inclusive.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchFilterParams {
+    /// Any of `file`, `image`, `video`.
+    pub kind: Vec<String>,
+    /// Directory (or single file) results must live under.
+    #[serde(alias = "root")]
+    pub path_prefix: Option<String>,
+    /// With or without the leading dot, case-insensitive.
+    pub extensions: Vec<String>,
+    pub indexed_after: Option<TimeBound>,
+    pub indexed_before: Option<TimeBound>,
+    pub modified_after: Option<TimeBound>,
+    pub modified_before: Option<TimeBound>,
+}
+
+/// What a filter can see of one asset.
+pub struct AssetFacts<'a> {
+    pub kind: &'a str,
+    pub path: &'a str,
+    pub modified_at: Option<i64>,
+    pub indexed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    kinds: Vec<String>,
+    root: Option<String>,
+    extensions: Vec<String>,
+    indexed: (Option<i64>, Option<i64>),
+    modified: (Option<i64>, Option<i64>),
+}
+
+impl SearchFilterParams {
+    /// Adds the `kind:`, `ext:` and `path:` operators of `q`. Where `filters`
+    /// already constrains a field, the operators may only narrow it.
+    pub fn narrow(
+        &mut self,
+        kinds: &[String],
+        extensions: &[String],
+        path: Option<&str>,
+    ) -> Result<(), String> {
+        if !kinds.is_empty() {
+            if self.kind.is_empty() {
+                self.kind = kinds.to_vec();
+            } else {
+                self.kind
+                    .retain(|kind| kinds.contains(&kind.trim().to_lowercase()));
+                if self.kind.is_empty() {
+                    return Err("kind: operator excludes every kind in filters.kind".to_string());
+                }
+            }
+        }
+        if !extensions.is_empty() {
+            if self.extensions.is_empty() {
+                self.extensions = extensions.to_vec();
+            } else {
+                let wanted: Vec<String> = extensions
+                    .iter()
+                    .map(|ext| normalize_extension(ext))
+                    .collect();
+                self.extensions
+                    .retain(|ext| wanted.contains(&normalize_extension(ext)));
+                if self.extensions.is_empty() {
+                    return Err(
+                        "ext: operator excludes every extension in filters.extensions".to_string(),
+                    );
+                }
+            }
+        }
+        if let Some(path) = path {
+            if self.path_prefix.is_some() {
+                return Err("path: operator conflicts with filters.path_prefix".to_string());
+            }
+            self.path_prefix = Some(path.to_string());
+        }
+        Ok(())
+    }
+}
+
+fn bound(value: &Option<TimeBound>, field: &str) -> Result<Option<i64>, String> {
+    value.as_ref().map(|b| b.to_unix(field)).transpose()
+}
+
+/// Assets without a timestamp (stored before it was recorded) never satisfy
+/// a bound on it.
+fn in_range(value: Option<i64>, (after, before): (Option<i64>, Option<i64>)) -> bool {
+    if after.is_none() && before.is_none() {
+        return true;
+    }
+    value.is_some_and(|at| after.is_none_or(|a| at >= a) && before.is_none_or(|b| at <= b))
+}
+
+impl SearchFilters {
+    pub fn from_params(params: &SearchFilterParams) -> Result<Self, String> {
+        let mut kinds = Vec::new();
+        for kind in &params.kind {
+            let kind = kind.trim().to_lowercase();
+            if !KINDS.contains(&kind.as_str()) {
+                return Err(format!(
+                    "unknown kind {:?}; expected one of {}",
+                    kind,
+                    KINDS.join(", ")
+                ));
+            }
+            kinds.push(kind);
+        }
+
+        let indexed = (
+            bound(&params.indexed_after, "indexed_after")?,
+            bound(&params.indexed_before, "indexed_before")?,
+        );
+        let modified = (
+            bound(&params.modified_after, "modified_after")?,
+            bound(&params.modified_before, "modified_before")?,
+        );
+        for (name, (after, before)) in [("indexed", indexed), ("modified", modified)] {
+            if let (Some(after), Some(before)) = (after, before) {
+                if after > before {
+                    return Err(format!("{}_after is later than {}_before", name, name));
+                }
+            }
+        }
+
+        Ok(Self {
+            kinds,
+            root: params
+                .path_prefix
+                .as_deref()
+                .map(str::trim)
+                .filter(|prefix| !prefix.is_empty())
+                .map(normalize_root),
+            extensions: params
+                .extensions
+                .iter()
+                .map(|ext| normalize_extension(ext))
+                .filter(|ext| !ext.is_empty())
+                .collect(),
+            indexed,
+            modified,
+        })
+    }
+
+    /// Asset kinds results must have; empty admits all.
+    pub fn kinds(&self) -> &[String] {
+        &self.kinds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+            && self.root.is_none()
+            && self.extensions.is_empty()
+            && self.indexed == (None, None)
+            && self.modified == (None, None)
+    }
+
+    pub fn matches(&self, asset: &AssetFacts) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| kind == asset.kind) {
+            return false;
+        }
+        if let Some(root) = &self.root {
+            if !is_under_root(root, &asset.path.replace('\\', "/")) {
+                return false;
+            }
+        }
+        if !self.extensions.is_empty()
+            && !self
+                .extensions
+                .contains(&extension_of(Path::new(asset.path)))
+        {
+            return false;
+        }
+        in_range(asset.indexed_at, self.indexed) && in_range(asset.modified_at, self.modified)
+    }
+}
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+/// Rank offset from the original RRF paper; damps the gap between the top few
+/// ranks so one list cannot dominate the fused order on its own.
+pub const RRF_K: f64 = 60.0;
+
+/// Reciprocal rank fusion: every ranking contributes `1 / (RRF_K + rank)` for
+/// each key it contains, with ranks starting at 1.
+pub fn reciprocal_rank_fusion(rankings: &[Vec<String>]) -> HashMap<String, f64> {
+    let mut fused: HashMap<String, f64> = HashMap::new();
+    for ranking in rankings {
+        for (idx, key) in ranking.iter().enumerate() {
+            *fused.entry(key.clone()).or_insert(0.0) += 1.0 / (RRF_K + idx as f64 + 1.0);
+        }
+    }
+    fused
+}
+
+/// Fused score of a key ranked first in every one of `lists` rankings; used to
+/// normalize fused scores into `[0, 1]`.
+pub fn max_fused_score(lists: usize) -> f64 {
+    lists as f64 / (RRF_K + 1.0)
+}
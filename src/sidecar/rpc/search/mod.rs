@@ -0,0 +1,1208 @@
+use helix_rs::{HelixDB, HelixDBClient};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::sidecar::protocol::{
+    err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
+};
+use crate::sidecar::rpc::indexing::adapters::embeddings::{
+    embedding_client_from_env, EmbeddingClient,
+};
+use crate::sidecar::rpc::indexing::lexical::{self, LexicalHit};
+use crate::sidecar::rpc::indexing::text::chunk::{ChunkSpan, CHUNK_UNIT_KIND};
+
+mod by_image;
+mod cursor;
+mod filters;
+mod fusion;
+mod query;
+mod query_cache;
+mod rerank;
+mod similar;
+mod snippet;
+mod video;
+
+use filters::{AssetFacts, SearchFilterParams, SearchFilters};
+use fusion::{max_fused_score, reciprocal_rank_fusion};
+use query::ParsedQuery;
+
+pub use by_image::handle_by_image;
+pub use similar::handle_similar;
+
+/// Minimum ranking depth per retriever. Pages within the first this-many
+/// results are fused from identically deep lists, so they stay consistent.
+const SEARCH_CANDIDATES: usize = 50;
+/// Starting embeddings-per-asset guess when sizing the first `SearchV`.
+const EMBEDDINGS_PER_ASSET: usize = 4;
+const FILTERED_FIRST_K: usize = 500;
+const DEFAULT_MAX_CANDIDATES: usize = 2_000;
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+/// Unit lookups one query may spend checking phrases and exclusions.
+const MAX_UNIT_FETCHES: usize = 400;
+/// Unit lookups in flight at once.
+const UNIT_FETCH_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SearchMode {
+    /// Vector search over `AssetEmbedding` only.
+    Semantic,
+    /// BM25 over unit content and file paths only; needs no embedding API.
+    Lexical,
+    /// Both, merged with reciprocal rank fusion.
+    #[default]
+    Hybrid,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQueryParams {
+    q: String,
+    #[serde(default)]
+    mode: SearchMode,
+    /// Drop results whose `score` is below this, in `[0, 1]`. The score is
+    /// cosine similarity in semantic mode, BM25 relative to the best hit in
+    /// lexical mode and the normalized fused rank in hybrid mode; reranked
+    /// candidates carry the reranker's relevance instead, which is absolute
+    /// for both rerankers (the lexical one saturates BM25 as `s / (s + 1)`).
+    #[serde(default)]
+    min_score: Option<f64>,
+    #[serde(default)]
+    filters: SearchFilterParams,
+    /// Unique assets per page; defaults to 20, capped at 200.
+    #[serde(default)]
+    limit: Option<usize>,
+    /// `next_cursor` from the previous page.
+    #[serde(default)]
+    cursor: Option<String>,
+    /// Rescore the leading candidates against their matched text. `score`
+    /// then holds the reranker's relevance for those candidates.
+    #[serde(default)]
+    rerank: bool,
+}
+
+fn value_as_string(value: Option<&Value>) -> Option<String> {
+    value.and_then(Value::as_str).map(ToString::to_string)
+}
+
+/// Cosine distance Helix attached to a `SearchV` hit. Newer Helix builds call
+/// it `distance`, older ones `score`; both hold the distance.
+fn embedding_distance(embedding: Option<&Value>) -> Option<f64> {
+    let embedding = embedding?.as_object()?;
+    embedding
+        .get("distance")
+        .or_else(|| embedding.get("score"))
+        .and_then(Value::as_f64)
+        .filter(|distance| distance.is_finite())
+}
+
+/// Maps cosine distance (0 = identical, 2 = opposite) to a `[0, 1]` score
+/// equal to the cosine similarity, floored at zero.
+fn score_from_distance(distance: f64) -> f64 {
+    (1.0 - distance).clamp(0.0, 1.0)
+}
+
+fn apply_chunk_match(result: &mut Value, embedding: Option<&Value>, terms: &HashSet<String>) {
+    let Some(embedding) = embedding.and_then(Value::as_object) else {
+        return;
+    };
+    if value_as_string(embedding.get("unit_kind")).as_deref() != Some(CHUNK_UNIT_KIND) {
+        return;
+    }
+    let Some(span) = value_as_string(embedding.get("unit_key"))
+        .as_deref()
+        .and_then(ChunkSpan::parse_unit_key)
+    else {
+        return;
+    };
+
+    result["chunk"] = json!({
+        "index": span.index,
+        "byte_start": span.byte_start,
+        "byte_end": span.byte_end,
+    });
+    result["lines"] = json!({ "start": span.line_start, "end": span.line_end });
+    if let Some(content) = value_as_string(embedding.get("content")) {
+        let snippet = snippet::build(&content, terms);
+        let line_start = span.line_start + content[..snippet.byte_start].matches('\n').count();
+        let line_end = line_start
+            + content[snippet.byte_start..snippet.byte_end]
+                .trim_end()
+                .matches('\n')
+                .count();
+        result["snippet_lines"] = json!({ "start": line_start, "end": line_end });
+        result["highlights"] = snippet.highlights_json();
+        result["snippet"] = Value::String(snippet.text);
+    }
+}
+
+/// Shows the OCR or summary part of an image's caption that matched best.
+fn apply_image_match(result: &mut Value, unit: Option<&Value>, terms: &HashSet<String>) {
+    let Some(content) = unit.and_then(|unit| value_as_string(unit.get("content"))) else {
+        return;
+    };
+    let (source, text) = snippet::best_image_part(&content, terms);
+    let snippet = snippet::build(text, terms);
+    result["snippet_source"] = Value::String(source.to_string());
+    result["highlights"] = snippet.highlights_json();
+    result["snippet"] = Value::String(snippet.text);
+}
+
+fn is_empty_vector_index_error(message: &str) -> bool {
+    let lowered = message.to_ascii_lowercase();
+    lowered.contains("no entry point found for hnsw index")
+        || lowered.contains("empty input provided to reranker")
+        || (lowered.contains("graph_error") && lowered.contains("vector error"))
+        || (lowered.contains("graph_error") && lowered.contains("reranker error"))
+}
+
+fn is_transient_embedding_error(message: &str) -> bool {
+    let lowered = message.to_ascii_lowercase();
+    lowered.contains("embeddingerror")
+        || lowered.contains("embedding error")
+        || lowered.contains("error while embedding text")
+        || lowered.contains("failed to send request to openai")
+        || lowered.contains("error sending request for url")
+}
+
+fn normalize_vector_query_result(
+    label: &str,
+    result: Result<Value, helix_rs::HelixError>,
+) -> Result<Value, String> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(error) => {
+            let message = error.to_string();
+            if is_empty_vector_index_error(&message) {
+                eprintln!(
+                    "[sidecar:search] {} search returned empty-index/reranker response; treating as no results: {}",
+                    label, message
+                );
+                Ok(Value::Array(Vec::new()))
+            } else if is_transient_embedding_error(&message) {
+                eprintln!(
+                    "[sidecar:search] {} search embedding backend failed; treating as no results: {}",
+                    label, message
+                );
+                Ok(Value::Array(Vec::new()))
+            } else {
+                Err(format!("{} search failed: {}", label, message))
+            }
+        }
+    }
+}
+
+fn normalize_timed_vector_query_result(
+    label: &str,
+    result: Result<Result<Value, helix_rs::HelixError>, tokio::time::error::Elapsed>,
+) -> Result<Value, String> {
+    match result {
+        Ok(inner) => normalize_vector_query_result(label, inner),
+        Err(_) => {
+            eprintln!(
+                "[sidecar:search] {} search timed out; treating as no results",
+                label
+            );
+            Ok(Value::Array(Vec::new()))
+        }
+    }
+}
+
+/// An asset hit from the vector search, closest first.
+struct SemanticHit {
+    asset: Value,
+    embedding: Option<Value>,
+    distance: Option<f64>,
+}
+
+/// One asset in the final ranking with whatever each retriever said about it.
+struct Candidate {
+    path: String,
+    kind: String,
+    content_hash: String,
+    semantic: Option<SemanticHit>,
+    lexical: Option<LexicalHit>,
+    score: Option<f64>,
+    /// Score before reranking, when the candidate was reranked.
+    first_stage_score: Option<f64>,
+}
+
+static HELIX: OnceLock<HelixDB> = OnceLock::new();
+static EMBEDDER: OnceLock<Arc<dyn EmbeddingClient>> = OnceLock::new();
+
+/// The search Helix client, built once so every request reuses its pooled
+/// connections. Clones share that pool.
+fn helix_client() -> Result<HelixDB, String> {
+    if let Some(client) = HELIX.get() {
+        return Ok(client.clone());
+    }
+    let endpoint = env::var("HELIX_ENDPOINT").unwrap_or_else(|_| "http://localhost".to_string());
+    let port = env::var("HELIX_PORT")
+        .unwrap_or_else(|_| "6969".to_string())
+        .parse::<u16>()
+        .map_err(|e| format!("invalid HELIX_PORT: {}", e))?;
+    let api_key = env::var("HELIX_API_KEY").ok();
+    let client = HelixDB::new(Some(endpoint.as_str()), Some(port), api_key.as_deref());
+    Ok(HELIX.get_or_init(|| client).clone())
+}
+
+/// The configured embedding provider, shared by every search request.
+fn embedding_client() -> Result<&'static Arc<dyn EmbeddingClient>, String> {
+    if let Some(client) = EMBEDDER.get() {
+        return Ok(client);
+    }
+    let client = embedding_client_from_env()?;
+    Ok(EMBEDDER.get_or_init(|| client))
+}
+
+/// Query vector for `query`, from the query cache when it has been embedded
+/// before with the current provider and retrieval model.
+async fn embed_query(query: &str) -> Result<Vec<f64>, String> {
+    let embedder = embedding_client()?;
+    let model = embedder.query_model_id();
+    let vector = match query_cache::get(&model, query) {
+        Some(vector) => vector,
+        None => {
+            let vector = embedder.embed_query(query).await?;
+            query_cache::insert(&model, query, vector.clone());
+            vector
+        }
+    };
+    Ok(vector.into_iter().map(f64::from).collect())
+}
+
+fn max_candidates() -> usize {
+    env::var("SIDECAR_SEARCH_MAX_CANDIDATES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_CANDIDATES)
+        .max(SEARCH_CANDIDATES)
+}
+
+fn asset_facts(asset: &Value) -> Option<AssetFacts<'_>> {
+    Some(AssetFacts {
+        kind: asset.get("kind").and_then(Value::as_str).unwrap_or("file"),
+        path: asset.get("path").and_then(Value::as_str)?,
+        modified_at: asset.get("modified_at").and_then(Value::as_i64),
+        indexed_at: asset
+            .get("indexed_at")
+            .and_then(Value::as_i64)
+            .filter(|at| *at > 0),
+    })
+}
+
+/// Time any one backend call of a search may take.
+fn backend_timeout() -> Duration {
+    let backend_timeout_ms = env::var("SIDECAR_SEARCH_BACKEND_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(12_000);
+    Duration::from_millis(backend_timeout_ms)
+}
+
+/// Restricts a `SearchV` inside Helix, before the k nearest are cut, so a
+/// narrow filter on a large index still reaches its own nearest embeddings.
+#[derive(Debug, Clone, Copy)]
+enum Prefilter<'a> {
+    AssetKind(&'a str),
+    UnitKind(&'a str),
+}
+
+/// Runs one `SearchV` with the given k and returns the parallel asset and
+/// embedding lists.
+async fn fetch_nearest(
+    client: &HelixDB,
+    vector: &[f64],
+    k: usize,
+    prefilter: Option<Prefilter<'_>>,
+) -> Result<(Vec<Value>, Vec<Value>), String> {
+    let (query, payload) = match prefilter {
+        None => (
+            "SearchAssetEmbeddingsTopK",
+            json!({ "vector": vector, "k": k }),
+        ),
+        Some(Prefilter::AssetKind(kind)) => (
+            "SearchAssetEmbeddingsOfKindTopK",
+            json!({ "vector": vector, "k": k, "asset_kind": kind }),
+        ),
+        Some(Prefilter::UnitKind(kind)) => (
+            "SearchAssetEmbeddingsOfUnitKindTopK",
+            json!({ "vector": vector, "k": k, "unit_kind": kind }),
+        ),
+    };
+    let raw =
+        tokio::time::timeout(backend_timeout(), client.query::<_, Value>(query, &payload)).await;
+
+    let response = normalize_timed_vector_query_result("asset", raw)?;
+    let list = |key: &str| {
+        response
+            .get(key)
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    };
+    Ok((list("assets"), list("embeddings")))
+}
+
+/// Collapses nearest embeddings into unique assets, closest first.
+fn collapse_to_assets(
+    assets_raw: &[Value],
+    embeddings_raw: &[Value],
+    filters: &SearchFilters,
+    scope: &NearestScope,
+) -> Vec<SemanticHit> {
+    // assets and embeddings are parallel: embeddings[i] drove the traversal to assets[i].
+    // Helix returns embeddings most-relevant-first, so lowest index = best rank.
+    // For assets with multiple chunks (videos, long text files), keep the closest chunk,
+    // falling back to the earliest-appearing one when Helix reports no distances.
+    let mut best_pos: HashMap<String, (usize, Option<f64>)> = HashMap::new();
+    for (idx, asset) in assets_raw.iter().enumerate() {
+        let Some(facts) = asset_facts(asset) else {
+            continue;
+        };
+        if !filters.matches(&facts) || !scope.admits(asset, embeddings_raw.get(idx)) {
+            continue;
+        }
+        let distance = embedding_distance(embeddings_raw.get(idx));
+        match best_pos.get(facts.path) {
+            Some((_, Some(best))) if distance.is_none_or(|d| d >= *best) => {}
+            Some((_, None)) if distance.is_none() => {}
+            _ => {
+                best_pos.insert(facts.path.to_string(), (idx, distance));
+            }
+        }
+    }
+
+    let mut ranked: Vec<(usize, Option<f64>)> = best_pos.into_values().collect();
+    ranked.sort_by(|(a_idx, a_dist), (b_idx, b_dist)| {
+        let a_dist = a_dist.unwrap_or(f64::INFINITY);
+        let b_dist = b_dist.unwrap_or(f64::INFINITY);
+        a_dist.total_cmp(&b_dist).then(a_idx.cmp(b_idx))
+    });
+
+    ranked
+        .into_iter()
+        .map(|(idx, distance)| SemanticHit {
+            asset: assets_raw[idx].clone(),
+            embedding: embeddings_raw.get(idx).cloned(),
+            distance,
+        })
+        .collect()
+}
+
+/// Narrows a vector search beyond the asset filters.
+#[derive(Default)]
+struct NearestScope<'a> {
+    /// Content hash of an asset to leave out, e.g. the seed of a similar search.
+    exclude_hash: Option<&'a str>,
+    /// Only embeddings of these unit kinds count; empty admits all.
+    unit_kinds: &'a [&'a str],
+}
+
+impl<'a> NearestScope<'a> {
+    /// The `SearchV` runs that together cover this scope and the kind filter.
+    /// `None` is an unrestricted run; it also finds embeddings stored before
+    /// they carried their asset's kind.
+    fn searches(&self, filters: &'a SearchFilters) -> Vec<Option<Prefilter<'a>>> {
+        if !self.unit_kinds.is_empty() {
+            return self
+                .unit_kinds
+                .iter()
+                .map(|kind| Some(Prefilter::UnitKind(kind)))
+                .collect();
+        }
+        filters
+            .kinds()
+            .iter()
+            .map(|kind| Some(Prefilter::AssetKind(kind)))
+            .chain([None])
+            .collect()
+    }
+
+    fn admits(&self, asset: &Value, embedding: Option<&Value>) -> bool {
+        if let Some(exclude) = self.exclude_hash {
+            if asset.get("content_hash").and_then(Value::as_str) == Some(exclude) {
+                return false;
+            }
+        }
+        self.unit_kinds.is_empty()
+            || embedding
+                .and_then(|embedding| embedding.get("unit_kind"))
+                .and_then(Value::as_str)
+                .is_some_and(|kind| self.unit_kinds.contains(&kind))
+    }
+}
+
+/// Whether an embedding was stored before embeddings carried their asset's kind.
+fn is_unkinded(embedding: &Value) -> bool {
+    embedding
+        .get("asset_kind")
+        .and_then(Value::as_str)
+        .is_none_or(str::is_empty)
+}
+
+/// Nearest unique assets to `vector` that pass `filters` and `scope`.
+#[derive(Default)]
+struct Nearest {
+    hits: Vec<SemanticHit>,
+    /// The search stopped at `SIDECAR_SEARCH_MAX_CANDIDATES` short of the
+    /// hits asked for, so matches further out may be missing.
+    truncated: bool,
+}
+
+/// Nearest `want` unique assets to `vector` that pass `filters` and `scope`.
+/// Kind and unit-kind restrictions run inside Helix; the remaining filters
+/// apply to what comes back. Many embeddings can belong to one asset, so k
+/// grows until enough assets turn up, the index runs out, or k reaches
+/// `SIDECAR_SEARCH_MAX_CANDIDATES`.
+async fn nearest_assets(
+    client: &HelixDB,
+    vector: &[f64],
+    filters: &SearchFilters,
+    scope: &NearestScope<'_>,
+    want: usize,
+) -> Result<Nearest, String> {
+    let max_k = max_candidates();
+    // Filtered searches start wide, since most of the nearest embeddings may
+    // belong to assets the filters reject.
+    let first_k = if filters.is_empty() && scope.unit_kinds.is_empty() {
+        want.saturating_mul(EMBEDDINGS_PER_ASSET)
+    } else {
+        FILTERED_FIRST_K
+    };
+    let searches = scope.searches(filters);
+    let mut k = first_k.clamp(SEARCH_CANDIDATES, max_k);
+    loop {
+        let mut assets_raw = Vec::new();
+        let mut embeddings_raw = Vec::new();
+        let mut exhausted = true;
+        for prefilter in &searches {
+            let (assets, embeddings) = fetch_nearest(client, vector, k, *prefilter).await?;
+            // Beside prefiltered runs, the unrestricted one only looks for
+            // embeddings stored without a kind; once it finds none, going
+            // deeper cannot add anything.
+            let legacy_only = prefilter.is_none() && searches.len() > 1;
+            exhausted &= assets.len() < k || (legacy_only && !embeddings.iter().any(is_unkinded));
+            assets_raw.extend(assets);
+            embeddings_raw.extend(embeddings);
+        }
+        let mut hits = collapse_to_assets(&assets_raw, &embeddings_raw, filters, scope);
+        if hits.len() >= want || exhausted || k >= max_k {
+            let truncated = hits.len() < want && !exhausted;
+            if truncated {
+                eprintln!(
+                    "[sidecar:search] stopped at {} candidates with {} of {} hits",
+                    k,
+                    hits.len(),
+                    want
+                );
+            }
+            hits.truncate(want);
+            return Ok(Nearest { hits, truncated });
+        }
+        k = k.saturating_mul(2).min(max_k);
+    }
+}
+
+async fn semantic_search(
+    query: &str,
+    filters: &SearchFilters,
+    want: usize,
+) -> Result<Nearest, String> {
+    let client = helix_client()?;
+    let vector = embed_query(query).await?;
+    nearest_assets(&client, &vector, filters, &NearestScope::default(), want).await
+}
+
+/// Rebuilds the embedding unit a lexical hit matched. File chunks carry the
+/// text they were indexed with; other units get theirs from Helix later.
+fn lexical_unit(hit: &LexicalHit) -> Value {
+    let mut unit = json!({ "unit_kind": hit.unit_kind, "unit_key": hit.unit_key });
+    if let Some(text) = &hit.text {
+        unit["content"] = Value::String(text.clone());
+    }
+    unit
+}
+
+fn rank_candidates(
+    mode: SearchMode,
+    semantic: Vec<SemanticHit>,
+    lexical: Vec<LexicalHit>,
+) -> Vec<Candidate> {
+    let semantic_order: Vec<String> = semantic
+        .iter()
+        .filter_map(|hit| value_as_string(hit.asset.get("path")))
+        .collect();
+    let lexical_order: Vec<String> = lexical.iter().map(|hit| hit.path.clone()).collect();
+    let top_lexical = lexical.first().map(|hit| hit.score).unwrap_or(0.0);
+
+    let mut by_path: HashMap<String, Candidate> = HashMap::new();
+    for hit in semantic {
+        let Some(path) = value_as_string(hit.asset.get("path")) else {
+            continue;
+        };
+        by_path.insert(
+            path.clone(),
+            Candidate {
+                path,
+                kind: value_as_string(hit.asset.get("kind")).unwrap_or_else(|| "file".to_string()),
+                content_hash: value_as_string(hit.asset.get("content_hash")).unwrap_or_default(),
+                score: hit.distance.map(score_from_distance),
+                first_stage_score: None,
+                semantic: Some(hit),
+                lexical: None,
+            },
+        );
+    }
+    for hit in lexical {
+        let candidate = by_path
+            .entry(hit.path.clone())
+            .or_insert_with(|| Candidate {
+                path: hit.path.clone(),
+                kind: hit.kind.clone(),
+                content_hash: hit.content_hash.clone(),
+                semantic: None,
+                lexical: None,
+                score: None,
+                first_stage_score: None,
+            });
+        if mode == SearchMode::Lexical && top_lexical > 0.0 {
+            candidate.score = Some(hit.score / top_lexical);
+        }
+        candidate.lexical = Some(hit);
+    }
+
+    let order = match mode {
+        SearchMode::Semantic => semantic_order,
+        SearchMode::Lexical => lexical_order,
+        SearchMode::Hybrid => {
+            let fused = reciprocal_rank_fusion(&[semantic_order, lexical_order]);
+            let max_fused = max_fused_score(2);
+            let mut order: Vec<(String, f64)> = fused.into_iter().collect();
+            order.sort_by(|(a_path, a), (b_path, b)| b.total_cmp(a).then(a_path.cmp(b_path)));
+            for (path, fused_score) in &order {
+                if let Some(candidate) = by_path.get_mut(path) {
+                    candidate.score = Some(fused_score / max_fused);
+                }
+            }
+            order.into_iter().map(|(path, _)| path).collect()
+        }
+    };
+
+    order
+        .into_iter()
+        .filter_map(|path| by_path.remove(&path))
+        .collect()
+}
+
+fn matched_unit(candidate: &Candidate) -> Option<Value> {
+    match (&candidate.semantic, &candidate.lexical) {
+        (Some(hit), _) if hit.embedding.is_some() => hit.embedding.clone(),
+        (_, Some(hit)) => Some(lexical_unit(hit)),
+        _ => None,
+    }
+}
+
+fn render_candidate(candidate: &Candidate, unit: Option<&Value>, terms: &HashSet<String>) -> Value {
+    let mut result = json!({
+        "label": candidate.kind,
+        "path": candidate.path,
+    });
+    if let Some(score) = candidate.score {
+        result["score"] = json!(score);
+    }
+    if let Some(distance) = candidate.semantic.as_ref().and_then(|hit| hit.distance) {
+        result["distance"] = json!(distance);
+    }
+    if let Some(hit) = &candidate.lexical {
+        result["lexical_score"] = json!(hit.score);
+    }
+    if let Some(score) = candidate.first_stage_score {
+        result["first_stage_score"] = json!(score);
+    }
+
+    match candidate.kind.as_str() {
+        "file" => apply_chunk_match(&mut result, unit, terms),
+        "image" => apply_image_match(&mut result, unit, terms),
+        "video" => video::apply_video_match(
+            &mut result,
+            &candidate.content_hash,
+            &candidate.path,
+            unit,
+            terms,
+        ),
+        _ => {}
+    }
+
+    result
+}
+
+/// Embedding units of the candidates of one query, fetched from Helix at
+/// most once per asset. Lookups are best effort: a failure only leaves text out.
+#[derive(Default)]
+struct UnitCache {
+    client: Option<HelixDB>,
+    by_hash: HashMap<String, Vec<Value>>,
+    /// Lookups made by `prefetch`, counted against `MAX_UNIT_FETCHES`.
+    fetches: usize,
+}
+
+impl UnitCache {
+    async fn units(&mut self, content_hash: &str) -> &[Value] {
+        if !self.by_hash.contains_key(content_hash) {
+            let units = match self.client() {
+                Ok(client) => fetch_units(client, content_hash.to_string()).await,
+                Err(error) => Err(error),
+            };
+            self.store(content_hash.to_string(), units);
+        }
+        &self.by_hash[content_hash]
+    }
+
+    fn is_loaded(&self, content_hash: &str) -> bool {
+        self.by_hash.contains_key(content_hash)
+    }
+
+    /// Loads the units of `hashes` not seen yet, in order and a few lookups at
+    /// a time, until the query's `MAX_UNIT_FETCHES` are spent.
+    async fn prefetch<'a>(&mut self, hashes: impl IntoIterator<Item = &'a str>) {
+        let mut seen = HashSet::new();
+        let missing: Vec<String> = hashes
+            .into_iter()
+            .filter(|hash| !self.by_hash.contains_key(*hash) && seen.insert(*hash))
+            .take(MAX_UNIT_FETCHES.saturating_sub(self.fetches))
+            .map(str::to_string)
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        self.fetches += missing.len();
+        let client = match self.client() {
+            Ok(client) => client,
+            Err(error) => {
+                eprintln!("[sidecar:search] failed to load units: {}", error);
+                for hash in missing {
+                    self.by_hash.insert(hash, Vec::new());
+                }
+                return;
+            }
+        };
+        let mut queue = missing.into_iter();
+        let mut in_flight = tokio::task::JoinSet::new();
+        loop {
+            while in_flight.len() < UNIT_FETCH_CONCURRENCY {
+                let Some(hash) = queue.next() else {
+                    break;
+                };
+                let client = client.clone();
+                in_flight.spawn(async move {
+                    let units = fetch_units(client, hash.clone()).await;
+                    (hash, units)
+                });
+            }
+            match in_flight.join_next().await {
+                Some(Ok((hash, units))) => self.store(hash, units),
+                Some(Err(error)) => {
+                    eprintln!("[sidecar:search] unit lookup task failed: {}", error)
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn client(&mut self) -> Result<HelixDB, String> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let client = helix_client()?;
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    fn store(&mut self, content_hash: String, units: Result<Vec<Value>, String>) {
+        let units = units.unwrap_or_else(|error| {
+            eprintln!(
+                "[sidecar:search] failed to load units for {}: {}",
+                content_hash, error
+            );
+            Vec::new()
+        });
+        self.by_hash.insert(content_hash, units);
+    }
+
+    /// Fills in `content` of a matched unit that came without it: lexical hits
+    /// on images and videos, and on chunks indexed before their text was kept.
+    async fn hydrate(&mut self, content_hash: &str, unit: &mut Value) {
+        if unit.get("content").is_some()
+            || value_as_string(unit.get("unit_kind")).as_deref() == Some(lexical::PATH_UNIT_KIND)
+        {
+            return;
+        }
+        let kind = unit.get("unit_kind").cloned();
+        let key = unit.get("unit_key").cloned();
+        let stored = self
+            .units(content_hash)
+            .await
+            .iter()
+            .find(|stored| {
+                stored.get("unit_kind") == kind.as_ref() && stored.get("unit_key") == key.as_ref()
+            })
+            .and_then(|stored| stored.get("content").cloned());
+        if let Some(content) = stored {
+            unit["content"] = content;
+        }
+    }
+}
+
+/// Every embedding unit stored for `content_hash`, within `backend_timeout`.
+async fn fetch_units(client: HelixDB, content_hash: String) -> Result<Vec<Value>, String> {
+    let timeout = backend_timeout();
+    let payload = json!({ "content_hash": content_hash });
+    let response: Value = tokio::time::timeout(
+        timeout,
+        client.query::<_, Value>("GetAssetEmbeddingsByHash", &payload),
+    )
+    .await
+    .map_err(|_| format!("timed out after {}ms", timeout.as_millis()))?
+    .map_err(|e| e.to_string())?;
+    Ok(response
+        .get("embeddings")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// The matched unit of a candidate, with its text filled in where the hit
+/// itself did not carry it.
+async fn resolve_unit(units: &mut UnitCache, candidate: &Candidate) -> Option<Value> {
+    let mut unit = matched_unit(candidate);
+    if let Some(unit) = unit.as_mut() {
+        units.hydrate(&candidate.content_hash, unit).await;
+    }
+    unit
+}
+
+/// Rescores the first `SIDECAR_SEARCH_RERANK_TOP_N` candidates against the
+/// text of their matched unit (or their path, when it has none) and reorders
+/// them by that score. Later candidates keep their first-stage order. Returns
+/// the name of the reranker used.
+async fn rerank_candidates(
+    query: &str,
+    candidates: &mut [Candidate],
+    units: &mut UnitCache,
+) -> &'static str {
+    let head = rerank::top_n().min(candidates.len());
+    let mut documents = Vec::with_capacity(head);
+    for candidate in &candidates[..head] {
+        let content = resolve_unit(units, candidate)
+            .await
+            .and_then(|unit| value_as_string(unit.get("content")))
+            .filter(|content| !content.trim().is_empty());
+        documents.push(content.unwrap_or_else(|| candidate.path.clone()));
+    }
+
+    let (scores, reranker) = rerank::rerank(query, &documents, backend_timeout()).await;
+    for (candidate, score) in candidates[..head].iter_mut().zip(scores) {
+        candidate.first_stage_score = candidate.score;
+        candidate.score = Some(score);
+    }
+    // Stable, so ties keep their first-stage order.
+    candidates[..head].sort_by(|a, b| b.score.unwrap_or(0.0).total_cmp(&a.score.unwrap_or(0.0)));
+    reranker
+}
+
+/// Drops candidates below `min_score` and renders the requested page.
+/// Returns the page and the cursor of the next one, if there is more.
+async fn finish_page(
+    candidates: Vec<Candidate>,
+    min_score: Option<f64>,
+    page: &SearchPage,
+    terms: &HashSet<String>,
+    units: &mut UnitCache,
+) -> (Vec<Value>, Option<String>) {
+    let mut ranked: Vec<Candidate> = Vec::new();
+    let mut below_min_score = 0usize;
+    for candidate in candidates {
+        // Without a score there is nothing to compare, so keep the hit.
+        if let (Some(score), Some(min_score)) = (candidate.score, min_score) {
+            if score < min_score {
+                below_min_score += 1;
+                continue;
+            }
+        }
+        ranked.push(candidate);
+    }
+
+    if below_min_score > 0 {
+        eprintln!(
+            "[sidecar:search] dropped {} result(s) below min_score",
+            below_min_score
+        );
+    }
+
+    let next_offset = page.offset.saturating_add(page.limit);
+    let next_cursor =
+        (ranked.len() > next_offset).then(|| cursor::encode(next_offset, &page.fingerprint));
+    let mut results: Vec<Value> = Vec::new();
+    for candidate in ranked.iter().skip(page.offset).take(page.limit) {
+        let unit = resolve_unit(units, candidate).await;
+        let mut result = render_candidate(candidate, unit.as_ref(), terms);
+        if candidate.kind == "video" {
+            video::attach_moment_excerpts(units, &candidate.content_hash, &mut result, terms).await;
+        }
+        results.push(result);
+    }
+    (results, next_cursor)
+}
+
+/// One page of a search: where it starts, how long it is, and the
+/// fingerprint its `next_cursor` must carry.
+struct SearchPage {
+    offset: usize,
+    limit: usize,
+    fingerprint: String,
+}
+
+impl SearchPage {
+    /// Hits to rank for this page: everything up to its end plus one, which
+    /// tells whether there is a next page, and never fewer than
+    /// `SEARCH_CANDIDATES`.
+    fn depth(&self) -> usize {
+        self.offset
+            .saturating_add(self.limit)
+            .saturating_add(1)
+            .max(SEARCH_CANDIDATES)
+    }
+
+    /// Validates `limit` and resolves `cursor` against the fingerprint of the
+    /// search it must have come from.
+    fn from_params(
+        limit: Option<usize>,
+        cursor: Option<&str>,
+        fingerprint: String,
+    ) -> Result<Self, String> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(format!("limit must be between 1 and {}", MAX_LIMIT));
+        }
+        let offset = match cursor {
+            None => 0,
+            Some(raw) => cursor::decode(raw, &fingerprint, max_candidates())?,
+        };
+        Ok(Self {
+            offset,
+            limit,
+            fingerprint,
+        })
+    }
+}
+
+fn check_min_score(min_score: Option<f64>) -> Result<(), String> {
+    match min_score {
+        Some(min_score) if !(0.0..=1.0).contains(&min_score) => {
+            Err("min_score must be between 0 and 1".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// First-stage candidates from retrievers searched `depth` deep.
+struct Retrieved {
+    candidates: Vec<Candidate>,
+    /// No retriever had more to give, so searching deeper finds nothing new.
+    exhausted: bool,
+    /// The vector search stopped at the candidate cap.
+    truncated: bool,
+}
+
+async fn retrieve(
+    query: &str,
+    mode: SearchMode,
+    filters: &SearchFilters,
+    depth: usize,
+) -> Result<Retrieved, String> {
+    let nearest = match mode {
+        SearchMode::Lexical => Nearest::default(),
+        SearchMode::Semantic | SearchMode::Hybrid => semantic_search(query, filters, depth).await?,
+    };
+    let lexical = match mode {
+        SearchMode::Semantic => Vec::new(),
+        SearchMode::Lexical | SearchMode::Hybrid => lexical_search(query, filters, depth).await?,
+    };
+    let exhausted = nearest.hits.len() < depth && !nearest.truncated && lexical.len() < depth;
+    Ok(Retrieved {
+        candidates: rank_candidates(mode, nearest.hits, lexical),
+        exhausted,
+        truncated: nearest.truncated,
+    })
+}
+
+/// Lexical hits passing `filters`, ranked on the blocking pool since scoring
+/// holds the index lock.
+async fn lexical_search(
+    query: &str,
+    filters: &SearchFilters,
+    depth: usize,
+) -> Result<Vec<LexicalHit>, String> {
+    let query = query.to_string();
+    let filters = filters.clone();
+    tokio::task::spawn_blocking(move || {
+        lexical::search(&query, depth, &|hit| {
+            filters.matches(&AssetFacts {
+                kind: &hit.kind,
+                path: &hit.path,
+                modified_at: hit.modified_at,
+                indexed_at: hit.indexed_at,
+            })
+        })
+    })
+    .await
+    .map_err(|e| format!("Lexical search failed: {}", e))
+}
+
+/// Whether `candidate` satisfies the phrases and exclusions of `parsed`,
+/// checked against its path, its matched unit and every unit stored for it.
+async fn passes_checks(parsed: &ParsedQuery, units: &mut UnitCache, candidate: &Candidate) -> bool {
+    let matched = resolve_unit(units, candidate)
+        .await
+        .and_then(|unit| value_as_string(unit.get("content")));
+    let stored = units.units(&candidate.content_hash).await;
+    let mut texts: Vec<&str> = vec![candidate.path.as_str()];
+    texts.extend(matched.as_deref());
+    texts.extend(
+        stored
+            .iter()
+            .filter_map(|unit| unit.get("content").and_then(Value::as_str)),
+    );
+    parsed.accepts(&texts)
+}
+
+async fn rust_helix_search_query(
+    raw_query: &str,
+    parsed: &ParsedQuery,
+    mode: SearchMode,
+    min_score: Option<f64>,
+    filters: &SearchFilters,
+    page: &SearchPage,
+    rerank: bool,
+) -> Result<Value, String> {
+    let query = parsed.text.as_str();
+    let mut units = UnitCache::default();
+    // Phrases and exclusions can only be checked on retrieved candidates, so
+    // retrieval goes deeper until the page and one more hit pass them.
+    let wanted = page.offset.saturating_add(page.limit).saturating_add(1);
+    let max_depth = max_candidates();
+    let mut depth = page.depth();
+    let mut verdicts: HashMap<String, bool> = HashMap::new();
+    let (mut first_stage, truncated) = loop {
+        let retrieved = retrieve(query, mode, filters, depth).await?;
+        if !parsed.has_checks() {
+            break (retrieved.candidates, retrieved.truncated);
+        }
+        units
+            .prefetch(
+                retrieved
+                    .candidates
+                    .iter()
+                    .filter(|candidate| !verdicts.contains_key(&candidate.path))
+                    .map(|candidate| candidate.content_hash.as_str()),
+            )
+            .await;
+        let mut kept = Vec::with_capacity(retrieved.candidates.len());
+        let mut out_of_fetches = false;
+        for candidate in retrieved.candidates {
+            let passes = match verdicts.get(&candidate.path) {
+                Some(passes) => *passes,
+                // Unchecked candidates past the fetch budget end the list, so
+                // it never skips a hit that might pass.
+                None if !units.is_loaded(&candidate.content_hash) => {
+                    out_of_fetches = true;
+                    break;
+                }
+                None => {
+                    let passes = passes_checks(parsed, &mut units, &candidate).await;
+                    verdicts.insert(candidate.path.clone(), passes);
+                    passes
+                }
+            };
+            if passes {
+                kept.push(candidate);
+            }
+        }
+        if out_of_fetches && kept.len() < wanted {
+            eprintln!(
+                "[sidecar:search] stopped checking after {} unit lookups with {} of {} hits",
+                MAX_UNIT_FETCHES,
+                kept.len(),
+                wanted
+            );
+        }
+        if kept.len() >= wanted || retrieved.exhausted || depth >= max_depth || out_of_fetches {
+            let truncated = retrieved.truncated
+                || (kept.len() < wanted && (!retrieved.exhausted || out_of_fetches));
+            break (kept, truncated);
+        }
+        depth = depth.saturating_mul(2).min(max_depth);
+    };
+    let reranker = if rerank {
+        Some(rerank_candidates(query, &mut first_stage, &mut units).await)
+    } else {
+        None
+    };
+
+    let terms = snippet::query_terms(query);
+    let (results, next_cursor) =
+        finish_page(first_stage, min_score, page, &terms, &mut units).await;
+
+    let mut response = json!({
+        "query": raw_query,
+        "parsed": parsed,
+        "mode": mode,
+        "results": results,
+        "next_cursor": next_cursor,
+        "truncated": truncated,
+    });
+    if let Some(reranker) = reranker {
+        response["reranker"] = Value::String(reranker.to_string());
+    }
+    Ok(response)
+}
+
+pub async fn handle_query(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let parsed: SearchQueryParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+
+    if let Err(reason) = check_min_score(parsed.min_score) {
+        return err_response(
+            request.id.clone(),
+            -32602,
+            "Invalid params",
+            Some(json!({ "reason": reason })),
+        );
+    }
+
+    let query = match query::parse(&parsed.q) {
+        Ok(query) => query,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32602,
+                "Invalid params",
+                Some(json!({
+                    "reason": error.message,
+                    "position": { "start": error.start, "end": error.end },
+                })),
+            );
+        }
+    };
+    if query.text.trim().is_empty() {
+        return err_response(
+            request.id.clone(),
+            -32602,
+            "Invalid params",
+            Some(json!({
+                "reason": "q has no search terms besides operators",
+                "position": { "start": 0, "end": parsed.q.chars().count() },
+            })),
+        );
+    }
+    let mut filter_params = parsed.filters;
+    if let Err(reason) =
+        filter_params.narrow(&query.kinds, &query.extensions, query.path.as_deref())
+    {
+        return err_response(
+            request.id.clone(),
+            -32602,
+            "Invalid params",
+            Some(json!({ "reason": reason })),
+        );
+    }
+
+    let filters = match SearchFilters::from_params(&filter_params) {
+        Ok(filters) => filters,
+        Err(reason) => {
+            return err_response(
+                request.id.clone(),
+                -32602,
+                "Invalid params",
+                Some(json!({ "reason": reason })),
+            );
+        }
+    };
+
+    let fingerprint = cursor::fingerprint(format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}",
+        parsed.q, parsed.mode, parsed.min_score, filters, parsed.rerank
+    ));
+    let page = match SearchPage::from_params(parsed.limit, parsed.cursor.as_deref(), fingerprint) {
+        Ok(page) => page,
+        Err(reason) => {
+            return err_response(
+                request.id.clone(),
+                -32602,
+                "Invalid params",
+                Some(json!({ "reason": reason })),
+            );
+        }
+    };
+
+    let started = Instant::now();
+
+    match rust_helix_search_query(
+        &parsed.q,
+        &query,
+        parsed.mode,
+        parsed.min_score,
+        &filters,
+        &page,
+        parsed.rerank,
+    )
+    .await
+    {
+        Ok(result) => {
+            let count = result
+                .get("results")
+                .and_then(Value::as_array)
+                .map(|items| items.len())
+                .unwrap_or(0);
+            eprintln!(
+                "[sidecar:search] {:?} search completed in {}ms with {} results",
+                parsed.mode,
+                started.elapsed().as_millis(),
+                count
+            );
+            ok_response(request.id.clone(), result)
+        }
+        Err(message) => {
+            eprintln!(
+                "[sidecar:search] failed in {}ms: {}",
+                started.elapsed().as_millis(),
+                message
+            );
+            err_response(
+                request.id.clone(),
+                -32603,
+                "Search query failed",
+                Some(json!({ "reason": message })),
+            )
+        }
+    }
+}
+
+/// Hit and miss counts of the query-vector cache since the sidecar started.
+pub fn handle_cache_stats(request: &JsonRpcRequest) -> JsonRpcResponse {
+    ok_response(request.id.clone(), query_cache::stats())
+}
+
+/// Saves the query-vector cache, if persisted, and pending lexical index
+/// changes before the sidecar exits.
+pub fn flush_caches() {
+    query_cache::flush();
+    lexical::flush();
+}
@@ -0,0 +1,285 @@
+//! Operators in the `q` of `search.query`.
+//!
+//! `kind:video path:~/talks "kubernetes" -draft slides` parses into the
+//! search text `kubernetes slides`, the exact phrase `kubernetes`, the
+//! excluded term `draft` and the kind and path filters. Words with any other
+//! `name:` prefix (`E0308:`, `http://...`) stay search text. Positions are
+//! character offsets into `q`, half-open.
+
+use serde::Serialize;
+use std::env;
+
+use crate::sidecar::rpc::indexing::lexical::tokenize;
+
+const KINDS: &[&str] = &["file", "image", "video"];
+
+/// One operator as typed, for the UI to render as a removable chip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryOperator {
+    /// `kind`, `ext`, `path`, `phrase` or `exclude`.
+    pub operator: &'static str,
+    pub value: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ParsedQuery {
+    /// What the retrievers search for: plain words and phrase contents.
+    pub text: String,
+    /// Must appear, whitespace- and case-insensitively, in the matched text.
+    pub phrases: Vec<String>,
+    /// No matched text may contain these.
+    pub excluded: Vec<String>,
+    pub kinds: Vec<String>,
+    pub extensions: Vec<String>,
+    pub path: Option<String>,
+    pub operators: Vec<QueryOperator>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryParseError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl QueryParseError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        Self {
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+}
+
+/// A whitespace-separated piece of `q`; `quoted` pieces kept their spaces.
+struct Piece {
+    text: String,
+    quoted: bool,
+    start: usize,
+    end: usize,
+}
+
+/// Reads the value starting at `chars[at]`: a quoted string or a run of
+/// non-space characters. Returns it with the index just past it.
+fn read_value(chars: &[char], at: usize) -> Result<(Piece, usize), QueryParseError> {
+    if chars.get(at) == Some(&'"') {
+        let Some(close) = chars[at + 1..].iter().position(|c| *c == '"') else {
+            return Err(QueryParseError::new("unterminated quote", at, chars.len()));
+        };
+        let end = at + 1 + close + 1;
+        let piece = Piece {
+            text: chars[at + 1..end - 1].iter().collect(),
+            quoted: true,
+            start: at,
+            end,
+        };
+        return Ok((piece, end));
+    }
+    let mut end = at;
+    while end < chars.len() && !chars[end].is_whitespace() {
+        end += 1;
+    }
+    let piece = Piece {
+        text: chars[at..end].iter().collect(),
+        quoted: false,
+        start: at,
+        end,
+    };
+    Ok((piece, end))
+}
+
+fn expand_home(path: &str) -> String {
+    let home = || env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok();
+    if path == "~" {
+        return home().unwrap_or_else(|| path.to_string());
+    }
+    match (path.strip_prefix("~/"), home()) {
+        (Some(rest), Some(home)) => format!("{}/{}", home.trim_end_matches(['/', '\\']), rest),
+        _ => path.to_string(),
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+impl ParsedQuery {
+    fn push_operator(&mut self, operator: &'static str, value: &str, start: usize, end: usize) {
+        self.operators.push(QueryOperator {
+            operator,
+            value: value.to_string(),
+            start,
+            end,
+        });
+    }
+
+    fn apply_filter(
+        &mut self,
+        key: &'static str,
+        value: Piece,
+        start: usize,
+    ) -> Result<(), QueryParseError> {
+        let end = value.end;
+        if value.text.trim().is_empty() {
+            return Err(QueryParseError::new(
+                format!("{}: needs a value", key),
+                start,
+                end,
+            ));
+        }
+        match key {
+            "kind" => {
+                for kind in value.text.split(',') {
+                    let kind = kind.trim().to_lowercase();
+                    if !KINDS.contains(&kind.as_str()) {
+                        return Err(QueryParseError::new(
+                            format!(
+                                "unknown kind {:?}; expected one of {}",
+                                kind,
+                                KINDS.join(", ")
+                            ),
+                            value.start,
+                            end,
+                        ));
+                    }
+                    if !self.kinds.contains(&kind) {
+                        self.kinds.push(kind);
+                    }
+                }
+            }
+            "ext" => {
+                for ext in value.text.split(',').map(str::trim) {
+                    if !ext.is_empty() {
+                        self.extensions.push(ext.to_string());
+                    }
+                }
+            }
+            _ => {
+                if self.path.is_some() {
+                    return Err(QueryParseError::new(
+                        "only one path: operator is allowed",
+                        start,
+                        end,
+                    ));
+                }
+                self.path = Some(expand_home(value.text.trim()));
+            }
+        }
+        self.push_operator(key, value.text.trim(), start, end);
+        Ok(())
+    }
+
+    /// Whether there are phrases or exclusions to check results against.
+    pub fn has_checks(&self) -> bool {
+        !self.phrases.is_empty() || !self.excluded.is_empty()
+    }
+
+    /// Whether an asset whose text is split across `texts` (its path and each
+    /// of its units) satisfies the phrases and exclusions: every phrase must
+    /// appear within one of the texts, and no excluded term in any of them.
+    pub fn accepts(&self, texts: &[&str]) -> bool {
+        if !self.has_checks() {
+            return true;
+        }
+        let normalized: Vec<String> = texts
+            .iter()
+            .map(|text| collapse_whitespace(text).to_lowercase())
+            .collect();
+        if !self.phrases.iter().all(|phrase| {
+            let phrase = phrase.to_lowercase();
+            normalized.iter().any(|text| text.contains(&phrase))
+        }) {
+            return false;
+        }
+        if self.excluded.is_empty() {
+            return true;
+        }
+        let tokens: Vec<Vec<String>> = texts.iter().map(|text| tokenize(text)).collect();
+        !self.excluded.iter().any(|excluded| {
+            // Single words match whole tokens, so `-test` spares `testing`.
+            match tokenize(excluded).as_slice() {
+                [token] => tokens.iter().any(|tokens| tokens.contains(token)),
+                _ => {
+                    let excluded = excluded.to_lowercase();
+                    normalized.iter().any(|text| text.contains(&excluded))
+                }
+            }
+        })
+    }
+}
+
+/// Splits `q` into search text, phrases, exclusions and filters.
+pub fn parse(q: &str) -> Result<ParsedQuery, QueryParseError> {
+    let chars: Vec<char> = q.chars().collect();
+    let mut parsed = ParsedQuery::default();
+    let mut text_parts: Vec<String> = Vec::new();
+
+    let mut at = 0;
+    while at < chars.len() {
+        if chars[at].is_whitespace() {
+            at += 1;
+            continue;
+        }
+        let start = at;
+        let negated = chars[at] == '-' && chars.get(at + 1).is_some_and(|c| !c.is_whitespace());
+        let body = if negated { at + 1 } else { at };
+
+        let (piece, next) = read_value(&chars, body)?;
+        at = next;
+
+        if piece.quoted {
+            let phrase = collapse_whitespace(&piece.text);
+            if phrase.is_empty() {
+                return Err(QueryParseError::new("empty phrase", start, piece.end));
+            }
+            if negated {
+                parsed.push_operator("exclude", &phrase, start, piece.end);
+                parsed.excluded.push(phrase);
+            } else {
+                parsed.push_operator("phrase", &phrase, start, piece.end);
+                text_parts.push(phrase.clone());
+                parsed.phrases.push(phrase);
+            }
+            continue;
+        }
+
+        let filter_key =
+            piece
+                .text
+                .split_once(':')
+                .and_then(|(key, _)| match key.to_lowercase().as_str() {
+                    "kind" => Some("kind"),
+                    "ext" => Some("ext"),
+                    "path" => Some("path"),
+                    _ => None,
+                });
+        if let Some(key) = filter_key {
+            if negated {
+                return Err(QueryParseError::new(
+                    format!("{}: cannot be negated", key),
+                    start,
+                    piece.end,
+                ));
+            }
+            // The value starts right after the colon and may be quoted.
+            let value_at = body + key.chars().count() + 1;
+            let (value, next) = read_value(&chars, value_at)?;
+            at = next;
+            parsed.apply_filter(key, value, start)?;
+            continue;
+        }
+
+        if negated {
+            parsed.push_operator("exclude", &piece.text, start, piece.end);
+            parsed.excluded.push(piece.text);
+        } else {
+            text_parts.push(piece.text);
+        }
+    }
+
+    parsed.text = text_parts.join(" ");
+    Ok(parsed)
+}
@@ -0,0 +1,301 @@
+//! Process-wide LRU cache of query embeddings.
+//!
+//! Search-as-you-type sends the same few queries over and over; each one
+//! would otherwise cost an embeddings round trip. Vectors are keyed by model
+//! and normalized query text, so changing `VOYAGE_RETRIEVAL_MODEL` never
+//! serves a vector from another embedding space. With
+//! `SIDECAR_QUERY_CACHE_PERSIST` set, the cache is saved under the data dir
+//! and survives restarts; saves work from a snapshot on the blocking pool.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::sidecar::data::{data_file, write_atomic};
+
+const CACHE_FILE: &str = "query_vectors.json";
+const PERSIST_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_CAPACITY: usize = 512;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedVector {
+    model: String,
+    query: String,
+    vector: Vec<f32>,
+    #[serde(skip)]
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct QueryCache {
+    capacity: usize,
+    persist: bool,
+    entries: HashMap<String, CachedVector>,
+    /// Monotonic use counter; the entry with the lowest `last_used` goes first.
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    /// Bumped by every insert since the cache was loaded.
+    generation: u64,
+    /// Generation of the last snapshot taken for saving.
+    snapshotted: u64,
+}
+
+/// On-disk form: entries oldest first, so reloading restores recency.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PersistedCache {
+    entries: Vec<CachedVector>,
+}
+
+static CACHE: OnceLock<Mutex<QueryCache>> = OnceLock::new();
+static LAST_PERSIST: Mutex<Option<Instant>> = Mutex::new(None);
+/// Generation on disk; held while writing so saves land in order.
+static SAVED: Mutex<u64> = Mutex::new(0);
+
+fn env_flag(name: &str) -> bool {
+    env::var(name)
+        .map(|raw| {
+            matches!(
+                raw.trim().to_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+fn cache() -> &'static Mutex<QueryCache> {
+    CACHE.get_or_init(|| {
+        // 0 disables caching.
+        let capacity = env::var("SIDECAR_QUERY_CACHE_SIZE")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_CAPACITY);
+        let persist = env_flag("SIDECAR_QUERY_CACHE_PERSIST");
+        let mut cache = QueryCache {
+            capacity,
+            persist,
+            ..QueryCache::default()
+        };
+        if persist && capacity > 0 {
+            cache.load();
+        }
+        Mutex::new(cache)
+    })
+}
+
+/// Cache key text: trimmed, lowercased and whitespace-collapsed, so queries
+/// that differ only in case or spacing share one vector.
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn cache_key(model: &str, normalized: &str) -> String {
+    format!("{}\u{0}{}", model, normalized)
+}
+
+impl QueryCache {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn load(&mut self) {
+        let path = data_file(CACHE_FILE);
+        let Ok(bytes) = fs::read(&path) else {
+            return;
+        };
+        match serde_json::from_slice::<PersistedCache>(&bytes) {
+            Ok(persisted) => {
+                for entry in persisted.entries {
+                    self.insert(entry.model.clone(), entry.query.clone(), entry.vector);
+                }
+                self.generation = 0;
+                eprintln!(
+                    "[sidecar:search] restored {} cached query vector(s) from {}",
+                    self.entries.len(),
+                    path.display()
+                );
+            }
+            Err(error) => {
+                eprintln!(
+                    "[sidecar:search] ignoring unreadable {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+    }
+
+    fn get(&mut self, model: &str, normalized: &str) -> Option<Vec<f32>> {
+        let now = self.tick();
+        match self.entries.get_mut(&cache_key(model, normalized)) {
+            Some(entry) => {
+                entry.last_used = now;
+                self.hits += 1;
+                Some(entry.vector.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, model: String, query: String, vector: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = cache_key(&model, &query);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+            }
+        }
+        let last_used = self.tick();
+        self.entries.insert(
+            key,
+            CachedVector {
+                model,
+                query,
+                vector,
+                last_used,
+            },
+        );
+        self.generation += 1;
+    }
+
+    /// Entries to save, when there are inserts not yet snapshotted and the
+    /// throttle allows a save now. `force` skips both checks, so a flush also
+    /// covers a background save that has not landed yet.
+    fn snapshot(&mut self, force: bool) -> Option<Snapshot> {
+        if !self.persist || self.generation == 0 {
+            return None;
+        }
+        if !force && self.generation == self.snapshotted {
+            return None;
+        }
+        {
+            let mut last = LAST_PERSIST.lock().unwrap_or_else(|e| e.into_inner());
+            if !force && last.is_some_and(|at| at.elapsed() < PERSIST_INTERVAL) {
+                return None;
+            }
+            *last = Some(Instant::now());
+        }
+        self.snapshotted = self.generation;
+
+        let mut entries: Vec<CachedVector> = self.entries.values().cloned().collect();
+        entries.sort_by_key(|entry| entry.last_used);
+        Some(Snapshot {
+            generation: self.generation,
+            persisted: PersistedCache { entries },
+        })
+    }
+}
+
+/// The cache at one generation, saved outside the cache lock.
+struct Snapshot {
+    generation: u64,
+    persisted: PersistedCache,
+}
+
+impl Snapshot {
+    /// Writes the snapshot unless a newer one is already on disk.
+    fn write(self) {
+        let mut saved = SAVED.lock().unwrap_or_else(|e| e.into_inner());
+        if *saved >= self.generation {
+            return;
+        }
+        let result = serde_json::to_vec(&self.persisted)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| write_atomic(&data_file(CACHE_FILE), &bytes));
+        match result {
+            Ok(()) => *saved = self.generation,
+            Err(error) => {
+                eprintln!("[sidecar:search] failed to persist query cache: {}", error)
+            }
+        }
+    }
+
+    /// Writes on the blocking pool, or right here outside a runtime.
+    fn write_in_background(self) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || self.write());
+            }
+            Err(_) => self.write(),
+        }
+    }
+}
+
+fn with_cache<T>(apply: impl FnOnce(&mut QueryCache) -> T) -> T {
+    let mut guard = cache().lock().unwrap_or_else(|e| e.into_inner());
+    apply(&mut guard)
+}
+
+/// Cached vector for `query` under `model`, counting a hit or a miss.
+pub fn get(model: &str, query: &str) -> Option<Vec<f32>> {
+    let normalized = normalize_query(query);
+    with_cache(|cache| {
+        if cache.capacity == 0 {
+            return None;
+        }
+        cache.get(model, &normalized)
+    })
+}
+
+pub fn insert(model: &str, query: &str, vector: Vec<f32>) {
+    let normalized = normalize_query(query);
+    let snapshot = with_cache(|cache| {
+        cache.insert(model.to_string(), normalized, vector);
+        cache.snapshot(false)
+    });
+    if let Some(snapshot) = snapshot {
+        snapshot.write_in_background();
+    }
+}
+
+/// Writes pending entries now instead of waiting for the next throttled save.
+/// Blocks on the write, so call it off the async workers.
+pub fn flush() {
+    let Some(cache) = CACHE.get() else {
+        return;
+    };
+    let snapshot = cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .snapshot(true);
+    if let Some(snapshot) = snapshot {
+        snapshot.write();
+    }
+}
+
+/// Counters for `search.cacheStats`, accumulated since the sidecar started.
+pub fn stats() -> Value {
+    with_cache(|cache| {
+        let lookups = cache.hits + cache.misses;
+        json!({
+            "entries": cache.entries.len(),
+            "capacity": cache.capacity,
+            "hits": cache.hits,
+            "misses": cache.misses,
+            "hit_rate": if lookups == 0 { 0.0 } else { cache.hits as f64 / lookups as f64 },
+            "evictions": cache.evictions,
+            "persisted": cache.persist,
+        })
+    })
+}
@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::sidecar::rpc::indexing::adapters::rerank::{Reranker, VoyageReranker};
+use crate::sidecar::rpc::indexing::lexical::tokenize;
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+const DEFAULT_TOP_N: usize = 50;
+/// BM25 score the lexical reranker maps to 0.5.
+const SCORE_HALF_POINT: f64 = 1.0;
+
+/// BM25 over the candidate texts themselves. Needs no network, so it backs
+/// up the remote reranker and serves when none is configured.
+pub struct LexicalReranker;
+
+#[async_trait]
+impl Reranker for LexicalReranker {
+    fn name(&self) -> &'static str {
+        "lexical"
+    }
+
+    async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f64>, String> {
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        let docs: Vec<HashMap<String, u32>> = documents
+            .iter()
+            .map(|document| {
+                let mut tf = HashMap::new();
+                for token in tokenize(document) {
+                    *tf.entry(token).or_insert(0) += 1;
+                }
+                tf
+            })
+            .collect();
+        let lens: Vec<f64> = docs
+            .iter()
+            .map(|tf| tf.values().sum::<u32>() as f64)
+            .collect();
+        let avg_len = (lens.iter().sum::<f64>() / docs.len().max(1) as f64).max(1.0);
+        let count = docs.len() as f64;
+
+        let mut scores = vec![0.0; docs.len()];
+        for term in &terms {
+            let df = docs.iter().filter(|tf| tf.contains_key(term)).count() as f64;
+            if df == 0.0 {
+                continue;
+            }
+            let idf = ((count - df + 0.5) / (df + 0.5) + 1.0).ln();
+            for (idx, tf) in docs.iter().enumerate() {
+                let Some(freq) = tf.get(term) else {
+                    continue;
+                };
+                let freq = *freq as f64;
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * lens[idx] / avg_len);
+                scores[idx] += idf * freq * (BM25_K1 + 1.0) / (freq + norm);
+            }
+        }
+        // Saturated into [0, 1) rather than divided by the best document, so
+        // a score means the same on every page and `min_score` compares like
+        // it does against the remote reranker's relevance.
+        scores
+            .iter_mut()
+            .for_each(|score| *score /= *score + SCORE_HALF_POINT);
+        Ok(scores)
+    }
+}
+
+static REMOTE: OnceLock<Option<VoyageReranker>> = OnceLock::new();
+
+/// The remote reranker picked by `SIDECAR_RERANKER` (`voyage` or `lexical`).
+/// Unset means Voyage when `VOYAGE_API_KEY` is available.
+fn remote_reranker() -> Option<&'static VoyageReranker> {
+    REMOTE
+        .get_or_init(|| {
+            let choice = env::var("SIDECAR_RERANKER")
+                .map(|raw| raw.trim().to_lowercase())
+                .unwrap_or_default();
+            if choice == "lexical" {
+                return None;
+            }
+            match VoyageReranker::from_env() {
+                Ok(reranker) => Some(reranker),
+                Err(error) => {
+                    if choice == "voyage" {
+                        eprintln!(
+                            "[sidecar:search] voyage reranker unavailable, using lexical: {}",
+                            error
+                        );
+                    }
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// How many leading candidates get rescored (`SIDECAR_SEARCH_RERANK_TOP_N`).
+pub fn top_n() -> usize {
+    env::var("SIDECAR_SEARCH_RERANK_TOP_N")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_TOP_N)
+}
+
+/// Time the remote reranker may take (`SIDECAR_SEARCH_RERANK_TIMEOUT_MS`),
+/// never more than the backend budget every other search call gets.
+fn budget(backend_timeout: Duration) -> Duration {
+    env::var("SIDECAR_SEARCH_RERANK_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .map_or(backend_timeout, |budget| budget.min(backend_timeout))
+}
+
+/// Scores `documents` with the remote reranker, falling back to the lexical
+/// one when it is not configured, fails or runs out of time. Returns the
+/// scores and the name of the reranker that produced them.
+pub async fn rerank(
+    query: &str,
+    documents: &[String],
+    backend_timeout: Duration,
+) -> (Vec<f64>, &'static str) {
+    if let Some(remote) = remote_reranker() {
+        match tokio::time::timeout(budget(backend_timeout), remote.rerank(query, documents)).await {
+            Ok(Ok(scores)) => return (scores, remote.name()),
+            Ok(Err(error)) => {
+                eprintln!(
+                    "[sidecar:search] {} rerank failed; using lexical: {}",
+                    remote.name(),
+                    error
+                );
+            }
+            Err(_) => {
+                eprintln!(
+                    "[sidecar:search] {} rerank timed out; using lexical",
+                    remote.name()
+                );
+            }
+        }
+    }
+    let fallback = LexicalReranker;
+    let scores = fallback
+        .rerank(query, documents)
+        .await
+        .unwrap_or_else(|_| vec![0.0; documents.len()]);
+    (scores, fallback.name())
+}
@@ -0,0 +1,327 @@
+//! `search.similar`: "more like this" seeded by an indexed asset's own
+//! stored vectors instead of an embedded text query.
+
+use helix_rs::{HelixDB, HelixDBClient};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use super::filters::{SearchFilterParams, SearchFilters};
+use super::{
+    check_min_score, cursor, finish_page, helix_client, nearest_assets, rank_candidates,
+    value_as_string, Nearest, NearestScope, SearchMode, SearchPage, SemanticHit, UnitCache,
+};
+use crate::sidecar::protocol::{
+    err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
+};
+
+/// Seed vectors searched per request. Long files and videos have many
+/// units; an evenly spaced sample keeps the fan-out bounded.
+const MAX_SEED_VECTORS: usize = 8;
+/// Units that describe where an asset is rather than what it holds.
+const BOOKKEEPING_UNIT_KINDS: &[&str] = &["file_path", "video_index_state"];
+
+#[derive(Debug, Deserialize)]
+struct SearchSimilarParams {
+    /// Seed asset by its indexed path...
+    #[serde(default)]
+    path: Option<String>,
+    /// ...or by content hash. Exactly one of the two.
+    #[serde(default)]
+    content_hash: Option<String>,
+    #[serde(default)]
+    min_score: Option<f64>,
+    #[serde(default)]
+    filters: SearchFilterParams,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+enum SeedError {
+    NotFound(String),
+    Failed(String),
+}
+
+/// First node in a Helix response that looks like an asset.
+fn find_asset(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Object(obj) if obj.contains_key("content_hash") && obj.contains_key("path") => {
+            Some(value)
+        }
+        Value::Object(obj) => obj.values().find_map(find_asset),
+        Value::Array(items) => items.iter().find_map(find_asset),
+        _ => None,
+    }
+}
+
+/// Vector of a stored `AssetEmbedding`. Helix returns it as `data`.
+fn embedding_vector(embedding: &Value) -> Option<Vec<f64>> {
+    let raw = embedding
+        .get("data")
+        .or_else(|| embedding.get("vector"))?
+        .as_array()?;
+    let vector: Option<Vec<f64>> = raw.iter().map(Value::as_f64).collect();
+    vector.filter(|vector| !vector.is_empty())
+}
+
+async fn query_or_not_found(
+    client: &HelixDB,
+    name: &str,
+    payload: &Value,
+) -> Result<Value, SeedError> {
+    match client.query::<_, Value>(name, payload).await {
+        Ok(value) => Ok(value),
+        Err(error) => {
+            let message = error.to_string();
+            if message.to_ascii_lowercase().contains("no value found") {
+                Ok(Value::Null)
+            } else {
+                Err(SeedError::Failed(message))
+            }
+        }
+    }
+}
+
+async fn resolve_seed(
+    client: &HelixDB,
+    path: Option<&str>,
+    content_hash: Option<&str>,
+) -> Result<Value, SeedError> {
+    if let Some(content_hash) = content_hash {
+        let payload = json!({ "content_hash": content_hash });
+        let response = query_or_not_found(client, "GetAssetByHash", &payload).await?;
+        return find_asset(&response).cloned().ok_or_else(|| {
+            SeedError::NotFound(format!(
+                "no indexed asset has content_hash {}",
+                content_hash
+            ))
+        });
+    }
+
+    let path = path.unwrap_or_default();
+    let mut candidates = vec![path.to_string()];
+    if path.contains('\\') {
+        candidates.push(path.replace('\\', "/"));
+    }
+    for candidate in candidates {
+        let payload = json!({ "path": candidate });
+        let response = query_or_not_found(client, "GetAssetsByPath", &payload).await?;
+        if let Some(asset) = find_asset(&response) {
+            return Ok(asset.clone());
+        }
+    }
+    Err(SeedError::NotFound(format!("{} is not indexed", path)))
+}
+
+/// Content vectors of the seed, falling back to its path vector when that is
+/// all it has, thinned to `MAX_SEED_VECTORS`.
+async fn seed_vectors(client: &HelixDB, content_hash: &str) -> Result<Vec<Vec<f64>>, SeedError> {
+    let payload = json!({ "content_hash": content_hash });
+    let response = query_or_not_found(client, "GetAssetEmbeddingsByHash", &payload).await?;
+    let units = response
+        .get("embeddings")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let (content, bookkeeping): (Vec<&Value>, Vec<&Value>) = units.iter().partition(|unit| {
+        !value_as_string(unit.get("unit_kind"))
+            .is_some_and(|kind| BOOKKEEPING_UNIT_KINDS.contains(&kind.as_str()))
+    });
+    let source = if content.is_empty() {
+        bookkeeping
+    } else {
+        content
+    };
+    let vectors: Vec<Vec<f64>> = source.into_iter().filter_map(embedding_vector).collect();
+    if vectors.len() <= MAX_SEED_VECTORS {
+        return Ok(vectors);
+    }
+    let step = vectors.len() as f64 / MAX_SEED_VECTORS as f64;
+    Ok((0..MAX_SEED_VECTORS)
+        .map(|i| vectors[(i as f64 * step) as usize].clone())
+        .collect())
+}
+
+/// Runs one nearest-neighbour search per seed vector and keeps each asset's
+/// closest hit, closest first. Truncated when any one search was.
+async fn similar_assets(
+    client: &HelixDB,
+    vectors: &[Vec<f64>],
+    filters: &SearchFilters,
+    want: usize,
+    seed_hash: &str,
+) -> Result<Nearest, String> {
+    let scope = NearestScope {
+        exclude_hash: Some(seed_hash),
+        ..NearestScope::default()
+    };
+    let mut best: HashMap<String, (usize, SemanticHit)> = HashMap::new();
+    let mut seen = 0usize;
+    let mut truncated = false;
+    for vector in vectors {
+        let nearest = nearest_assets(client, vector, filters, &scope, want).await?;
+        truncated |= nearest.truncated;
+        for hit in nearest.hits {
+            let Some(path) = value_as_string(hit.asset.get("path")) else {
+                continue;
+            };
+            seen += 1;
+            let closer = |current: &SemanticHit| match (hit.distance, current.distance) {
+                (Some(new), Some(old)) => new < old,
+                (Some(_), None) => true,
+                _ => false,
+            };
+            match best.get(&path) {
+                Some((_, current)) if !closer(current) => {}
+                Some((order, _)) => {
+                    let order = *order;
+                    best.insert(path, (order, hit));
+                }
+                None => {
+                    best.insert(path, (seen, hit));
+                }
+            }
+        }
+    }
+
+    let mut hits: Vec<(usize, SemanticHit)> = best.into_values().collect();
+    hits.sort_by(|(a_order, a), (b_order, b)| {
+        let a_dist = a.distance.unwrap_or(f64::INFINITY);
+        let b_dist = b.distance.unwrap_or(f64::INFINITY);
+        a_dist.total_cmp(&b_dist).then(a_order.cmp(b_order))
+    });
+    hits.truncate(want);
+    Ok(Nearest {
+        hits: hits.into_iter().map(|(_, hit)| hit).collect(),
+        truncated,
+    })
+}
+
+pub async fn handle_similar(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let parsed: SearchSimilarParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+    let invalid = |reason: String| {
+        err_response(
+            request.id.clone(),
+            -32602,
+            "Invalid params",
+            Some(json!({ "reason": reason })),
+        )
+    };
+
+    let path = parsed
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    let content_hash = parsed
+        .content_hash
+        .as_deref()
+        .map(str::trim)
+        .filter(|h| !h.is_empty());
+    if path.is_some() == content_hash.is_some() {
+        return invalid("pass exactly one of path or content_hash".to_string());
+    }
+    if let Err(reason) = check_min_score(parsed.min_score) {
+        return invalid(reason);
+    }
+    let filters = match SearchFilters::from_params(&parsed.filters) {
+        Ok(filters) => filters,
+        Err(reason) => return invalid(reason),
+    };
+    let fingerprint = cursor::fingerprint(format!(
+        "similar|{:?}|{:?}|{:?}|{:?}",
+        path, content_hash, parsed.min_score, filters
+    ));
+    let page = match SearchPage::from_params(parsed.limit, parsed.cursor.as_deref(), fingerprint) {
+        Ok(page) => page,
+        Err(reason) => return invalid(reason),
+    };
+
+    let started = Instant::now();
+    let failed = |reason: String| {
+        eprintln!(
+            "[sidecar:search] similar failed in {}ms: {}",
+            started.elapsed().as_millis(),
+            reason
+        );
+        err_response(
+            request.id.clone(),
+            -32603,
+            "Similar search failed",
+            Some(json!({ "reason": reason })),
+        )
+    };
+    let not_found = |reason: String| {
+        err_response(
+            request.id.clone(),
+            -32004,
+            "Asset not found",
+            Some(json!({ "reason": reason })),
+        )
+    };
+
+    let client = match helix_client() {
+        Ok(client) => client,
+        Err(reason) => return failed(reason),
+    };
+    let seed = match resolve_seed(&client, path, content_hash).await {
+        Ok(seed) => seed,
+        Err(SeedError::NotFound(reason)) => return not_found(reason),
+        Err(SeedError::Failed(reason)) => return failed(reason),
+    };
+    let seed_hash = value_as_string(seed.get("content_hash")).unwrap_or_default();
+    let vectors = match seed_vectors(&client, &seed_hash).await {
+        Ok(vectors) if vectors.is_empty() => {
+            return not_found(format!(
+                "asset {} has no stored embedding vectors",
+                seed_hash
+            ));
+        }
+        Ok(vectors) => vectors,
+        Err(SeedError::NotFound(reason)) => return not_found(reason),
+        Err(SeedError::Failed(reason)) => return failed(reason),
+    };
+
+    let depth = page.depth();
+    let nearest = match similar_assets(&client, &vectors, &filters, depth, &seed_hash).await {
+        Ok(nearest) => nearest,
+        Err(reason) => return failed(reason),
+    };
+    let candidates = rank_candidates(SearchMode::Semantic, nearest.hits, Vec::new());
+    let mut units = UnitCache::default();
+    let (results, next_cursor) = finish_page(
+        candidates,
+        parsed.min_score,
+        &page,
+        &HashSet::new(),
+        &mut units,
+    )
+    .await;
+
+    eprintln!(
+        "[sidecar:search] similar search from {} vector(s) completed in {}ms with {} results",
+        vectors.len(),
+        started.elapsed().as_millis(),
+        results.len()
+    );
+    ok_response(
+        request.id.clone(),
+        json!({
+            "seed": {
+                "content_hash": seed_hash,
+                "path": seed.get("path"),
+                "kind": seed.get("kind"),
+            },
+            "results": results,
+            "next_cursor": next_cursor,
+            "truncated": nearest.truncated,
+        }),
+    )
+}
@@ -0,0 +1,219 @@
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::sidecar::rpc::indexing::lexical::tokenize;
+
+pub const SNIPPET_MAX_CHARS: usize = 280;
+/// Characters of lead-in kept before the first highlighted word.
+const CONTEXT_BEFORE_CHARS: usize = 60;
+/// Shortest shared prefix that counts as a related word (`index`/`indexing`).
+const MIN_RELATED_PREFIX: usize = 4;
+
+/// An excerpt of a unit's content, whitespace-collapsed, with the words that
+/// matched the query marked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub text: String,
+    /// Half-open `[start, end)` ranges in UTF-16 code units of `text`, so the
+    /// client can slice the string directly.
+    pub highlights: Vec<(usize, usize)>,
+    /// Byte range of the source content the excerpt was cut from.
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+impl Snippet {
+    pub fn highlights_json(&self) -> Value {
+        Value::Array(
+            self.highlights
+                .iter()
+                .map(|(start, end)| json!({ "start": start, "end": end }))
+                .collect(),
+        )
+    }
+}
+
+/// Query terms in the form the lexical index uses, deduplicated.
+pub fn query_terms(query: &str) -> HashSet<String> {
+    tokenize(query).into_iter().collect()
+}
+
+/// Byte ranges of the words in `content`, split the way the lexical index
+/// splits them.
+fn words(content: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (idx, c) in content.char_indices() {
+        let in_word = c.is_alphanumeric() || c == '_';
+        match (in_word, start) {
+            (true, None) => start = Some(idx),
+            (false, Some(begin)) => {
+                words.push((begin, idx));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        words.push((begin, content.len()));
+    }
+    words
+}
+
+fn is_exact(word: &str, terms: &HashSet<String>) -> bool {
+    tokenize(word).iter().any(|token| terms.contains(token))
+}
+
+/// Loose match for semantic hits that share no exact term with the query,
+/// e.g. `indexer` for `indexing`.
+fn is_related(word: &str, terms: &HashSet<String>) -> bool {
+    let word = word.to_lowercase();
+    terms.iter().any(|term| {
+        let shared = word
+            .chars()
+            .zip(term.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        shared >= MIN_RELATED_PREFIX
+    })
+}
+
+/// Cuts the most query-dense window of `content` and marks the matching
+/// words. Exact term matches win; failing those, words sharing a prefix with
+/// a term. Without either, the excerpt is the start of the content.
+pub fn build(content: &str, terms: &HashSet<String>) -> Snippet {
+    let all_words = words(content);
+    let mut matches: Vec<(usize, usize)> = all_words
+        .iter()
+        .copied()
+        .filter(|(s, e)| is_exact(&content[*s..*e], terms))
+        .collect();
+    if matches.is_empty() {
+        matches = all_words
+            .iter()
+            .copied()
+            .filter(|(s, e)| is_related(&content[*s..*e], terms))
+            .collect();
+    }
+
+    // Char index -> byte offset, with one trailing entry for the end.
+    let offsets: Vec<usize> = content
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(content.len()))
+        .collect();
+    let char_at = |byte: usize| offsets.partition_point(|offset| *offset < byte);
+    let total_chars = offsets.len() - 1;
+
+    let mut window_start = 0usize;
+    let mut best_count = 0usize;
+    for (start, _) in &matches {
+        let candidate = char_at(*start).saturating_sub(CONTEXT_BEFORE_CHARS);
+        let window_end = candidate + SNIPPET_MAX_CHARS;
+        let count = matches
+            .iter()
+            .filter(|(s, e)| char_at(*s) >= candidate && char_at(*e) <= window_end)
+            .count();
+        if count > best_count {
+            best_count = count;
+            window_start = candidate;
+        }
+    }
+    // Don't open mid-word.
+    if window_start > 0 {
+        if let Some((_, word_end)) = all_words
+            .iter()
+            .find(|(s, e)| char_at(*s) < window_start && char_at(*e) > window_start)
+        {
+            window_start = char_at(*word_end);
+        }
+    }
+    let window_end = (window_start + SNIPPET_MAX_CHARS).min(total_chars);
+    let byte_start = offsets[window_start];
+    let byte_end = offsets[window_end];
+
+    render(content, byte_start, byte_end, &matches)
+}
+
+fn render(
+    content: &str,
+    byte_start: usize,
+    byte_end: usize,
+    matches: &[(usize, usize)],
+) -> Snippet {
+    let mut text = String::new();
+    let mut utf16_len = 0usize;
+    let mut push = |text: &mut String, c: char| {
+        text.push(c);
+        utf16_len += c.len_utf16();
+        utf16_len
+    };
+
+    if byte_start > 0 {
+        push(&mut text, '…');
+    }
+    // Output position reached at each source byte offset we may need.
+    let mut positions: Vec<(usize, usize)> = Vec::new();
+    let mut pending_space = false;
+    let mut current = if byte_start > 0 { 1 } else { 0 };
+    for (idx, c) in content[byte_start..byte_end].char_indices() {
+        let at = byte_start + idx;
+        if c.is_whitespace() {
+            pending_space = !text.is_empty() && !text.ends_with('…');
+            positions.push((at, current));
+            continue;
+        }
+        if pending_space {
+            current = push(&mut text, ' ');
+            pending_space = false;
+        }
+        positions.push((at, current));
+        current = push(&mut text, c);
+    }
+    positions.push((byte_end, current));
+    if byte_end < content.len() {
+        push(&mut text, '…');
+    }
+
+    let position_of = |byte: usize| {
+        let idx = positions.partition_point(|(at, _)| *at < byte);
+        positions.get(idx).map(|(_, pos)| *pos)
+    };
+    let highlights = matches
+        .iter()
+        .filter(|(s, e)| *s >= byte_start && *e <= byte_end)
+        .filter_map(|(s, e)| Some((position_of(*s)?, position_of(*e)?)))
+        .filter(|(s, e)| e > s)
+        .collect();
+
+    Snippet {
+        text,
+        highlights,
+        byte_start,
+        byte_end,
+    }
+}
+
+/// Picks the labelled part of an image's `build_embedding_text` output that
+/// best explains the match: the one with the most query words, preferring OCR
+/// and then the summary on ties. Returns the part's label and text.
+pub fn best_image_part<'a>(content: &'a str, terms: &HashSet<String>) -> (&'a str, &'a str) {
+    let priority = |label: &str| match label {
+        "ocr" => 0,
+        "summary" => 1,
+        _ => 2,
+    };
+    content
+        .split(" | ")
+        .filter_map(|part| part.split_once(": "))
+        .map(|(label, text)| {
+            let hits = words(text)
+                .iter()
+                .filter(|(s, e)| is_exact(&text[*s..*e], terms))
+                .count();
+            (label, text, hits)
+        })
+        .min_by(|a, b| b.2.cmp(&a.2).then(priority(a.0).cmp(&priority(b.0))))
+        .map(|(label, text, _)| (label, text))
+        .unwrap_or(("content", content))
+}
@@ -0,0 +1,226 @@
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{snippet, value_as_string, UnitCache};
+use crate::sidecar::rpc::indexing::video::chunk_thumbnail_file_name;
+use crate::sidecar::rpc::indexing::video::span::{
+    VideoSpan, FRAME_SUMMARY_UNIT_KIND, TRANSCRIPT_UNIT_KIND,
+};
+
+fn infer_thumbnails_dir() -> PathBuf {
+    if let Ok(custom_dir) = env::var("THUMBNAILS_DIR") {
+        return PathBuf::from(custom_dir);
+    }
+
+    std::env::current_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("videos")
+        .join("output_indexer")
+        .join("thumbnail_cache")
+}
+
+fn infer_extracted_thumbnails_dir() -> PathBuf {
+    if let Ok(custom_dir) = env::var("EXTRACTED_THUMBNAILS_DIR") {
+        return PathBuf::from(custom_dir);
+    }
+
+    std::env::current_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("videos")
+        .join("output_indexer")
+        .join("thumbnails")
+}
+
+fn find_extracted_thumbnail(video_path: &str) -> Option<PathBuf> {
+    let stem = Path::new(video_path)
+        .file_stem()?
+        .to_string_lossy()
+        .to_string();
+    if stem.is_empty() {
+        return None;
+    }
+
+    let extracted_dir = infer_extracted_thumbnails_dir();
+
+    for name in ["middle.jpg", "start.jpg", "end.jpg"] {
+        let direct = extracted_dir.join(&stem).join(name);
+        if direct.exists() {
+            return Some(direct);
+        }
+    }
+
+    let prefix = format!("{}_chunk_", stem);
+    let mut candidates = fs::read_dir(&extracted_dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let file_type = entry.file_type().ok()?;
+            if !file_type.is_dir() {
+                return None;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(&prefix) {
+                Some((name, entry.path()))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (_, dir) in candidates {
+        for name in ["middle.jpg", "start.jpg", "end.jpg"] {
+            let candidate = dir.join(name);
+            if candidate.exists() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+fn resolve_thumbnail_path(content_hash: &str, video_path: &str) -> Option<String> {
+    if content_hash.is_empty() {
+        return None;
+    }
+
+    let cache_dir = infer_thumbnails_dir();
+    let cached = cache_dir.join(format!("{}.jpg", content_hash));
+    if cached.exists() {
+        return Some(cached.to_string_lossy().replace('\\', "/"));
+    }
+
+    let source = find_extracted_thumbnail(video_path)?;
+    fs::create_dir_all(&cache_dir).ok()?;
+    fs::copy(source, &cached).ok()?;
+
+    Some(cached.to_string_lossy().replace('\\', "/"))
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for &byte in value.as_bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~' | b'/' | b':') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn resolve_chunk_thumbnail(content_hash: &str, chunk_index: usize) -> Option<String> {
+    if content_hash.is_empty() {
+        return None;
+    }
+    let cached = infer_thumbnails_dir().join(chunk_thumbnail_file_name(content_hash, chunk_index));
+    cached
+        .exists()
+        .then(|| cached.to_string_lossy().replace('\\', "/"))
+}
+
+fn thumbnail_url(path: &str) -> Value {
+    Value::String(format!("localimg://preview?path={}", percent_encode(path)))
+}
+
+fn matched_span(unit: &Value) -> Option<(String, VideoSpan)> {
+    let unit_kind = value_as_string(unit.get("unit_kind"))?;
+    let unit_key = value_as_string(unit.get("unit_key"))?;
+    let span = VideoSpan::parse_unit_key(&unit_key)?;
+    Some((unit_kind, span))
+}
+
+/// Adds the matching moment of a video hit: its chunk, start and end time,
+/// that chunk's thumbnail and whatever text of the chunk the unit carried.
+/// Hits on the file name or without a chunk keep the whole-video thumbnail.
+pub fn apply_video_match(
+    result: &mut Value,
+    content_hash: &str,
+    path: &str,
+    unit: Option<&Value>,
+    terms: &HashSet<String>,
+) {
+    let matched = unit.and_then(|unit| Some((unit, matched_span(unit)?)));
+    let mut thumbnail = None;
+    if let Some((unit, (unit_kind, span))) = matched {
+        result["moment"] = json!({
+            "chunk_index": span.chunk_index,
+            "start_secs": span.start_ms as f64 / 1000.0,
+            "end_secs": span.end_ms as f64 / 1000.0,
+            "unit_kind": unit_kind,
+        });
+        if let Some(content) = value_as_string(unit.get("content")) {
+            let field = if unit_kind == TRANSCRIPT_UNIT_KIND {
+                "transcript_excerpt"
+            } else {
+                "snippet"
+            };
+            set_excerpt(result, field, &content, terms);
+        }
+        thumbnail = resolve_chunk_thumbnail(content_hash, span.chunk_index);
+    }
+
+    if let Some(thumbnail_path) = thumbnail.or_else(|| resolve_thumbnail_path(content_hash, path)) {
+        result["thumbnail_url"] = thumbnail_url(&thumbnail_path);
+    }
+}
+
+/// Sets `field` to a query-focused excerpt of `content` and the matching
+/// highlight list next to it (`highlights` or `transcript_highlights`).
+fn set_excerpt(result: &mut Value, field: &str, content: &str, terms: &HashSet<String>) {
+    let excerpt = snippet::build(content, terms);
+    let highlights_field = if field == "snippet" {
+        "highlights".to_string()
+    } else {
+        field.replace("_excerpt", "_highlights")
+    };
+    result[highlights_field.as_str()] = excerpt.highlights_json();
+    result[field] = Value::String(excerpt.text);
+}
+
+/// Fills in the transcript excerpt (and, for frame matches, the frame
+/// summary) of a video moment whose matched unit did not carry that text,
+/// such as frame-summary hits and lexical hits.
+pub async fn attach_moment_excerpts(
+    units: &mut UnitCache,
+    content_hash: &str,
+    result: &mut Value,
+    terms: &HashSet<String>,
+) {
+    let Some(chunk_index) = result
+        .get("moment")
+        .and_then(|moment| moment.get("chunk_index"))
+        .and_then(Value::as_u64)
+    else {
+        return;
+    };
+    let is_frame = result["moment"]["unit_kind"] == json!(FRAME_SUMMARY_UNIT_KIND);
+    let wants_transcript = result.get("transcript_excerpt").is_none();
+    let wants_snippet = is_frame && result.get("snippet").is_none();
+    if !wants_transcript && !wants_snippet {
+        return;
+    }
+
+    for unit in units.units(content_hash).await {
+        let Some((unit_kind, span)) = matched_span(unit) else {
+            continue;
+        };
+        if span.chunk_index as u64 != chunk_index {
+            continue;
+        }
+        let Some(content) = value_as_string(unit.get("content")) else {
+            continue;
+        };
+        if wants_transcript && unit_kind == TRANSCRIPT_UNIT_KIND {
+            set_excerpt(result, "transcript_excerpt", &content, terms);
+        }
+        if wants_snippet && unit_kind == FRAME_SUMMARY_UNIT_KIND {
+            set_excerpt(result, "snippet", &content, terms);
+        }
+    }
+}
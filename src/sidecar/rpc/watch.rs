@@ -0,0 +1,584 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::sidecar::output::emit_notification;
+use crate::sidecar::protocol::{
+    err_response, ok_response, parse_params, JsonRpcRequest, JsonRpcResponse,
+};
+use crate::sidecar::rpc::indexing::adapters::groq::GroqClient;
+use crate::sidecar::rpc::indexing::adapters::hash::{
+    CachedPathHasher, PathHasher, Sha256PathHasher,
+};
+use crate::sidecar::rpc::indexing::adapters::helix::HelixTextStore;
+use crate::sidecar::rpc::indexing::collect::{collect_files, FileTypeConfig};
+use crate::sidecar::rpc::indexing::control::{JobControl, JOB_CANCELLED};
+use crate::sidecar::rpc::indexing::ignore::IgnoreRules;
+use crate::sidecar::rpc::indexing::image::image_indexer_with_sidecar;
+use crate::sidecar::rpc::indexing::lexical;
+use crate::sidecar::rpc::indexing::reconcile::{
+    drop_partial_assets, normalize_root, reconcile_changes,
+};
+use crate::sidecar::rpc::indexing::text::{file_indexer, DUPLICATE_CONTENT_HASH};
+use crate::sidecar::rpc::indexing::video::{
+    default_output_dir, index_video_with_sidecar, DEFAULT_CHUNK_DURATION_SECS,
+};
+use crate::sidecar::runtime::shared_runtime;
+
+const DEFAULT_DEBOUNCE_MS: u64 = 750;
+/// A directory that never goes quiet still gets flushed after this many
+/// debounce windows.
+const MAX_DEBOUNCE_WINDOWS: u32 = 8;
+
+#[derive(Debug, Deserialize)]
+struct WatchAddParams {
+    dir: String,
+    #[serde(default)]
+    debounce_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchRemoveParams {
+    dir: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchListParams {}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct WatchStats {
+    batches: usize,
+    files_indexed: usize,
+    files_skipped: usize,
+    files_moved: usize,
+    files_removed: usize,
+    files_failed: usize,
+    last_batch_at: Option<String>,
+}
+
+struct WatchEntry {
+    debounce_ms: u64,
+    started_at: String,
+    stats: Arc<Mutex<WatchStats>>,
+    control: Arc<JobControl>,
+    // Dropping the watcher closes the event channel, which stops the worker.
+    _watcher: RecommendedWatcher,
+}
+
+static WATCHES: OnceLock<Mutex<HashMap<String, WatchEntry>>> = OnceLock::new();
+
+fn watches() -> &'static Mutex<HashMap<String, WatchEntry>> {
+    WATCHES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now_string() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .to_string()
+}
+
+fn default_debounce_ms() -> u64 {
+    env::var("SIDECAR_WATCH_DEBOUNCE_MS")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_DEBOUNCE_MS)
+}
+
+fn normalize_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn update_stats(stats: &Mutex<WatchStats>, updater: impl FnOnce(&mut WatchStats)) {
+    let mut stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+    updater(&mut stats);
+}
+
+/// Folds one watcher event into the pending set. Access events never change
+/// what is indexed, so they are dropped here.
+fn collect_event(root: &str, event: notify::Result<Event>, pending: &mut HashSet<PathBuf>) {
+    match event {
+        Ok(event) => {
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            pending.extend(event.paths);
+        }
+        Err(error) => {
+            eprintln!("[sidecar:watch] watcher error for {}: {}", root, error);
+            emit_notification(
+                "watch.error",
+                json!({ "root": root, "reason": error.to_string() }),
+            );
+        }
+    }
+}
+
+struct WatchWorker {
+    root: String,
+    store: HelixTextStore,
+    groq: Result<GroqClient, String>,
+    output_dir: String,
+    stats: Arc<Mutex<WatchStats>>,
+    control: Arc<JobControl>,
+}
+
+impl WatchWorker {
+    fn run(self, events: Receiver<notify::Result<Event>>, debounce: Duration) {
+        let runtime = shared_runtime();
+
+        while let Ok(first) = events.recv() {
+            let mut pending = HashSet::new();
+            collect_event(&self.root, first, &mut pending);
+
+            let batch_started = Instant::now();
+            let mut disconnected = false;
+            while batch_started.elapsed() < debounce * MAX_DEBOUNCE_WINDOWS {
+                match events.recv_timeout(debounce) {
+                    Ok(event) => collect_event(&self.root, event, &mut pending),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        disconnected = true;
+                        break;
+                    }
+                }
+            }
+
+            if disconnected || self.control.is_cancelled() {
+                break;
+            }
+            if !pending.is_empty() {
+                runtime.block_on(self.process_batch(pending));
+                lexical::flush();
+            }
+        }
+
+        eprintln!("[sidecar:watch] stopped watching {}", self.root);
+    }
+
+    /// Splits changed paths into existing files to reconcile and paths that are
+    /// gone (or are now ignored), expanding newly created directories.
+    fn classify(&self, pending: HashSet<PathBuf>) -> (Vec<(&'static str, String)>, Vec<String>) {
+        let file_types = FileTypeConfig::load();
+        let ignore = IgnoreRules::load();
+        let root = Path::new(&self.root);
+
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+        for path in pending {
+            if path.is_dir() {
+                if ignore.is_ignored(root, &path) {
+                    removed.push(normalize_path(&path));
+                    continue;
+                }
+                let dir = normalize_path(&path);
+                for (kind, exts) in [
+                    ("file", &file_types.text),
+                    ("image", &file_types.image),
+                    ("video", &file_types.video),
+                ] {
+                    // Rules from ignore files above `dir` are not seen by the
+                    // walk, so each file is re-checked against the watch root.
+                    changed.extend(
+                        collect_files(&dir, exts, &ignore)
+                            .into_iter()
+                            .filter(|file| !ignore.is_ignored(root, Path::new(file)))
+                            .map(|file| (kind, file)),
+                    );
+                }
+            } else if path.is_file() {
+                if ignore.is_ignored(root, &path) {
+                    removed.push(normalize_path(&path));
+                } else if let Some(kind) = file_types.kind_of(&path) {
+                    changed.push((kind, normalize_path(&path)));
+                }
+            } else {
+                removed.push(normalize_path(&path));
+            }
+        }
+
+        changed.sort();
+        changed.dedup();
+        (changed, removed)
+    }
+
+    fn file_indexed(&self, kind: &str, path: &str) {
+        update_stats(&self.stats, |stats| stats.files_indexed += 1);
+        emit_notification(
+            "watch.fileIndexed",
+            json!({ "root": self.root, "kind": kind, "path": path }),
+        );
+    }
+
+    fn file_skipped(&self, kind: &str, path: &str, reason: &str) {
+        update_stats(&self.stats, |stats| stats.files_skipped += 1);
+        emit_notification(
+            "watch.fileSkipped",
+            json!({ "root": self.root, "kind": kind, "path": path, "reason": reason }),
+        );
+    }
+
+    fn file_failed(&self, kind: &str, path: &str, reason: &str) {
+        eprintln!(
+            "[sidecar:watch] failed to index {} {}: {}",
+            kind, path, reason
+        );
+        update_stats(&self.stats, |stats| stats.files_failed += 1);
+        emit_notification(
+            "watch.fileFailed",
+            json!({ "root": self.root, "kind": kind, "path": path, "reason": reason }),
+        );
+    }
+
+    async fn process_batch(&self, pending: HashSet<PathBuf>) {
+        let (changed, removed) = self.classify(pending);
+        if changed.is_empty() && removed.is_empty() {
+            return;
+        }
+        eprintln!(
+            "[sidecar:watch] {}: {} changed, {} removed",
+            self.root,
+            changed.len(),
+            removed.len()
+        );
+        update_stats(&self.stats, |stats| {
+            stats.batches += 1;
+            stats.last_batch_at = Some(now_string());
+        });
+
+        let mut report =
+            match reconcile_changes(changed, removed, &Sha256PathHasher, &self.store).await {
+                Ok(report) => report,
+                Err(error) => {
+                    eprintln!(
+                        "[sidecar:watch] reconcile failed for {}: {}",
+                        self.root, error
+                    );
+                    emit_notification("watch.error", json!({ "root": self.root, "reason": error }));
+                    return;
+                }
+            };
+        for (path, error) in &report.errors {
+            eprintln!("[sidecar:watch] reconcile warning for {}: {}", path, error);
+        }
+        for (from, to) in &report.moves {
+            update_stats(&self.stats, |stats| stats.files_moved += 1);
+            emit_notification(
+                "watch.fileMoved",
+                json!({ "root": self.root, "from": from, "to": to }),
+            );
+        }
+        for path in &report.removed_paths {
+            update_stats(&self.stats, |stats| stats.files_removed += 1);
+            emit_notification(
+                "watch.fileRemoved",
+                json!({ "root": self.root, "path": path }),
+            );
+        }
+
+        let text_plan = report.take_plan("file");
+        let image_plan = report.take_plan("image");
+        let video_plan = report.take_plan("video");
+        let hasher = CachedPathHasher::new(std::mem::take(&mut report.hashes));
+
+        if !text_plan.to_index.is_empty() {
+            let results =
+                file_indexer(text_plan.to_index, &hasher, &self.store, &self.control).await;
+            drop_partial_assets(
+                &self.store,
+                results
+                    .iter()
+                    .filter(|r| r.created && !r.indexed)
+                    .filter_map(|r| r.content_hash.as_deref()),
+            )
+            .await;
+            for result in &results {
+                match (&result.error, result.indexed) {
+                    (_, true) => self.file_indexed("file", &result.path),
+                    (Some(error), _) if result.is_skipped() => {
+                        self.file_skipped("file", &result.path, error)
+                    }
+                    (error, _) => self.file_failed(
+                        "file",
+                        &result.path,
+                        error.as_deref().unwrap_or("unknown error"),
+                    ),
+                }
+            }
+        }
+
+        if image_plan.to_index.is_empty() && video_plan.to_index.is_empty() {
+            return;
+        }
+        let groq = match &self.groq {
+            Ok(groq) => groq,
+            Err(error) => {
+                for path in image_plan.to_index.iter() {
+                    self.file_failed("image", path, error);
+                }
+                for path in video_plan.to_index.iter() {
+                    self.file_failed("video", path, error);
+                }
+                return;
+            }
+        };
+
+        if !image_plan.to_index.is_empty() {
+            let results = image_indexer_with_sidecar(
+                image_plan.to_index,
+                groq,
+                &hasher,
+                &self.store,
+                &self.control,
+            )
+            .await;
+            drop_partial_assets(
+                &self.store,
+                results
+                    .iter()
+                    .filter(|r| r.created && !r.indexed)
+                    .filter_map(|r| r.content_hash.as_deref()),
+            )
+            .await;
+            for result in &results {
+                match result.error.as_deref() {
+                    _ if result.indexed => self.file_indexed("image", &result.path),
+                    Some(DUPLICATE_CONTENT_HASH) => {
+                        self.file_skipped("image", &result.path, DUPLICATE_CONTENT_HASH)
+                    }
+                    error => {
+                        self.file_failed("image", &result.path, error.unwrap_or("unknown error"))
+                    }
+                }
+            }
+        }
+
+        for video_path in video_plan.to_index {
+            let content_hash = match hasher.compute_file_hash(&video_path).await {
+                Ok(hash) => hash,
+                Err(error) => {
+                    self.file_failed("video", &video_path, &error);
+                    continue;
+                }
+            };
+            match index_video_with_sidecar(
+                &content_hash,
+                &video_path,
+                &self.output_dir,
+                DEFAULT_CHUNK_DURATION_SECS,
+                groq,
+                &self.store,
+                &self.control,
+            )
+            .await
+            {
+                Ok(r) if r.indexed => self.file_indexed("video", &video_path),
+                Ok(r) if r.error.as_deref() == Some(DUPLICATE_CONTENT_HASH) => {
+                    self.file_skipped("video", &video_path, DUPLICATE_CONTENT_HASH)
+                }
+                Ok(r) => self.file_failed(
+                    "video",
+                    &video_path,
+                    r.error
+                        .as_deref()
+                        .unwrap_or("video produced no searchable chunks"),
+                ),
+                Err(error) if error == JOB_CANCELLED => break,
+                Err(error) => self.file_failed("video", &video_path, &error),
+            }
+        }
+    }
+}
+
+pub fn handle_add(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let params: WatchAddParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+
+    let root = normalize_root(params.dir.trim());
+    if root.is_empty() || !Path::new(&root).is_dir() {
+        return err_response(
+            request.id.clone(),
+            -32602,
+            "Invalid params",
+            Some(json!({ "reason": format!("not a directory: {}", params.dir) })),
+        );
+    }
+    let debounce_ms = params.debounce_ms.unwrap_or_else(default_debounce_ms);
+
+    let mut registry = match watches().lock() {
+        Ok(registry) => registry,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Watch add failed",
+                Some(json!({ "reason": error.to_string() })),
+            );
+        }
+    };
+    if let Some(entry) = registry.get(&root) {
+        return ok_response(
+            request.id.clone(),
+            json!({
+                "dir": root,
+                "debounce_ms": entry.debounce_ms,
+                "started_at": entry.started_at,
+                "already_watching": true,
+            }),
+        );
+    }
+
+    let store = match HelixTextStore::from_env() {
+        Ok(store) => store,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Watch add failed",
+                Some(json!({ "reason": error })),
+            );
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Watch add failed",
+                Some(json!({ "reason": error.to_string() })),
+            );
+        }
+    };
+    if let Err(error) = watcher.watch(Path::new(&root), RecursiveMode::Recursive) {
+        return err_response(
+            request.id.clone(),
+            -32603,
+            "Watch add failed",
+            Some(json!({ "reason": error.to_string() })),
+        );
+    }
+
+    let stats = Arc::new(Mutex::new(WatchStats::default()));
+    let control = Arc::new(JobControl::default());
+    let worker = WatchWorker {
+        root: root.clone(),
+        store,
+        // Text changes are still indexed without Groq; image and video
+        // changes report this error per file instead.
+        groq: GroqClient::from_env(),
+        output_dir: default_output_dir(),
+        stats: Arc::clone(&stats),
+        control: Arc::clone(&control),
+    };
+    let debounce = Duration::from_millis(debounce_ms);
+    thread::spawn(move || worker.run(receiver, debounce));
+
+    let started_at = now_string();
+    registry.insert(
+        root.clone(),
+        WatchEntry {
+            debounce_ms,
+            started_at: started_at.clone(),
+            stats,
+            control,
+            _watcher: watcher,
+        },
+    );
+    eprintln!(
+        "[sidecar:watch] watching {} (debounce {}ms)",
+        root, debounce_ms
+    );
+
+    ok_response(
+        request.id.clone(),
+        json!({
+            "dir": root,
+            "debounce_ms": debounce_ms,
+            "started_at": started_at,
+            "already_watching": false,
+        }),
+    )
+}
+
+pub fn handle_remove(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let params: WatchRemoveParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+
+    let root = normalize_root(params.dir.trim());
+    let removed = match watches().lock() {
+        Ok(mut registry) => registry.remove(&root),
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Watch remove failed",
+                Some(json!({ "reason": error.to_string() })),
+            );
+        }
+    };
+
+    // Stop a batch that is still indexing instead of letting it run to the end.
+    if let Some(entry) = &removed {
+        entry.control.cancel();
+    }
+
+    ok_response(
+        request.id.clone(),
+        json!({ "dir": root, "removed": removed.is_some() }),
+    )
+}
+
+pub fn handle_list(request: &JsonRpcRequest) -> JsonRpcResponse {
+    let _: WatchListParams = match parse_params(request) {
+        Ok(parsed) => parsed,
+        Err(error_response) => return error_response,
+    };
+
+    let registry = match watches().lock() {
+        Ok(registry) => registry,
+        Err(error) => {
+            return err_response(
+                request.id.clone(),
+                -32603,
+                "Watch list failed",
+                Some(json!({ "reason": error.to_string() })),
+            );
+        }
+    };
+
+    let mut watching: Vec<serde_json::Value> = registry
+        .iter()
+        .map(|(root, entry)| {
+            let stats = entry
+                .stats
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+            json!({
+                "dir": root,
+                "debounce_ms": entry.debounce_ms,
+                "started_at": entry.started_at,
+                "stats": stats,
+            })
+        })
+        .collect();
+    watching.sort_by(|a, b| a["dir"].as_str().cmp(&b["dir"].as_str()));
+
+    ok_response(request.id.clone(), json!({ "watching": watching }))
+}
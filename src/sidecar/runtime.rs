@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+static FAMILY_LIMITS: OnceLock<Mutex<HashMap<String, Arc<Semaphore>>>> = OnceLock::new();
+
+/// The one tokio runtime shared by request dispatch, index jobs and watchers.
+/// `SIDECAR_WORKER_THREADS` overrides tokio's default worker count.
+pub fn shared_runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all().thread_name("sidecar-worker");
+        if let Some(threads) = env::var("SIDECAR_WORKER_THREADS")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .filter(|threads| *threads > 0)
+        {
+            builder.worker_threads(threads);
+        }
+        builder
+            .build()
+            .expect("failed to start the sidecar tokio runtime")
+    })
+}
+
+/// `search.query` -> `search`; methods without a namespace form their own family.
+pub fn method_family(method: &str) -> &str {
+    method.split_once('.').map_or(method, |(family, _)| family)
+}
+
+fn default_family_limit(family: &str) -> usize {
+    match family {
+        "search" => 4,
+        "index" | "fs" | "watch" => 2,
+        _ => 8,
+    }
+}
+
+/// Concurrency cap for one method family, read from
+/// `SIDECAR_CONCURRENCY_<FAMILY>` (e.g. `SIDECAR_CONCURRENCY_SEARCH`).
+pub fn family_limit(family: &str) -> usize {
+    env::var(format!(
+        "SIDECAR_CONCURRENCY_{}",
+        family.to_ascii_uppercase()
+    ))
+    .ok()
+    .and_then(|raw| raw.trim().parse::<usize>().ok())
+    .filter(|limit| *limit > 0)
+    .unwrap_or_else(|| default_family_limit(family))
+}
+
+/// Waits for a slot in `method`'s family; the slot is released on drop.
+pub async fn acquire_family_permit(method: &str) -> OwnedSemaphorePermit {
+    let family = method_family(method);
+    let semaphore = {
+        let mut limits = FAMILY_LIMITS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        Arc::clone(
+            limits
+                .entry(family.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(family_limit(family)))),
+        )
+    };
+    semaphore
+        .acquire_owned()
+        .await
+        .expect("family semaphores are never closed")
+}
@@ -0,0 +1,263 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use the_search_thing::sidecar::rpc::indexing::adapters::hash::PathHasher;
+use the_search_thing::sidecar::rpc::indexing::adapters::store::{
+    AssetCatalogStore, FileStamp, IndexedAsset,
+};
+use the_search_thing::sidecar::rpc::indexing::reconcile::{reconcile, ReconcileReport};
+
+/// Hashes by path so a test decides which files share content.
+#[derive(Default)]
+struct FakeHasher {
+    hashes: HashMap<String, String>,
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl PathHasher for FakeHasher {
+    async fn compute_file_hash(&self, path: &str) -> Result<String, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.hashes
+            .get(path)
+            .cloned()
+            .ok_or_else(|| format!("no hash for {}", path))
+    }
+}
+
+#[derive(Default)]
+struct MemoryCatalog {
+    assets: Mutex<HashMap<String, IndexedAsset>>,
+}
+
+impl MemoryCatalog {
+    fn with(assets: Vec<IndexedAsset>) -> Self {
+        Self {
+            assets: Mutex::new(
+                assets
+                    .into_iter()
+                    .map(|asset| (asset.content_hash.clone(), asset))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn path_of(&self, content_hash: &str) -> Option<String> {
+        let assets = self.assets.lock().unwrap();
+        assets.get(content_hash).map(|asset| asset.path.clone())
+    }
+}
+
+#[async_trait]
+impl AssetCatalogStore for MemoryCatalog {
+    async fn list_assets(&self) -> Result<Vec<IndexedAsset>, String> {
+        Ok(self.assets.lock().unwrap().values().cloned().collect())
+    }
+
+    async fn asset_is_complete(&self, _asset: &IndexedAsset) -> Result<bool, String> {
+        Ok(true)
+    }
+
+    async fn update_asset_location(
+        &self,
+        content_hash: &str,
+        path: &str,
+        stamp: FileStamp,
+    ) -> Result<(), String> {
+        let mut assets = self.assets.lock().unwrap();
+        let asset = assets
+            .get_mut(content_hash)
+            .ok_or_else(|| format!("no asset {}", content_hash))?;
+        asset.path = path.to_string();
+        asset.stamp = Some(stamp);
+        Ok(())
+    }
+
+    async fn delete_asset(&self, content_hash: &str) -> Result<(), String> {
+        self.assets.lock().unwrap().remove(content_hash);
+        Ok(())
+    }
+}
+
+fn make_temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time")
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("reconcile-{}-{}", name, nanos));
+    fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+fn write_file(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, content).expect("write file");
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn asset(content_hash: &str, path: &str, stamp: Option<FileStamp>) -> IndexedAsset {
+    IndexedAsset {
+        content_hash: content_hash.to_string(),
+        kind: "file".to_string(),
+        path: path.to_string(),
+        stamp,
+        indexed_at: None,
+    }
+}
+
+fn run(
+    root: &str,
+    files: Vec<String>,
+    hasher: &FakeHasher,
+    catalog: &MemoryCatalog,
+) -> ReconcileReport {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime")
+        .block_on(reconcile(root, vec![("file", files)], hasher, catalog))
+        .expect("reconcile")
+}
+
+fn root_of(dir: &Path) -> String {
+    dir.to_string_lossy().replace('\\', "/")
+}
+
+#[test]
+fn unchanged_file_is_kept_without_hashing() {
+    let dir = make_temp_dir("unchanged");
+    let path = write_file(&dir, "notes.txt", "same");
+    let catalog = MemoryCatalog::with(vec![asset("h1", &path, FileStamp::read(&path))]);
+    let hasher = FakeHasher::default();
+
+    let mut report = run(&root_of(&dir), vec![path.clone()], &hasher, &catalog);
+    let plan = report.take_plan("file");
+
+    assert_eq!(plan.unchanged, 1);
+    assert!(plan.to_index.is_empty());
+    assert_eq!(hasher.calls.load(Ordering::SeqCst), 0);
+    assert_eq!(catalog.path_of("h1"), Some(path));
+}
+
+#[test]
+fn moved_file_updates_the_asset_path() {
+    let dir = make_temp_dir("moved");
+    let old_path = root_of(&dir) + "/old.txt";
+    let new_path = write_file(&dir, "new.txt", "moved content");
+    let catalog = MemoryCatalog::with(vec![asset("h1", &old_path, None)]);
+    let hasher = FakeHasher {
+        hashes: HashMap::from([(new_path.clone(), "h1".to_string())]),
+        ..FakeHasher::default()
+    };
+
+    let mut report = run(&root_of(&dir), vec![new_path.clone()], &hasher, &catalog);
+    let plan = report.take_plan("file");
+
+    assert_eq!(plan.moved, 1);
+    assert!(plan.to_index.is_empty());
+    assert_eq!(report.moves, vec![(old_path, new_path.clone())]);
+    assert!(report.removed_paths.is_empty());
+    assert_eq!(catalog.path_of("h1"), Some(new_path));
+}
+
+#[test]
+fn changed_content_drops_the_stale_asset_and_reindexes() {
+    let dir = make_temp_dir("changed");
+    let path = write_file(&dir, "notes.txt", "new content");
+    let stale_stamp = FileStamp {
+        modified_at: 1,
+        size_bytes: 3,
+    };
+    let catalog = MemoryCatalog::with(vec![asset("old", &path, Some(stale_stamp))]);
+    let hasher = FakeHasher {
+        hashes: HashMap::from([(path.clone(), "new".to_string())]),
+        ..FakeHasher::default()
+    };
+
+    let mut report = run(&root_of(&dir), vec![path.clone()], &hasher, &catalog);
+    let plan = report.take_plan("file");
+
+    assert_eq!(report.replaced, 1);
+    assert_eq!(plan.to_index, vec![path.clone()]);
+    assert_eq!(report.hashes.get(&path), Some(&"new".to_string()));
+    assert_eq!(catalog.path_of("old"), None);
+}
+
+#[test]
+fn deleted_file_removes_its_asset() {
+    let dir = make_temp_dir("deleted");
+    let kept = write_file(&dir, "kept.txt", "kept");
+    let gone = root_of(&dir) + "/gone.txt";
+    let outside = "/elsewhere/other.txt";
+    let catalog = MemoryCatalog::with(vec![
+        asset("kept", &kept, FileStamp::read(&kept)),
+        asset("gone", &gone, None),
+        asset("outside", outside, None),
+    ]);
+    let hasher = FakeHasher::default();
+
+    let report = run(&root_of(&dir), vec![kept.clone()], &hasher, &catalog);
+
+    assert_eq!(report.removed_paths, vec![gone]);
+    assert_eq!(catalog.path_of("gone"), None);
+    assert_eq!(catalog.path_of("kept"), Some(kept));
+    // Assets outside the indexed root are not this run's to delete.
+    assert_eq!(catalog.path_of("outside"), Some(outside.to_string()));
+}
+
+#[test]
+fn identical_files_keep_their_asset_when_one_changes_in_either_order() {
+    let dir = make_temp_dir("identical-changed");
+    let changed = write_file(&dir, "a.txt", "new content");
+    let copy = write_file(&dir, "b.txt", "same");
+    let stale_stamp = FileStamp {
+        modified_at: 1,
+        size_bytes: 4,
+    };
+    let hasher = FakeHasher {
+        hashes: HashMap::from([
+            (changed.clone(), "new".to_string()),
+            (copy.clone(), "same".to_string()),
+        ]),
+        ..FakeHasher::default()
+    };
+
+    for files in [
+        vec![changed.clone(), copy.clone()],
+        vec![copy.clone(), changed.clone()],
+    ] {
+        let catalog = MemoryCatalog::with(vec![asset("same", &changed, Some(stale_stamp))]);
+
+        let mut report = run(&root_of(&dir), files, &hasher, &catalog);
+        let plan = report.take_plan("file");
+
+        assert_eq!(report.replaced, 0);
+        assert!(report.removed_paths.is_empty());
+        assert_eq!(plan.moved, 1);
+        assert_eq!(plan.to_index, vec![changed.clone()]);
+        assert_eq!(catalog.path_of("same"), Some(copy.clone()));
+    }
+}
+
+#[test]
+fn identical_files_keep_their_asset_when_one_is_removed() {
+    let dir = make_temp_dir("identical-removed");
+    let removed = root_of(&dir) + "/a.txt";
+    let copy = write_file(&dir, "b.txt", "same");
+    let catalog = MemoryCatalog::with(vec![asset("same", &removed, None)]);
+    let hasher = FakeHasher {
+        hashes: HashMap::from([(copy.clone(), "same".to_string())]),
+        ..FakeHasher::default()
+    };
+
+    let mut report = run(&root_of(&dir), vec![copy.clone()], &hasher, &catalog);
+    let plan = report.take_plan("file");
+
+    assert!(report.removed_paths.is_empty());
+    assert_eq!(report.moves, vec![(removed, copy.clone())]);
+    assert!(plan.to_index.is_empty());
+    assert_eq!(catalog.path_of("same"), Some(copy));
+}
@@ -1,15 +1,15 @@
 use serde_json::{json, Value};
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 fn sidecar_bin() -> &'static str {
     env!("CARGO_BIN_EXE_the-search-thing-sidecar")
 }
 
-fn run_sidecar_requests(requests: &[Value], envs: &[(&str, &str)]) -> Vec<Value> {
+fn spawn_sidecar(envs: &[(&str, &str)]) -> Child {
     let mut cmd = Command::new(sidecar_bin());
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -19,27 +19,43 @@ fn run_sidecar_requests(requests: &[Value], envs: &[(&str, &str)]) -> Vec<Value>
         cmd.env(key, value);
     }
 
-    let mut child = cmd.spawn().expect("spawn sidecar");
+    cmd.spawn().expect("spawn sidecar")
+}
 
-    {
-        let mut stdin = child.stdin.take().expect("sidecar stdin");
-        for req in requests {
-            let line = serde_json::to_string(req).expect("serialize request");
-            stdin.write_all(line.as_bytes()).expect("write request");
-            stdin.write_all(b"\n").expect("write newline");
+/// Sends requests one at a time, waiting for each response before the next,
+/// since the sidecar answers concurrent requests out of order.
+fn run_sidecar_requests(requests: &[Value], envs: &[(&str, &str)]) -> Vec<Value> {
+    let mut child = spawn_sidecar(envs);
+    let mut stdin = child.stdin.take().expect("sidecar stdin");
+    let mut stdout = BufReader::new(child.stdout.take().expect("sidecar stdout"));
+
+    let mut responses = Vec::new();
+    for req in requests {
+        let line = serde_json::to_string(req).expect("serialize request");
+        stdin.write_all(line.as_bytes()).expect("write request");
+        stdin.write_all(b"\n").expect("write newline");
+        stdin.flush().expect("flush request");
+
+        loop {
+            let mut line = String::new();
+            let read = stdout.read_line(&mut line).expect("stdout line");
+            assert!(read > 0, "sidecar closed stdout before responding");
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message = serde_json::from_str::<Value>(&line).expect("parse response json");
+            // Notifications carry no id; requests here always do.
+            if message.get("id") == req.get("id") {
+                responses.push(message);
+                break;
+            }
         }
     }
 
-    let output = child.wait_with_output().expect("wait sidecar");
-    assert!(output.status.success(), "sidecar exited non-zero");
-
-    let reader = BufReader::new(output.stdout.as_slice());
-    reader
-        .lines()
-        .map(|line| line.expect("stdout line"))
-        .filter(|line| !line.trim().is_empty())
-        .map(|line| serde_json::from_str::<Value>(&line).expect("parse response json"))
-        .collect()
+    drop(stdin);
+    let status = child.wait().expect("wait sidecar");
+    assert!(status.success(), "sidecar exited non-zero");
+    responses
 }
 
 fn make_temp_dir(name: &str) -> PathBuf {
@@ -96,3 +112,1533 @@ fn jrpc_fs_walk_text_batch_returns_expected_batch() {
     assert!(batch.len() >= 2);
     assert_eq!(result.get("done"), Some(&Value::Bool(true)));
 }
+
+#[test]
+fn jrpc_fs_walk_text_batch_honors_ignore_files_and_patterns() {
+    let dir = make_temp_dir("walk-ignore");
+    fs::create_dir_all(dir.join("node_modules/pkg")).expect("create node_modules");
+    fs::create_dir_all(dir.join("notes/drafts")).expect("create notes");
+    fs::write(dir.join("node_modules/pkg/readme.txt"), "dep").expect("write dep");
+    fs::write(dir.join("notes/keep.txt"), "keep").expect("write keep");
+    fs::write(dir.join("notes/scratch.log.txt"), "scratch").expect("write scratch");
+    fs::write(dir.join("notes/drafts/wip.txt"), "wip").expect("write wip");
+    fs::write(dir.join("notes/important.log.txt"), "important").expect("write important");
+    fs::write(dir.join(".gitignore"), "*.log.txt\n").expect("write gitignore");
+    fs::write(
+        dir.join("notes/.searchignore"),
+        "# local rules\ndrafts/\n!important.log.txt\n",
+    )
+    .expect("write searchignore");
+
+    let req = json!({
+      "jsonrpc":"2.0",
+      "id":3,
+      "method":"fs.walkTextBatch",
+      "params":{
+        "dir":dir.to_string_lossy().to_string(),
+        "textExts":[".txt"],
+        "ignorePatterns":["node_modules/"],
+        "cursor":0,
+        "batchSize":10
+      }
+    });
+
+    let responses = run_sidecar_requests(&[req], &[]);
+    let result = responses[0].get("result").expect("result object");
+    let mut names = result
+        .get("batch")
+        .and_then(Value::as_array)
+        .expect("batch array")
+        .iter()
+        .filter_map(|entry| entry.get(0).and_then(Value::as_str))
+        .map(|path| path.replace('\\', "/"))
+        .map(|path| path.rsplit('/').next().unwrap_or_default().to_string())
+        .collect::<Vec<String>>();
+    names.sort();
+
+    assert_eq!(names, vec!["important.log.txt", "keep.txt"]);
+}
+
+#[test]
+fn jrpc_watch_add_list_remove_round_trip() {
+    let dir = make_temp_dir("watch");
+    let dir_str = dir.to_string_lossy().to_string();
+    let missing = dir.join("missing").to_string_lossy().to_string();
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"watch.add","params":{"dir":dir_str}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"watch.add","params":{"dir":dir_str}}),
+        json!({"jsonrpc":"2.0","id":3,"method":"watch.list","params":{}}),
+        json!({"jsonrpc":"2.0","id":4,"method":"watch.remove","params":{"dir":dir_str}}),
+        json!({"jsonrpc":"2.0","id":5,"method":"watch.list","params":{}}),
+        json!({"jsonrpc":"2.0","id":6,"method":"watch.add","params":{"dir":missing}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[]);
+    let by_id = |id: u64| {
+        responses
+            .iter()
+            .find(|r| r.get("id") == Some(&json!(id)))
+            .expect("response for id")
+    };
+
+    assert_eq!(by_id(1)["result"]["already_watching"], json!(false));
+    assert_eq!(by_id(2)["result"]["already_watching"], json!(true));
+    let watching = by_id(3)["result"]["watching"]
+        .as_array()
+        .expect("watching array");
+    assert_eq!(watching.len(), 1);
+    assert_eq!(watching[0]["dir"], json!(dir_str));
+    assert_eq!(by_id(4)["result"]["removed"], json!(true));
+    assert_eq!(by_id(5)["result"]["watching"], json!([]));
+    assert_eq!(by_id(6)["error"]["code"], json!(-32602));
+}
+
+#[test]
+fn jrpc_watch_reconciles_created_and_deleted_files() {
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let dir = make_temp_dir("watch-events");
+    let dir_str = dir.to_string_lossy().to_string();
+    let data_dir = make_temp_dir("watch-events-data");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    // The fake Helix remembers created assets so the delete can find them.
+    let assets: Arc<Mutex<Vec<Value>>> = Arc::default();
+    let catalog = Arc::clone(&assets);
+    let (base_url, _) = spawn_fake_json_server(move |path, body| match path {
+        "/CreateAsset" => {
+            catalog.lock().unwrap().push(body.clone());
+            json!({})
+        }
+        "/ListAssets" => json!({ "assets": *catalog.lock().unwrap() }),
+        "/DeleteAssetByHash" => {
+            let hash = body["content_hash"].clone();
+            catalog
+                .lock()
+                .unwrap()
+                .retain(|asset| asset["content_hash"] != hash);
+            json!({})
+        }
+        "/v1/embeddings" => {
+            let inputs = body["input"].as_array().map_or(0, Vec::len);
+            let data: Vec<Value> = (0..inputs)
+                .map(|index| json!({"index": index, "embedding": [0.5, 0.5]}))
+                .collect();
+            json!({ "data": data })
+        }
+        _ => json!({}),
+    });
+    let (endpoint, port) = base_url.rsplit_once(':').expect("host and port");
+    let embed_url = format!("{}/v1", base_url);
+
+    let mut child = spawn_sidecar(&[
+        ("SIDECAR_DATA_DIR", &data_dir_str),
+        ("HELIX_ENDPOINT", endpoint),
+        ("HELIX_PORT", port),
+        ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+        ("OPENAI_EMBED_BASE_URL", &embed_url),
+    ]);
+    let mut stdin = child.stdin.take().expect("sidecar stdin");
+    let stdout = BufReader::new(child.stdout.take().expect("sidecar stdout"));
+    let (sender, messages) = mpsc::channel::<Value>();
+    std::thread::spawn(move || {
+        for line in stdout.lines().map_while(Result::ok) {
+            if let Ok(message) = serde_json::from_str::<Value>(&line) {
+                let _ = sender.send(message);
+            }
+        }
+    });
+    let mut send = |req: Value| {
+        stdin
+            .write_all(format!("{}\n", req).as_bytes())
+            .expect("write request");
+        stdin.flush().expect("flush request");
+    };
+    let wait_for = |method: &str| loop {
+        let message = messages
+            .recv_timeout(Duration::from_secs(15))
+            .unwrap_or_else(|_| panic!("timed out waiting for {}", method));
+        if message["method"] == json!(method) || message["id"] == json!(method) {
+            break message;
+        }
+    };
+
+    send(json!({
+        "jsonrpc":"2.0",
+        "id":"add",
+        "method":"watch.add",
+        "params":{"dir":dir_str,"debounce_ms":50}
+    }));
+    assert_eq!(wait_for("add")["result"]["already_watching"], json!(false));
+
+    let file = dir.join("notes.txt");
+    fs::write(&file, "watched notes").expect("write watched file");
+    let indexed = wait_for("watch.fileIndexed");
+    assert_eq!(indexed["params"]["kind"], json!("file"));
+    assert!(indexed["params"]["path"]
+        .as_str()
+        .is_some_and(|path| path.ends_with("/notes.txt")));
+    assert_eq!(assets.lock().unwrap().len(), 1);
+
+    fs::remove_file(&file).expect("delete watched file");
+    let removed = wait_for("watch.fileRemoved");
+    assert_eq!(removed["params"]["path"], indexed["params"]["path"]);
+    assert!(assets.lock().unwrap().is_empty());
+
+    send(json!({"jsonrpc":"2.0","id":"remove","method":"watch.remove","params":{"dir":dir_str}}));
+    assert_eq!(wait_for("remove")["result"]["removed"], json!(true));
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn jrpc_index_control_rejects_unknown_jobs() {
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"index.cancel","params":{"job_id":"missing"}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"index.pause","params":{"job_id":"missing"}}),
+        json!({"jsonrpc":"2.0","id":3,"method":"index.resume","params":{"job_id":"missing"}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[]);
+
+    assert_eq!(responses.len(), 3);
+    for response in &responses {
+        assert_eq!(response["error"]["code"], json!(-32004));
+    }
+}
+
+#[test]
+fn jrpc_index_jobs_survive_restart_as_interrupted() {
+    let data_dir = make_temp_dir("jobs");
+    let history = json!({
+        "jobs": [
+            {"job_id": "rust-text-100-1", "dir": "/tmp/a", "status": "completed", "started_at": "100"},
+            {"job_id": "rust-text-200-2", "dir": "/tmp/b", "status": "running", "started_at": "200"},
+            {"job_id": "rust-text-300-3", "dir": "/tmp/c", "status": "paused", "started_at": "300"}
+        ]
+    });
+    fs::write(data_dir.join("jobs.json"), history.to_string()).expect("write job history");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"index.list","params":{"limit":2}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"index.list","params":{"offset":2,"limit":2}}),
+        json!({"jsonrpc":"2.0","id":3,"method":"index.status","params":{"job_id":"rust-text-200-2"}}),
+        json!({"jsonrpc":"2.0","id":4,"method":"index.cancel","params":{"job_id":"rust-text-300-3"}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    let first_page = &responses[0]["result"];
+    assert_eq!(first_page["total"], json!(3));
+    assert_eq!(first_page["next_offset"], json!(2));
+    assert_eq!(first_page["jobs"][0]["job_id"], json!("rust-text-300-3"));
+    assert_eq!(first_page["jobs"][0]["status"], json!("interrupted"));
+    assert_eq!(
+        responses[1]["result"]["jobs"][0]["status"],
+        json!("completed")
+    );
+    assert_eq!(responses[1]["result"]["next_offset"], Value::Null);
+
+    let status = &responses[2]["result"];
+    assert_eq!(status["status"], json!("interrupted"));
+    assert_eq!(status["resumable"], json!(true));
+    assert_eq!(responses[3]["result"]["status"], json!("cancelled"));
+
+    let persisted: Value = serde_json::from_str(
+        &fs::read_to_string(data_dir.join("jobs.json")).expect("read job history"),
+    )
+    .expect("parse job history");
+    let cancelled = persisted["jobs"]
+        .as_array()
+        .expect("jobs array")
+        .iter()
+        .find(|job| job["job_id"] == json!("rust-text-300-3"))
+        .expect("cancelled job");
+    assert_eq!(cancelled["status"], json!("cancelled"));
+}
+
+#[test]
+fn jrpc_slow_search_does_not_block_other_requests() {
+    // Accepts connections but never answers, so the search stalls on embedding.
+    let stalled = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stalled backend");
+    let base_url = format!("http://{}", stalled.local_addr().expect("local addr"));
+
+    let mut child = spawn_sidecar(&[
+        ("VOYAGE_API_KEY", "test-key"),
+        ("VOYAGE_API_BASE_URL", &base_url),
+    ]);
+    let mut stdin = child.stdin.take().expect("sidecar stdin");
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"anything"}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"health.ping"}),
+    ];
+    for req in &requests {
+        let line = serde_json::to_string(req).expect("serialize request");
+        stdin.write_all(line.as_bytes()).expect("write request");
+        stdin.write_all(b"\n").expect("write newline");
+    }
+    stdin.flush().expect("flush requests");
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("sidecar stdout"));
+    let mut line = String::new();
+    stdout.read_line(&mut line).expect("stdout line");
+    let _ = child.kill();
+    let _ = child.wait();
+
+    let first = serde_json::from_str::<Value>(&line).expect("parse response json");
+    assert_eq!(first.get("id"), Some(&json!(2)));
+    assert_eq!(first["result"]["ok"], json!(true));
+}
+
+#[test]
+fn jrpc_index_start_with_notify_pushes_finished_notification() {
+    let dir = make_temp_dir("notify");
+    let data_dir = make_temp_dir("notify-data");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    // Without a Groq key the job fails right after starting, which is enough
+    // to see the notification stream end to end.
+    let mut child = spawn_sidecar(&[("SIDECAR_DATA_DIR", &data_dir_str), ("GROQ_API_KEY", "")]);
+    let mut stdin = child.stdin.take().expect("sidecar stdin");
+    let req = json!({
+        "jsonrpc":"2.0",
+        "id":1,
+        "method":"index.start",
+        "params":{"dir":dir.to_string_lossy().to_string(),"notify":true}
+    });
+    stdin
+        .write_all(format!("{}\n", req).as_bytes())
+        .expect("write request");
+    stdin.flush().expect("flush request");
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("sidecar stdout"));
+    let mut job_id = None;
+    let finished = loop {
+        let mut line = String::new();
+        let read = stdout.read_line(&mut line).expect("stdout line");
+        assert!(read > 0, "sidecar closed stdout before finishing");
+        let message = serde_json::from_str::<Value>(&line).expect("parse message");
+        if message.get("id") == Some(&json!(1)) {
+            job_id = message["result"]["job_id"]
+                .as_str()
+                .map(ToString::to_string);
+            continue;
+        }
+        assert!(message.get("id").is_none(), "notifications carry no id");
+        if message["method"] == json!("index.finished") {
+            break message;
+        }
+    };
+    drop(stdin);
+    let _ = child.wait();
+
+    assert_eq!(finished["params"]["status"], json!("failed"));
+    if let Some(job_id) = job_id {
+        assert_eq!(finished["params"]["job_id"], json!(job_id));
+    }
+}
+
+#[test]
+fn jrpc_index_errors_pages_through_the_issue_report() {
+    let data_dir = make_temp_dir("issues");
+    let history = json!({
+        "jobs": [{"job_id": "rust-text-1-1", "dir": "/tmp/a", "status": "completed_with_errors"}]
+    });
+    fs::write(data_dir.join("jobs.json"), history.to_string()).expect("write job history");
+    fs::create_dir_all(data_dir.join("job_issues")).expect("create issues dir");
+    let report = json!({
+        "issues": [
+            {"path": "/tmp/a/1.txt", "kind": "file", "phase": "index_text", "outcome": "failed", "category": "embedding", "message": "Voyage embeddings failed (500): boom"},
+            {"path": "/tmp/a/2.txt", "kind": "file", "phase": "index_text", "outcome": "skipped", "category": "binary", "message": "Binary content"},
+            {"path": "/tmp/a/3.mp4", "kind": "video", "phase": "index_video", "outcome": "failed", "category": "media", "message": "ffmpeg chunking failed: bad"}
+        ],
+        "dropped": 0
+    });
+    fs::write(
+        data_dir.join("job_issues").join("rust-text-1-1.json"),
+        report.to_string(),
+    )
+    .expect("write issue report");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"index.errors","params":{"job_id":"rust-text-1-1","limit":1}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"index.errors","params":{"job_id":"rust-text-1-1","outcome":"failed","offset":1}}),
+        json!({"jsonrpc":"2.0","id":3,"method":"index.errors","params":{"job_id":"missing"}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    let first = &responses[0]["result"];
+    assert_eq!(first["total"], json!(3));
+    assert_eq!(first["next_offset"], json!(1));
+    assert_eq!(first["errors"][0]["path"], json!("/tmp/a/1.txt"));
+
+    let failed = &responses[1]["result"];
+    assert_eq!(failed["total"], json!(2));
+    assert_eq!(failed["errors"][0]["category"], json!("media"));
+    assert_eq!(failed["next_offset"], Value::Null);
+
+    assert_eq!(responses[2]["error"]["code"], json!(-32004));
+}
+
+#[test]
+fn jrpc_search_query_rejects_out_of_range_min_score() {
+    let req = json!({
+      "jsonrpc":"2.0",
+      "id":1,
+      "method":"search.query",
+      "params":{"q":"anything","min_score":1.5}
+    });
+    let responses = run_sidecar_requests(&[req], &[]);
+
+    assert_eq!(responses[0]["error"]["code"], json!(-32602));
+}
+
+/// Writes a lexical index with a real `checker.rs` chunk, stored with its
+/// text, and a `notes.md` asset that only exists in the index. Returns the data dir and checker path.
+fn seed_lexical_index(name: &str) -> (PathBuf, String) {
+    let data_dir = make_temp_dir(name);
+    let source = data_dir.join("checker.rs");
+    let text = "fn check() {\n    // error E0308: mismatched types\n}\n";
+    fs::write(&source, text).expect("write source");
+    let source_str = source.to_string_lossy().replace('\\', "/");
+    let unit_key = format!("chunk_0:b0-{}:l1-3", text.len());
+    let index = json!({
+        "assets": {
+            "hash-checker": {
+                "kind": "file",
+                "path": source_str,
+                "modified_at": 1_700_000_000,
+                "units": [
+                    {"unit_kind": "path", "unit_key": "path", "terms": {"checker": 1, "rs": 1}},
+                    {"unit_kind": "file_chunk", "unit_key": unit_key, "terms": {"check": 1, "error": 1, "e0308": 1, "mismatched": 1, "types": 1}, "text": text}
+                ]
+            },
+            "hash-notes": {
+                "kind": "file",
+                "path": "/tmp/elsewhere/notes.md",
+                "modified_at": 1_800_000_000,
+                "units": [
+                    {"unit_kind": "path", "unit_key": "path", "terms": {"notes": 1, "md": 1}},
+                    {"unit_kind": "file_chunk", "unit_key": "chunk_0:b0-10:l1-1", "terms": {"error": 2, "types": 1}}
+                ]
+            }
+        }
+    });
+    fs::write(data_dir.join("lexical_index.json"), index.to_string()).expect("write index");
+    (data_dir, source_str)
+}
+
+#[test]
+fn jrpc_search_query_lexical_mode_ranks_exact_identifiers() {
+    let (data_dir, source_str) = seed_lexical_index("lexical");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"E0308","mode":"lexical"}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"search.query","params":{"q":"x","mode":"fuzzy"}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    let results = responses[0]["result"]["results"]
+        .as_array()
+        .expect("results array");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["path"], json!(source_str));
+    assert_eq!(results[0]["score"], json!(1.0));
+    assert_eq!(results[0]["lines"], json!({"start": 1, "end": 3}));
+    assert!(results[0]["snippet"]
+        .as_str()
+        .is_some_and(|snippet| snippet.contains("E0308")));
+    assert_eq!(results[0]["snippet_lines"], json!({"start": 1, "end": 3}));
+
+    // Highlights are UTF-16 offsets into the snippet.
+    let snippet: Vec<u16> = results[0]["snippet"]
+        .as_str()
+        .expect("snippet")
+        .encode_utf16()
+        .collect();
+    let highlights = results[0]["highlights"].as_array().expect("highlights");
+    assert_eq!(highlights.len(), 1);
+    let start = highlights[0]["start"].as_u64().expect("start") as usize;
+    let end = highlights[0]["end"].as_u64().expect("end") as usize;
+    assert_eq!(String::from_utf16_lossy(&snippet[start..end]), "E0308");
+
+    assert_eq!(responses[1]["error"]["code"], json!(-32602));
+}
+
+#[test]
+fn jrpc_search_query_highlights_land_on_multibyte_terms() {
+    let data_dir = make_temp_dir("snippet-utf8");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let text = "// Größe 🚀 ünïcödé prelude\nfn greet() { println!(\"naïve Café au lait\"); }\n";
+    let index = json!({
+        "assets": {
+            "hash-greet": {
+                "kind": "file",
+                "path": "/tmp/elsewhere/greet.rs",
+                "modified_at": 1_700_000_000,
+                "units": [
+                    {"unit_kind": "path", "unit_key": "path", "terms": {"greet": 1, "rs": 1}},
+                    {
+                        "unit_kind": "file_chunk",
+                        "unit_key": format!("chunk_0:b0-{}:l1-2", text.len()),
+                        "terms": {"größe": 1, "ünïcödé": 1, "prelude": 1, "fn": 1, "greet": 1, "println": 1, "naïve": 1, "café": 1, "au": 1, "lait": 1},
+                        "text": text
+                    }
+                ]
+            }
+        }
+    });
+    fs::write(data_dir.join("lexical_index.json"), index.to_string()).expect("write index");
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"café","mode":"lexical"}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"search.query","params":{"q":"größe naïve","mode":"lexical"}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    for (response, expected) in responses
+        .iter()
+        .zip([&["Café"][..], &["Größe", "naïve"][..]])
+    {
+        let results = response["result"]["results"]
+            .as_array()
+            .expect("results array");
+        assert_eq!(results.len(), 1, "{}", response);
+        let snippet = results[0]["snippet"].as_str().expect("snippet");
+        for term in expected {
+            assert!(snippet.contains(term), "{:?} lacks {}", snippet, term);
+        }
+
+        // Highlights are UTF-16 offsets, so the emoji and accents before a
+        // term must not shift them.
+        let units: Vec<u16> = snippet.encode_utf16().collect();
+        let highlighted: Vec<String> = results[0]["highlights"]
+            .as_array()
+            .expect("highlights")
+            .iter()
+            .map(|range| {
+                let start = range["start"].as_u64().expect("start") as usize;
+                let end = range["end"].as_u64().expect("end") as usize;
+                String::from_utf16(&units[start..end]).expect("whole code points")
+            })
+            .collect();
+        assert_eq!(highlighted, *expected);
+    }
+}
+
+#[test]
+fn jrpc_search_query_filters_apply_before_the_cut() {
+    let (data_dir, source_str) = seed_lexical_index("filters");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let search = |id: u64, filters: Value| json!({"jsonrpc":"2.0","id":id,"method":"search.query","params":{"q":"error types","mode":"lexical","filters":filters}});
+    let requests = [
+        search(1, json!({"extensions": [".RS"]})),
+        search(
+            2,
+            json!({"path_prefix": "/tmp/elsewhere/", "modified_after": "2026-01-01T00:00:00Z"}),
+        ),
+        search(3, json!({"kind": ["video"]})),
+        search(4, json!({"kind": ["audio"]})),
+        search(5, json!({"modified_after": "yesterday"})),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    let paths = |response: &Value| -> Vec<Value> {
+        response["result"]["results"]
+            .as_array()
+            .expect("results array")
+            .iter()
+            .map(|result| result["path"].clone())
+            .collect()
+    };
+    assert_eq!(paths(&responses[0]), vec![json!(source_str)]);
+    assert_eq!(paths(&responses[1]), vec![json!("/tmp/elsewhere/notes.md")]);
+    assert!(paths(&responses[2]).is_empty());
+    assert_eq!(responses[3]["error"]["code"], json!(-32602));
+    assert_eq!(responses[4]["error"]["code"], json!(-32602));
+}
+
+#[test]
+fn jrpc_search_query_prefilters_kinds_and_flags_truncation() {
+    let data_dir = make_temp_dir("prefilter");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    // The nearest embeddings are all text chunks; the one video only shows up
+    // when the kind is filtered inside Helix.
+    let (base_url, _) = spawn_fake_json_server(|path, body| match path {
+        "/v1/embeddings" => json!({"data": [{"embedding": [1.0, 0.0]}]}),
+        "/SearchAssetEmbeddingsTopK" => {
+            let k = body["k"].as_u64().unwrap_or(0);
+            json!({
+                "assets": (0..k).map(|i| json!({"content_hash": format!("f{}", i), "path": format!("/notes/{}.md", i), "kind": "file"})).collect::<Vec<_>>(),
+                "embeddings": (0..k).map(|_| json!({"unit_kind": "file_chunk", "unit_key": "chunk_0:b0-5:l1-1", "asset_kind": "file", "distance": 0.1})).collect::<Vec<_>>(),
+            })
+        }
+        "/SearchAssetEmbeddingsOfKindTopK" if body["asset_kind"] == json!("video") => json!({
+            "assets": [{"content_hash": "v", "path": "/clips/talk.mp4", "kind": "video"}],
+            "embeddings": [{"unit_kind": "video_transcript", "unit_key": "transcript", "distance": 0.4}],
+        }),
+        _ => json!({}),
+    });
+    let (endpoint, port) = base_url.rsplit_once(':').expect("host and port");
+    let embed_url = format!("{}/v1", base_url);
+
+    let search = |id: u64, filters: Value| json!({"jsonrpc":"2.0","id":id,"method":"search.query","params":{"q":"talk","mode":"semantic","filters":filters}});
+    let responses = run_sidecar_requests(
+        &[
+            search(1, json!({"kind": ["video"]})),
+            search(2, json!({"path_prefix": "/clips/"})),
+        ],
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+            ("OPENAI_EMBED_BASE_URL", &embed_url),
+            ("SIDECAR_SEARCH_MAX_CANDIDATES", "100"),
+            ("HELIX_ENDPOINT", endpoint),
+            ("HELIX_PORT", port),
+        ],
+    );
+
+    let by_kind = &responses[0]["result"];
+    assert_eq!(by_kind["results"][0]["path"], json!("/clips/talk.mp4"));
+    assert_eq!(by_kind["truncated"], json!(false));
+    // Path filters still run on what comes back, so a full cut says so.
+    let by_path = &responses[1]["result"];
+    assert_eq!(by_path["results"], json!([]));
+    assert_eq!(by_path["truncated"], json!(true));
+}
+
+#[test]
+fn jrpc_search_query_rerank_rescores_against_matched_text() {
+    let (data_dir, source_str) = seed_lexical_index("rerank");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{
+            "q": "mismatched error types",
+            "mode": "lexical",
+            "rerank": true
+        }}),
+    ];
+    let responses = run_sidecar_requests(
+        &requests,
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("SIDECAR_RERANKER", "lexical"),
+        ],
+    );
+
+    let result = &responses[0]["result"];
+    assert_eq!(result["reranker"], json!("lexical"));
+    let results = result["results"].as_array().expect("results array");
+    assert_eq!(results.len(), 2);
+    // notes.md's chunk carries no text, so only its path competes with the
+    // chunk text.
+    assert_eq!(results[0]["path"], json!(source_str));
+    // Saturated BM25, not relative to the best hit, so the top score is below 1.
+    let top = results[0]["score"].as_f64().expect("score");
+    assert!(top > 0.0 && top < 1.0, "{}", top);
+    assert!(results[0]["first_stage_score"].is_number());
+    assert_eq!(results[1]["score"], json!(0.0));
+}
+
+#[test]
+fn jrpc_search_query_parses_operators_phrases_and_negation() {
+    let (data_dir, source_str) = seed_lexical_index("query-language");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let search = |id: u64, q: &str| json!({"jsonrpc":"2.0","id":id,"method":"search.query","params":{"q":q,"mode":"lexical"}});
+    let requests = [
+        search(1, "ext:rs \"Mismatched  types\" error"),
+        search(2, "error types -E0308"),
+        search(3, "error kind:audio"),
+        search(4, "error \"types"),
+        search(5, "kind:file"),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    let first = &responses[0]["result"];
+    assert_eq!(first["parsed"]["text"], json!("Mismatched types error"));
+    assert_eq!(first["parsed"]["phrases"], json!(["Mismatched types"]));
+    assert_eq!(first["parsed"]["extensions"], json!(["rs"]));
+    assert_eq!(
+        first["parsed"]["operators"][0],
+        json!({"operator": "ext", "value": "rs", "start": 0, "end": 6})
+    );
+    let results = first["results"].as_array().expect("results array");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["path"], json!(source_str));
+
+    // checker.rs mentions E0308, so only notes.md is left.
+    let results = responses[1]["result"]["results"]
+        .as_array()
+        .expect("results array");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["path"], json!("/tmp/elsewhere/notes.md"));
+
+    assert_eq!(responses[2]["error"]["code"], json!(-32602));
+    assert_eq!(
+        responses[2]["error"]["data"]["position"],
+        json!({"start": 11, "end": 16})
+    );
+    assert_eq!(
+        responses[3]["error"]["data"]["position"],
+        json!({"start": 6, "end": 12})
+    );
+    assert_eq!(responses[4]["error"]["code"], json!(-32602));
+}
+
+#[test]
+fn jrpc_search_query_exclusions_check_every_unit_of_an_asset() {
+    let (data_dir, source_str) = seed_lexical_index("exclusion-units");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    // notes.md matches on its first chunk; a later one holds the excluded word.
+    let (helix_url, _) = spawn_fake_json_server(|path, body| match path {
+        "/GetAssetEmbeddingsByHash" if body["content_hash"] == json!("hash-notes") => json!({
+            "embeddings": [
+                {"unit_kind": "file_chunk", "unit_key": "chunk_0:b0-10:l1-1", "content": "error types"},
+                {"unit_kind": "file_chunk", "unit_key": "chunk_1:b10-40:l2-2", "content": "this api is deprecated"},
+            ]
+        }),
+        _ => json!({}),
+    });
+    let (endpoint, port) = helix_url.rsplit_once(':').expect("host and port");
+
+    let search = |id: u64, q: &str| json!({"jsonrpc":"2.0","id":id,"method":"search.query","params":{"q":q,"mode":"lexical"}});
+    let responses = run_sidecar_requests(
+        &[
+            search(1, "error types -deprecated"),
+            search(2, "error \"is deprecated\""),
+        ],
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("HELIX_ENDPOINT", endpoint),
+            ("HELIX_PORT", port),
+        ],
+    );
+
+    let paths = |response: &Value| -> Vec<Value> {
+        response["result"]["results"]
+            .as_array()
+            .expect("results array")
+            .iter()
+            .map(|result| result["path"].clone())
+            .collect()
+    };
+    assert_eq!(paths(&responses[0]), vec![json!(source_str)]);
+    assert_eq!(paths(&responses[1]), vec![json!("/tmp/elsewhere/notes.md")]);
+}
+
+#[test]
+fn jrpc_search_query_pages_with_a_cursor() {
+    let (data_dir, _) = seed_lexical_index("cursor");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let mut child = spawn_sidecar(&[("SIDECAR_DATA_DIR", &data_dir_str)]);
+    let mut stdin = child.stdin.take().expect("stdin");
+    let mut lines = BufReader::new(child.stdout.take().expect("stdout")).lines();
+    let mut call = |id: u64, params: Value| -> Value {
+        let request = json!({"jsonrpc":"2.0","id":id,"method":"search.query","params":params});
+        writeln!(stdin, "{}", request).expect("write request");
+        stdin.flush().expect("flush");
+        loop {
+            let line = lines.next().expect("response line").expect("read line");
+            let message: Value = serde_json::from_str(&line).expect("json");
+            if message["id"] == json!(id) {
+                return message;
+            }
+        }
+    };
+
+    let first = call(1, json!({"q":"error types","mode":"lexical","limit":1}));
+    let cursor = first["result"]["next_cursor"].clone();
+    assert!(cursor.is_string());
+    let second = call(
+        2,
+        json!({"q":"error types","mode":"lexical","limit":1,"cursor":cursor}),
+    );
+    assert_eq!(
+        second["result"]["results"].as_array().map(Vec::len),
+        Some(1)
+    );
+    assert_ne!(
+        second["result"]["results"][0]["path"],
+        first["result"]["results"][0]["path"]
+    );
+    assert_eq!(second["result"]["next_cursor"], Value::Null);
+
+    let replayed = call(3, json!({"q":"other","mode":"lexical","cursor":cursor}));
+    assert_eq!(replayed["error"]["code"], json!(-32602));
+
+    // A forged offset past the candidate cap is rejected, not overflowed.
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    let mut state: Value = serde_json::from_slice(
+        &URL_SAFE_NO_PAD
+            .decode(cursor.as_str().expect("cursor"))
+            .expect("cursor base64"),
+    )
+    .expect("cursor json");
+    state["offset"] = json!(usize::MAX);
+    let forged = URL_SAFE_NO_PAD.encode(state.to_string());
+    let forged = call(
+        4,
+        json!({"q":"error types","mode":"lexical","limit":1,"cursor":forged}),
+    );
+    assert_eq!(forged["error"]["code"], json!(-32602));
+
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn jrpc_search_query_video_hits_point_at_the_matching_moment() {
+    let data_dir = make_temp_dir("video-moment");
+    let thumbs_dir = data_dir.join("thumbnail_cache");
+    fs::create_dir_all(&thumbs_dir).expect("create thumbnail cache");
+    fs::write(thumbs_dir.join("hash-talk_chunk_2.jpg"), b"jpg").expect("write thumbnail");
+    let index = json!({
+        "assets": {
+            "hash-talk": {
+                "kind": "video",
+                "path": "/tmp/videos/talk.mp4",
+                "units": [
+                    {"unit_kind": "path", "unit_key": "path", "terms": {"talk": 1, "mp4": 1}},
+                    {"unit_kind": "video_transcript", "unit_key": "video_transcript_2:t61000-75500", "terms": {"borrow": 2, "checker": 1}}
+                ]
+            }
+        }
+    });
+    fs::write(data_dir.join("lexical_index.json"), index.to_string()).expect("write index");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let thumbs_dir_str = thumbs_dir.to_string_lossy().to_string();
+
+    let req = json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"borrow checker","mode":"lexical"}});
+    let responses = run_sidecar_requests(
+        &[req],
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("THUMBNAILS_DIR", &thumbs_dir_str),
+            ("HELIX_PORT", "9"),
+        ],
+    );
+
+    let hit = &responses[0]["result"]["results"][0];
+    assert_eq!(hit["path"], json!("/tmp/videos/talk.mp4"));
+    assert_eq!(hit["moment"]["chunk_index"], json!(2));
+    assert_eq!(hit["moment"]["start_secs"], json!(61.0));
+    assert_eq!(hit["moment"]["end_secs"], json!(75.5));
+    assert!(hit["thumbnail_url"]
+        .as_str()
+        .is_some_and(|url| url.ends_with("hash-talk_chunk_2.jpg")));
+}
+
+/// Minimal JSON-over-HTTP server. `respond` maps the request path and body
+/// to the response body; the counter tracks requests answered.
+fn spawn_fake_json_server<F>(respond: F) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>)
+where
+    F: Fn(&str, &Value) -> Value + Send + 'static,
+{
+    spawn_fake_http_server(move |path, body| (200, respond(path, body)))
+}
+
+/// Like `spawn_fake_json_server`, with the status code chosen per request.
+/// Error responses carry `Retry-After: 0`.
+fn spawn_fake_http_server<F>(respond: F) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>)
+where
+    F: Fn(&str, &Value) -> (u16, Value) + Send + 'static,
+{
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind fake server");
+    let addr = listener.local_addr().expect("local addr");
+    let served = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&served);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read headers, then as much body as Content-Length announces.
+            let (head, body) = loop {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break (String::from_utf8_lossy(&request).to_string(), String::new());
+                }
+                request.extend_from_slice(&buf[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length = text[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + length {
+                        let body = String::from_utf8_lossy(&request[header_end + 4..]).to_string();
+                        break (text[..header_end].to_string(), body);
+                    }
+                }
+            };
+            counter.fetch_add(1, Ordering::SeqCst);
+            let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let body = serde_json::from_str(&body).unwrap_or(Value::Null);
+            let (status, reply) = respond(&path, &body);
+            let reply = reply.to_string();
+            let extra = if status == 200 {
+                ""
+            } else {
+                "Retry-After: 0\r\n"
+            };
+            let response = format!(
+                "HTTP/1.1 {} Fake\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                extra,
+                reply.len(),
+                reply
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (format!("http://{}", addr), served)
+}
+
+#[test]
+fn jrpc_search_query_reuses_cached_query_vectors() {
+    use std::sync::atomic::Ordering;
+
+    let data_dir = make_temp_dir("query-cache");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let (base_url, served) =
+        spawn_fake_json_server(|_, _| json!({"data": [{"embedding": [0.1, 0.2, 0.3]}]}));
+    let envs = [
+        ("SIDECAR_DATA_DIR", data_dir_str.as_str()),
+        ("VOYAGE_API_KEY", "test-key"),
+        ("VOYAGE_API_BASE_URL", base_url.as_str()),
+        ("SIDECAR_QUERY_CACHE_PERSIST", "1"),
+        // Nothing listens here, so the vector search itself comes back empty.
+        ("HELIX_PORT", "9"),
+    ];
+
+    let responses = run_sidecar_requests(
+        &[
+            json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"Rust errors","mode":"semantic"}}),
+            json!({"jsonrpc":"2.0","id":2,"method":"search.query","params":{"q":"  rust   ERRORS ","mode":"semantic"}}),
+            json!({"jsonrpc":"2.0","id":3,"method":"search.cacheStats"}),
+        ],
+        &envs,
+    );
+    assert!(responses[0]["result"].is_object());
+    assert!(responses[1]["result"].is_object());
+    let stats = &responses[2]["result"];
+    assert_eq!(stats["hits"], json!(1));
+    assert_eq!(stats["misses"], json!(1));
+    assert_eq!(stats["entries"], json!(1));
+    assert_eq!(served.load(Ordering::SeqCst), 1);
+
+    // The persisted vector answers after a restart without another request.
+    let responses = run_sidecar_requests(
+        &[
+            json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"rust errors","mode":"semantic"}}),
+            json!({"jsonrpc":"2.0","id":2,"method":"search.cacheStats"}),
+        ],
+        &envs,
+    );
+    assert_eq!(responses[1]["result"]["hits"], json!(1));
+    assert_eq!(responses[1]["result"]["misses"], json!(0));
+    assert_eq!(served.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn jrpc_search_query_embeds_with_openai_compatible_provider() {
+    use std::sync::{Arc, Mutex};
+
+    let data_dir = make_temp_dir("openai-embed");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let seen: Arc<Mutex<Vec<(String, Value)>>> = Arc::default();
+    let recorder = Arc::clone(&seen);
+    let (base_url, _) = spawn_fake_json_server(move |path, body| {
+        recorder
+            .lock()
+            .unwrap()
+            .push((path.to_string(), body.clone()));
+        json!({"data": [{"embedding": [0.1, 0.2]}]})
+    });
+    let base_url = format!("{}/v1", base_url);
+
+    let responses = run_sidecar_requests(
+        &[
+            json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"rust errors","mode":"semantic"}}),
+            json!({"jsonrpc":"2.0","id":2,"method":"search.query","params":{"q":"borrow checker","mode":"semantic"}}),
+        ],
+        &[
+            ("SIDECAR_DATA_DIR", data_dir_str.as_str()),
+            ("SIDECAR_EMBEDDING_PROVIDER", "ollama"),
+            ("OPENAI_EMBED_BASE_URL", base_url.as_str()),
+            ("OPENAI_EMBED_MODEL", "nomic-embed-text"),
+            ("OPENAI_EMBED_QUERY_PREFIX", "search_query: "),
+            ("OPENAI_API_KEY", ""),
+            ("VOYAGE_API_KEY", ""),
+            ("HELIX_PORT", "9"),
+        ],
+    );
+    assert!(responses[0]["result"].is_object());
+
+    {
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].0, "/v1/embeddings");
+        assert_eq!(seen[0].1["model"], json!("nomic-embed-text"));
+        assert_eq!(seen[0].1["input"], json!(["search_query: rust errors"]));
+        assert!(seen[0].1.get("dimensions").is_none());
+    }
+
+    // A configured dimension is sent and the reply is checked against it.
+
+    let responses = run_sidecar_requests(
+        &[
+            json!({"jsonrpc":"2.0","id":1,"method":"search.query","params":{"q":"lifetimes","mode":"semantic"}}),
+        ],
+        &[
+            ("SIDECAR_DATA_DIR", data_dir_str.as_str()),
+            ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+            ("OPENAI_EMBED_BASE_URL", base_url.as_str()),
+            ("OPENAI_EMBED_DIMENSIONS", "3"),
+            ("HELIX_PORT", "9"),
+        ],
+    );
+    let reason = responses[0]["error"]["data"]["reason"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(reason.contains("expected 3"), "{}", reason);
+    assert_eq!(seen.lock().unwrap()[2].1["dimensions"], json!(3));
+}
+
+#[test]
+fn jrpc_provider_calls_retry_then_open_the_circuit() {
+    use std::sync::atomic::Ordering;
+
+    let data_dir = make_temp_dir("http-policy");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    // Fails twice, then answers; the sidecar sees one slow success.
+    let (flaky_url, flaky_served) = {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        spawn_fake_http_server(move |_, _| match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => (429, json!({"error": "rate limited"})),
+            1 => (503, json!({"error": "overloaded"})),
+            _ => (200, json!({"data": [{"embedding": [0.1, 0.2]}]})),
+        })
+    };
+    let (down_url, down_served) = spawn_fake_http_server(|_, _| (503, json!({"error": "down"})));
+    let search = |id: u64, q: &str| json!({"jsonrpc":"2.0","id":id,"method":"search.query","params":{"q":q,"mode":"semantic"}});
+    let envs = |base_url: &str| {
+        vec![
+            ("SIDECAR_DATA_DIR", data_dir_str.clone()),
+            ("SIDECAR_EMBEDDING_PROVIDER", "openai".to_string()),
+            ("OPENAI_EMBED_BASE_URL", base_url.to_string()),
+            ("SIDECAR_HTTP_BACKOFF_MS", "1".to_string()),
+            ("SIDECAR_HTTP_MAX_RETRIES", "2".to_string()),
+            ("SIDECAR_HTTP_BREAKER_THRESHOLD", "3".to_string()),
+            ("HELIX_PORT", "9".to_string()),
+        ]
+    };
+
+    let flaky_envs = envs(&flaky_url);
+    let flaky_envs: Vec<(&str, &str)> = flaky_envs.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    let responses = run_sidecar_requests(&[search(1, "retry me")], &flaky_envs);
+    assert!(responses[0]["result"].is_object(), "{}", responses[0]);
+    assert_eq!(flaky_served.load(Ordering::SeqCst), 3);
+
+    // Three failed attempts open the circuit; the next search fails fast.
+    let down_envs = envs(&down_url);
+    let down_envs: Vec<(&str, &str)> = down_envs.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    let responses = run_sidecar_requests(&[search(1, "first"), search(2, "second")], &down_envs);
+    assert_eq!(responses[0]["error"]["code"], json!(-32603));
+    assert_eq!(down_served.load(Ordering::SeqCst), 3);
+    let reason = responses[1]["error"]["data"]["reason"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(reason.contains("unavailable"), "{}", reason);
+    assert_eq!(down_served.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn jrpc_search_similar_aggregates_neighbours_and_skips_the_seed() {
+    let data_dir = make_temp_dir("similar");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let asset = |hash: &str| json!({"content_hash": hash, "path": format!("/notes/{}.md", hash), "kind": "file"});
+    let unit = |distance: f64| json!({"unit_kind": "file_chunk", "unit_key": "chunk_0:b0-5:l1-1", "content": "hello", "distance": distance});
+    let (helix_url, _) = spawn_fake_json_server(move |path, body| match path {
+        "/GetAssetsByPath" => json!({"assets": [asset("seed")]}),
+        "/GetAssetEmbeddingsByHash" => json!({"embeddings": [
+            {"unit_kind": "file_path", "unit_key": "path", "data": [9.0, 9.0]},
+            {"unit_kind": "file_chunk", "unit_key": "chunk_0:b0-5:l1-1", "content": "hello", "data": [1.0, 0.0]},
+            {"unit_kind": "file_chunk", "unit_key": "chunk_1:b5-9:l2-2", "content": "world", "data": [0.0, 1.0]},
+        ]}),
+        "/SearchAssetEmbeddingsTopK" if body["vector"] == json!([1.0, 0.0]) => json!({
+            "assets": [asset("seed"), asset("alpha"), asset("beta")],
+            "embeddings": [unit(0.0), unit(0.3), unit(0.5)],
+        }),
+        "/SearchAssetEmbeddingsTopK" if body["vector"] == json!([0.0, 1.0]) => json!({
+            "assets": [asset("seed"), asset("beta")],
+            "embeddings": [unit(0.0), unit(0.1)],
+        }),
+        _ => json!({}),
+    });
+    let (endpoint, port) = helix_url.rsplit_once(':').expect("host and port");
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"search.similar","params":{"path":"/notes/seed.md"}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"search.similar","params":{"path":"/a","content_hash":"b"}}),
+    ];
+    let responses = run_sidecar_requests(
+        &requests,
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("HELIX_ENDPOINT", endpoint),
+            ("HELIX_PORT", port),
+        ],
+    );
+
+    let result = &responses[0]["result"];
+    assert_eq!(result["seed"]["content_hash"], json!("seed"));
+    let paths: Vec<&Value> = result["results"]
+        .as_array()
+        .expect("results array")
+        .iter()
+        .map(|hit| &hit["path"])
+        .collect();
+    assert_eq!(paths, [&json!("/notes/beta.md"), &json!("/notes/alpha.md")]);
+    assert_eq!(result["results"][0]["distance"], json!(0.1));
+
+    assert_eq!(responses[1]["error"]["code"], json!(-32602));
+}
+
+#[test]
+fn jrpc_search_by_image_validates_the_image_input() {
+    let data_dir = make_temp_dir("by-image");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let missing = data_dir.join("missing.png").to_string_lossy().to_string();
+
+    let call = |id: u64, params: Value| json!({"jsonrpc":"2.0","id":id,"method":"search.byImage","params":params});
+    let requests = [
+        call(1, json!({})),
+        call(2, json!({"base64": "bm90IGFuIGltYWdl"})),
+        call(3, json!({"base64": "***"})),
+        call(4, json!({"path": missing})),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    assert_eq!(responses[0]["error"]["code"], json!(-32602));
+    assert_eq!(
+        responses[1]["error"]["data"]["reason"],
+        json!("bytes are not a supported image format")
+    );
+    assert_eq!(responses[2]["error"]["code"], json!(-32602));
+    assert_eq!(responses[3]["error"]["code"], json!(-32004));
+}
+
+/// Runs `index.start` on `dir` and returns the `index.finished` notification.
+fn index_until_finished(dir: &Path, envs: &[(&str, &str)]) -> Value {
+    let mut child = spawn_sidecar(envs);
+    let mut stdin = child.stdin.take().expect("sidecar stdin");
+    let req = json!({
+        "jsonrpc":"2.0",
+        "id":1,
+        "method":"index.start",
+        "params":{"dir":dir.to_string_lossy().to_string(),"notify":true}
+    });
+    stdin
+        .write_all(format!("{}\n", req).as_bytes())
+        .expect("write request");
+    stdin.flush().expect("flush request");
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("sidecar stdout"));
+    let finished = loop {
+        let mut line = String::new();
+        let read = stdout.read_line(&mut line).expect("stdout line");
+        assert!(read > 0, "sidecar closed stdout before finishing");
+        let message = serde_json::from_str::<Value>(&line).expect("parse message");
+        if message["method"] == json!("index.finished") {
+            break message;
+        }
+    };
+    drop(stdin);
+    let _ = child.wait();
+    finished
+}
+
+#[test]
+fn jrpc_index_start_batches_embedding_requests() {
+    use std::sync::{Arc, Mutex};
+
+    let dir = make_temp_dir("batch-index");
+    for name in ["alpha", "beta", "gamma"] {
+        fs::write(
+            dir.join(format!("{}.txt", name)),
+            format!("notes about {}", name),
+        )
+        .expect("write text file");
+    }
+    let data_dir = make_temp_dir("batch-index-data");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    // One fake serves both Helix queries and the embeddings endpoint.
+    let batches: Arc<Mutex<Vec<usize>>> = Arc::default();
+    let recorder = Arc::clone(&batches);
+    let (base_url, _) = spawn_fake_json_server(move |path, body| {
+        if path != "/v1/embeddings" {
+            return json!({});
+        }
+        let inputs = body["input"].as_array().map_or(0, Vec::len);
+        recorder.lock().unwrap().push(inputs);
+        let data: Vec<Value> = (0..inputs)
+            .map(|index| json!({"index": index, "embedding": [0.5, 0.5]}))
+            .collect();
+        json!({ "data": data })
+    });
+    let (endpoint, port) = base_url.rsplit_once(':').expect("host and port");
+    let embed_url = format!("{}/v1", base_url);
+
+    let finished = index_until_finished(
+        &dir,
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("GROQ_API_KEY", "test-key"),
+            ("HELIX_ENDPOINT", endpoint),
+            ("HELIX_PORT", port),
+            ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+            ("OPENAI_EMBED_BASE_URL", &embed_url),
+        ],
+    );
+
+    assert_eq!(
+        finished["params"]["status"],
+        json!("completed"),
+        "{}",
+        finished
+    );
+    // Three chunks and three path units share a single request.
+    assert_eq!(*batches.lock().unwrap(), vec![6]);
+}
+
+/// A sidecar kept open across requests. Its output is read on a thread so
+/// responses and notifications can be awaited in any order.
+struct Session {
+    child: Child,
+    stdin: std::process::ChildStdin,
+    messages: std::sync::mpsc::Receiver<Value>,
+}
+
+impl Session {
+    fn start(envs: &[(&str, &str)]) -> Self {
+        let mut child = spawn_sidecar(envs);
+        let stdin = child.stdin.take().expect("sidecar stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("sidecar stdout"));
+        let (sender, messages) = std::sync::mpsc::channel::<Value>();
+        std::thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                if let Ok(message) = serde_json::from_str::<Value>(&line) {
+                    let _ = sender.send(message);
+                }
+            }
+        });
+        Self {
+            child,
+            stdin,
+            messages,
+        }
+    }
+
+    /// Sends `method` with `params` and waits for its response.
+    fn call(&mut self, id: &str, method: &str, params: Value) -> Value {
+        let req = json!({"jsonrpc":"2.0","id":id,"method":method,"params":params});
+        self.stdin
+            .write_all(format!("{}\n", req).as_bytes())
+            .expect("write request");
+        self.stdin.flush().expect("flush request");
+        self.wait_for(id)
+    }
+
+    /// Waits for the response with id `key` or the next `key` notification.
+    fn wait_for(&self, key: &str) -> Value {
+        loop {
+            let message = self
+                .messages
+                .recv_timeout(std::time::Duration::from_secs(30))
+                .unwrap_or_else(|_| panic!("timed out waiting for {}", key));
+            if message["method"] == json!(key) || message["id"] == json!(key) {
+                return message;
+            }
+        }
+    }
+
+    fn finish(mut self) {
+        drop(self.stdin);
+        let _ = self.child.wait();
+    }
+}
+
+/// Starts a notifying index job over more text files than fit in one indexing
+/// group, so control requests sent after the first group land mid-job.
+fn start_index_job(name: &str) -> (Session, String, usize) {
+    const FILES: usize = 40;
+
+    let dir = make_temp_dir(name);
+    for index in 0..FILES {
+        fs::write(
+            dir.join(format!("note-{}.txt", index)),
+            format!("notes number {}", index),
+        )
+        .expect("write text file");
+    }
+    let data_dir = make_temp_dir(&format!("{}-data", name));
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+    let (base_url, _) = spawn_fake_json_server(|path, body| {
+        if path != "/v1/embeddings" {
+            return json!({});
+        }
+        let inputs = body["input"].as_array().map_or(0, Vec::len);
+        let data: Vec<Value> = (0..inputs)
+            .map(|index| json!({"index": index, "embedding": [0.5, 0.5]}))
+            .collect();
+        json!({ "data": data })
+    });
+    let (endpoint, port) = base_url.rsplit_once(':').expect("host and port");
+    let embed_url = format!("{}/v1", base_url);
+
+    let mut session = Session::start(&[
+        ("SIDECAR_DATA_DIR", &data_dir_str),
+        ("GROQ_API_KEY", "test-key"),
+        ("HELIX_ENDPOINT", endpoint),
+        ("HELIX_PORT", port),
+        ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+        ("OPENAI_EMBED_BASE_URL", &embed_url),
+    ]);
+    let started = session.call(
+        "start",
+        "index.start",
+        json!({"dir": dir.to_string_lossy(), "notify": true}),
+    );
+    let job_id = started["result"]["job_id"]
+        .as_str()
+        .expect("job id")
+        .to_string();
+    (session, job_id, FILES)
+}
+
+#[test]
+fn jrpc_index_cancel_stops_a_running_job() {
+    let (mut session, job_id, files) = start_index_job("cancel-running");
+    session.wait_for("index.fileIndexed");
+
+    let cancelled = session.call("cancel", "index.cancel", json!({"job_id": job_id}));
+    assert_eq!(cancelled["result"]["success"], json!(true), "{}", cancelled);
+
+    let finished = session.wait_for("index.finished");
+    assert_eq!(
+        finished["params"]["status"],
+        json!("cancelled"),
+        "{}",
+        finished
+    );
+    let status = session.call("status", "index.status", json!({"job_id": job_id}));
+    assert_eq!(status["result"]["status"], json!("cancelled"));
+    assert!(
+        status["result"]["text_indexed"]
+            .as_u64()
+            .expect("text_indexed")
+            < files as u64
+    );
+    session.finish();
+}
+
+#[test]
+fn jrpc_index_pause_then_resume_keeps_progress_and_finishes() {
+    use std::time::Duration;
+
+    let (mut session, job_id, files) = start_index_job("pause-resume");
+    session.wait_for("index.fileIndexed");
+
+    let paused = session.call("pause", "index.pause", json!({"job_id": job_id}));
+    assert_eq!(paused["result"]["status"], json!("paused"), "{}", paused);
+
+    // A group already in flight may still land; after that nothing moves.
+    std::thread::sleep(Duration::from_millis(800));
+    let before = session.call("before", "index.status", json!({"job_id": job_id}));
+    std::thread::sleep(Duration::from_millis(800));
+    let after = session.call("after", "index.status", json!({"job_id": job_id}));
+    assert_eq!(after["result"]["status"], json!("paused"));
+    let progress = before["result"]["text_indexed"]
+        .as_u64()
+        .expect("text_indexed");
+    assert!(progress > 0 && progress < files as u64, "{}", before);
+    assert_eq!(after["result"]["text_indexed"], json!(progress));
+
+    let resumed = session.call("resume", "index.resume", json!({"job_id": job_id}));
+    assert_eq!(resumed["result"]["status"], json!("running"), "{}", resumed);
+
+    let finished = session.wait_for("index.finished");
+    assert_eq!(
+        finished["params"]["status"],
+        json!("completed"),
+        "{}",
+        finished
+    );
+    assert_eq!(finished["params"]["text_indexed"], json!(files));
+    assert_eq!(finished["params"]["text_errors"], json!(0));
+    session.finish();
+}
+
+#[test]
+fn jrpc_index_pause_rejects_a_finished_job() {
+    let data_dir = make_temp_dir("pause-finished");
+    let history = json!({
+        "jobs": [
+            {"job_id": "rust-text-100-1", "dir": "/tmp/a", "status": "completed", "started_at": "100"}
+        ]
+    });
+    fs::write(data_dir.join("jobs.json"), history.to_string()).expect("write job history");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let requests = [
+        json!({"jsonrpc":"2.0","id":1,"method":"index.pause","params":{"job_id":"rust-text-100-1"}}),
+        json!({"jsonrpc":"2.0","id":2,"method":"index.status","params":{"job_id":"rust-text-100-1"}}),
+    ];
+    let responses = run_sidecar_requests(&requests, &[("SIDECAR_DATA_DIR", &data_dir_str)]);
+
+    assert_eq!(responses[0]["error"]["code"], json!(-32603));
+    assert_eq!(responses[0]["error"]["data"]["status"], json!("completed"));
+    assert_eq!(responses[1]["result"]["status"], json!("completed"));
+}
+
+#[test]
+fn jrpc_index_start_indexes_only_allowed_text_files() {
+    use std::sync::{Arc, Mutex};
+
+    let dir = make_temp_dir("text-filter");
+    fs::write(dir.join(".env"), "SECRET=value").expect("write env file");
+    fs::write(dir.join("notes.md"), "markdown is not a text extension").expect("write md");
+    fs::write(dir.join("blob.txt"), [b'a', 0, 1, 2, b'b']).expect("write binary file");
+    fs::write(dir.join("big.txt"), "x".repeat(4_096)).expect("write large file");
+    fs::write(dir.join("valid.txt"), "plain notes").expect("write valid file");
+    let data_dir = make_temp_dir("text-filter-data");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let created: Arc<Mutex<Vec<String>>> = Arc::default();
+    let recorder = Arc::clone(&created);
+    let (base_url, _) = spawn_fake_json_server(move |path, body| {
+        if path == "/CreateAsset" {
+            let asset = body["path"].as_str().unwrap_or_default();
+            let name = asset.rsplit('/').next().unwrap_or_default();
+            recorder.lock().unwrap().push(name.to_string());
+        }
+        if path != "/v1/embeddings" {
+            return json!({});
+        }
+        let inputs = body["input"].as_array().map_or(0, Vec::len);
+        let data: Vec<Value> = (0..inputs)
+            .map(|index| json!({"index": index, "embedding": [0.5, 0.5]}))
+            .collect();
+        json!({ "data": data })
+    });
+    let (endpoint, port) = base_url.rsplit_once(':').expect("host and port");
+    let embed_url = format!("{}/v1", base_url);
+
+    let finished = index_until_finished(
+        &dir,
+        &[
+            ("SIDECAR_DATA_DIR", &data_dir_str),
+            ("GROQ_API_KEY", "test-key"),
+            ("HELIX_ENDPOINT", endpoint),
+            ("HELIX_PORT", port),
+            ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+            ("OPENAI_EMBED_BASE_URL", &embed_url),
+            ("SIDECAR_TEXT_MAX_FILE_BYTES", "1024"),
+        ],
+    );
+
+    let params = &finished["params"];
+    assert_eq!(params["status"], json!("completed"), "{}", finished);
+    // `.env` and `.md` never reach the text pass; the blob and the oversized
+    // file are found but skipped.
+    assert_eq!(params["text_found"], json!(3), "{}", finished);
+    assert_eq!(params["text_indexed"], json!(1));
+    assert_eq!(params["text_skipped"], json!(2));
+    assert_eq!(*created.lock().unwrap(), vec!["valid.txt"]);
+}
+
+#[test]
+fn jrpc_model_cache_skips_repeat_embeddings_and_purges() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let dir = make_temp_dir("model-cache");
+    fs::write(dir.join("notes.txt"), "notes about caching").expect("write text file");
+    let data_dir = make_temp_dir("model-cache-data");
+    let data_dir_str = data_dir.to_string_lossy().to_string();
+
+    let embed_requests = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&embed_requests);
+    let (base_url, _) = spawn_fake_json_server(move |path, body| {
+        if path != "/v1/embeddings" {
+            return json!({});
+        }
+        counter.fetch_add(1, Ordering::SeqCst);
+        let inputs = body["input"].as_array().map_or(0, Vec::len);
+        let data: Vec<Value> = (0..inputs)
+            .map(|index| json!({"index": index, "embedding": [0.5, 0.5]}))
+            .collect();
+        json!({ "data": data })
+    });
+    let (endpoint, port) = base_url.rsplit_once(':').expect("host and port");
+    let embed_url = format!("{}/v1", base_url);
+    let envs = [
+        ("SIDECAR_DATA_DIR", data_dir_str.as_str()),
+        ("GROQ_API_KEY", "test-key"),
+        ("HELIX_ENDPOINT", endpoint),
+        ("HELIX_PORT", port),
+        ("SIDECAR_EMBEDDING_PROVIDER", "openai"),
+        ("OPENAI_EMBED_BASE_URL", embed_url.as_str()),
+    ];
+
+    // The fake Helix forgets everything, like a cleared index, so the second
+    // run re-embeds the same units and should be served from the cache.
+    let first = index_until_finished(&dir, &envs);
+    assert_eq!(first["params"]["status"], json!("completed"), "{}", first);
+    assert_eq!(embed_requests.load(Ordering::SeqCst), 1);
+    let second = index_until_finished(&dir, &envs);
+    assert_eq!(second["params"]["status"], json!("completed"), "{}", second);
+    assert_eq!(embed_requests.load(Ordering::SeqCst), 1);
+
+    let responses = run_sidecar_requests(
+        &[
+            json!({"jsonrpc":"2.0","id":1,"method":"modelCache.stats"}),
+            json!({"jsonrpc":"2.0","id":2,"method":"modelCache.purge","params":{"kind":"frames"}}),
+            json!({"jsonrpc":"2.0","id":3,"method":"modelCache.purge","params":{"kind":"embedding"}}),
+            json!({"jsonrpc":"2.0","id":4,"method":"modelCache.stats"}),
+        ],
+        &envs,
+    );
+
+    assert_eq!(
+        responses[0]["result"]["entries"],
+        json!(2),
+        "{}",
+        responses[0]
+    );
+    assert_eq!(
+        responses[0]["result"]["kinds"]["embedding"]["entries"],
+        json!(2)
+    );
+    assert_eq!(responses[1]["error"]["code"], json!(-32602));
+    assert_eq!(responses[2]["result"]["removed"], json!(2));
+    assert_eq!(responses[3]["result"]["entries"], json!(0));
+}
@@ -0,0 +1,66 @@
+use the_search_thing::sidecar::rpc::indexing::text::chunk::{chunk_text, ChunkConfig, ChunkSpan};
+
+#[test]
+fn chunks_cover_content_with_line_ranges_and_round_trip_keys() {
+    let mut content = String::new();
+    for section in 0..6 {
+        content.push_str(&format!("# Section {}\n\n", section));
+        for line in 0..8 {
+            content.push_str(&format!(
+                "section {} line {} has some words to fill the window\n",
+                section, line
+            ));
+        }
+        content.push('\n');
+    }
+
+    let config = ChunkConfig {
+        max_chars: 400,
+        overlap_chars: 60,
+    };
+    let chunks = chunk_text(&content, &config);
+    assert!(chunks.len() > 1);
+
+    assert_eq!(chunks[0].byte_start, 0);
+    assert_eq!(chunks[0].line_start, 1);
+    assert_eq!(chunks.last().expect("last chunk").byte_end, content.len());
+
+    for (idx, chunk) in chunks.iter().enumerate() {
+        assert_eq!(chunk.index, idx);
+        assert!(chunk.text.chars().count() <= config.max_chars);
+        assert_eq!(&content[chunk.byte_start..chunk.byte_end], chunk.text);
+        assert_eq!(
+            ChunkSpan::parse_unit_key(&chunk.unit_key()),
+            Some(chunk.span())
+        );
+    }
+
+    for pair in chunks.windows(2) {
+        assert!(pair[1].byte_start > pair[0].byte_start);
+        assert!(pair[1].byte_start <= pair[0].byte_end);
+    }
+}
+
+#[test]
+fn long_single_line_is_split_on_char_boundaries() {
+    let content = "é".repeat(1_000);
+    let config = ChunkConfig {
+        max_chars: 301,
+        overlap_chars: 0,
+    };
+    let chunks = chunk_text(&content, &config);
+
+    // The budget counts chars, not the two bytes each "é" takes.
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(chunks[0].text.chars().count(), 301);
+    assert!(chunks
+        .iter()
+        .all(|chunk| chunk.line_start == 1 && chunk.line_end == 1));
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|chunk| chunk.text.as_str())
+            .collect::<String>(),
+        content
+    );
+}